@@ -125,6 +125,7 @@ pub mod response;
 mod utils;
 
 pub use error::Error;
+pub use method::PositionHow;
 pub use options::TaskOptions;
 // Re-export `Map` for `TaskOptions`.
 pub use callback::Callbacks;
@@ -1,14 +1,156 @@
-from typing import Literal
+from typing import Literal, TypedDict
 
-def startAria(port: int, aria2_path: str | None=None) -> str | None: ...
+# aria2 options for download requests. keys are aria2 option names.
+DownloadOptions = TypedDict('DownloadOptions', {
+    'all-proxy': str,
+    'all-proxy-user': str,
+    'all-proxy-passwd': str,
+    'http-user': str,
+    'http-passwd': str,
+    'header': list[str],
+    'referer': str,
+    'user-agent': str,
+    'load-cookies': str,
+    'split': int | str,
+    'min-split-size': str,
+    'max-connection-per-server': int | str,
+    'max-download-limit': str,
+    'lowest-speed-limit': str,
+    'max-tries': int | str,
+    'retry-wait': int | str,
+    'timeout': int | str,
+    'continue': bool | str,
+    'check-certificate': bool | str,
+    'dir': str,
+    'out': str,
+    'gid': str,
+}, total=False)
+
+# global statistics of aria2. speeds are in bytes/s, rates are human readable.
+class GlobalStat(TypedDict):
+    download_speed: int
+    upload_speed: int
+    download_rate: str
+    upload_rate: str
+    num_active: int
+    num_waiting: int
+    num_stopped: int
+    num_stopped_total: int
+
+# download event of aria2 or scheduler.
+# gid is None for connected, disconnected, window and queue events.
+# category is only set for window and queue events.
+# synthetic events are made after reconnecting for events that were missed.
+class DownloadEvent(TypedDict):
+    gid: str | None
+    category: str | None
+    event: Literal['start', 'pause', 'stop', 'complete', 'error', 'bt_complete', 'connected', 'disconnected',
+                   'scheduled_start', 'scheduled_end', 'window_open', 'window_close',
+                   'queue_complete', 'queue_stopped', 'retry']
+    synthetic: bool
+
+class SpiderResult(TypedDict):
+    link: str
+    status: int
+    file_name: str | None
+    server_file_name: str | None
+    file_size: str | None
+    size: int | None
+    resumable: bool
+    content_type: str | None
+
+class LinkEntry(TypedDict):
+    link: str
+    out: str | None
+
+class SpiderItem(TypedDict):
+    index: int
+    link: str
+    duplicate: bool
+    result: SpiderResult | None
+    error: str | None
+
+# filters, sort order and page of DataBase.searchDownloads. every key is optional.
+# dates are unix time or '2017/09/09 , 13:12:26', sizes are bytes or like '1.5 GiB'.
+# text is a FTS5 query on file name and link. newest 100 downloads are returned by default.
+class DownloadQuery(TypedDict, total=False):
+    name: str
+    text: str
+    statuses: list[str]
+    category: str
+    host: str
+    first_try_from: int | str
+    first_try_to: int | str
+    last_try_from: int | str
+    last_try_to: int | str
+    min_size: int | str
+    max_size: int | str
+    sort: Literal['file_name', 'status', 'size', 'downloaded_size', 'rate',
+                  'first_try_date', 'last_try_date', 'category']
+    descending: bool
+    offset: int
+    limit: int | None
+
+# sizes are in bytes, rate in bytes/s, percent from 0 to 100 and dates in unix time.
+class DownloadRow(TypedDict):
+    gid: str
+    file_name: str | None
+    status: str | None
+    size: int | None
+    downloaded_size: int | None
+    percent: int | None
+    rate: int | None
+    link: str | None
+    first_try_date: int | None
+    last_try_date: int | None
+    category: str | None
+
+# every function of ghermez raises one of these exceptions when it fails.
+class GhermezError(Exception): ...
+class Aria2Unavailable(GhermezError): ...
+class Aria2RpcError(GhermezError):
+    # aria2 error code.
+    code: int
+class Aria2AnswerError(GhermezError): ...
+class DatabaseError(GhermezError): ...
+class IoError(GhermezError): ...
+class HttpError(GhermezError): ...
+
+def startAria(port: int, aria2_path: str | None=None, rpc_secret: str | None=None) -> str: ...
+def rpcSecret() -> str | None: ...
 def aria2Version() -> str: ...
-def tellActive() -> (list[str] | None, list[dict[str, str]] | None): ...
+def connect() -> None: ...
+def disconnect() -> None: ...
+def addDownload(uris: list[str], options: DownloadOptions | None=None) -> str: ...
+def startDownload(database: DataBase, gid: str, settings: dict[str, str] | None=None) -> str: ...
+def addTorrent(path: str, options: DownloadOptions | None=None) -> str: ...
+def addMetalink(path: str, options: DownloadOptions | None=None) -> str: ...
+def tellActive() -> tuple[list[str], list[dict[str, str | None]], list[dict[str, str]]]: ...
+def tellStatus(gid: str) -> tuple[dict[str, str | None], dict[str, str]]: ...
+def tellWaiting(offset: int, num: int) -> list[dict[str, str | None]]: ...
+def tellStopped(offset: int, num: int) -> list[dict[str, str | None]]: ...
 def findDownloadPath(file_name: str, download_path: str, subfolder: str) -> str: ...
 def shutDown() -> bool: ...
-def downloadPause(gid: str) -> str | None: ...
-def downloadUnpause(gid: str) -> str | None: ...
+def downloadStop(database: DataBase, gid: str) -> str: ...
+def downloadPause(gid: str) -> None: ...
+def downloadUnpause(gid: str) -> None: ...
 def limitSpeed(gid: str, limit: str) -> None: ...
+def pauseAll(force: bool = False) -> None: ...
+def resumeAll() -> None: ...
+def setGlobalSpeedLimit(limit: str, upload_limit: str | None = None) -> None: ...
 def activeDownloads() -> list[str]: ...
+def globalStat() -> GlobalStat: ...
+def pollEvents() -> list[DownloadEvent]: ...
+def startScheduler(database: DataBase) -> None: ...
+def scheduleDownload(database: DataBase, gid: str, settings: dict[str, str]) -> None: ...
+def configurePool(database: DataBase, max_active: int, per_host: int = 0) -> None: ...
+def submitDownload(database: DataBase, gid: str, settings: dict[str, str] | None = None, priority: int = 0) -> None: ...
+def extractLinks(text: str) -> list[LinkEntry]: ...
+def writeCookiesFile(load_cookies: str, link: str, path: str) -> int: ...
+def headerText(header: str) -> str: ...
+def spider(add_link_dict: dict[str, str]) -> SpiderResult: ...
+def spiderMany(database: DataBase, add_link_dicts: list[dict[str, str]], parallel: int = 8, per_host: int = 2) -> SpiderStream: ...
+def configureRetry(database: DataBase, settings: dict[str, str], max_attempts: int = 5, base_delay: int = 10) -> None: ...
 def nowDate() -> str: ...
 
 def determineConfigFolder() -> str: ...
@@ -30,8 +172,8 @@ def init_log_file() -> None: ...
 
 def touch(file_path: str) -> None: ...
 def xdgOpen(file_path: str, f_type: str='file', path: str='file') -> None: ...
-def remove(file_path: str) -> Literal['ok', 'cant', 'no']: ...
-def removeDir(folder_path: str) -> Literal['ok', 'cant', 'no']: ...
+def remove(file_path: str) -> bool: ...
+def removeDir(folder_path: str) -> bool: ...
 def makeDirs(folder_path: str, hidden: bool=False) -> str: ...
 def moveFile(old_file_path: str, new_path: str, new_path_type: str='folder') -> bool: ...
 
@@ -52,8 +194,22 @@ class PluginsDB:
   def createTables(self) -> None: ...
   def insertInPluginsTable(self, download_list: list[dict[str, str]]) -> None: ...
   def returnNewLinks(self) -> list[dict[str, str]]: ...
+  def searchHeaderInPluginsTable(self, link: str) -> list[tuple[str, str]]: ...
   def deleteOldLinks(self) -> None: ...
 
+class SpiderStream:
+  def __iter__(self) -> SpiderStream: ...
+  def __next__(self) -> SpiderItem: ...
+
+# QueueRunner sends downloads of category to download pool, parallel downloads at a time.
+# queue_complete event is sent when all of them are finished.
+class QueueRunner:
+  category: str
+  def __init__(self, database: DataBase, category: str, settings: dict[str, str], parallel: int = 1, wait: int = 0) -> None: ...
+  def start(self) -> None: ...
+  def stop(self) -> None: ...
+  def isRunning(self) -> bool: ...
+
 class DataBase:
   def __init__(self) -> None: ...
   def createTables(self) -> None: ...
@@ -64,15 +220,20 @@ class DataBase:
   def searchGidInVideoFinderTable(self, gid: str) -> dict[str, str] | None: ...
   def searchGidInDownloadTable(self, gid: str) -> dict[str, str] | None: ...
   def returnItemsInDownloadTable(self, category: str | None) -> dict[str, str]: ...
+  def searchDownloads(self, query: DownloadQuery) -> list[DownloadRow]: ...
   def searchLinkInAddLinkTable(self, link: str) -> bool: ...
   def searchGidInAddLinkTable(self, gid: str) -> dict[str, str] | None: ...
-  def returnItemsInAddLinkTable(self, category: str | None) -> dict[str, dict[str, str]]: ...
+  def searchHeaderInAddLinkTable(self, gid: str) -> list[tuple[str, str]]: ...
+  def searchGidInRetryTable(self, gid: str) -> dict[str, str] | None: ...
+  def returnItemsInAddLinkTable(self, category: str | None) -> dict[str, dict[str, str | None]]: ...
   def updateDownloadTable(self, download_list: list[dict[str, str]]) -> None: ...
   def updateCategoryTable(self, category_list: list[dict[str, str]]) -> None: ...
   def updateAddLinkTable(self, addlink_list: list[dict[str, str]]) -> None: ...
   def updateVideoFinderTable(self, video_list: list[dict[str, str]]) -> None: ...
   def setDefaultGidInAddlinkTable(self, gid: str, start_time: bool, end_time: bool, after_download: bool) -> None: ...
   def searchCategoryInCategoryTable(self, category: str) -> dict[str, str] | None: ...
+  def gidListOfCategory(self, category: str) -> list[str]: ...
+  def updateGidListOfCategory(self, category: str, gid_list: list[str]) -> None: ...
   def categoriesList(self) -> list[str]: ...
   def setDBTablesToDefaultValue(self) -> None: ...
   def findActiveDownloads(self, category: str | None) -> list[str]: ...
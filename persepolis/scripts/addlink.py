@@ -198,7 +198,7 @@ class AddLinkWindow(AddLinkWindow_Ui):
             self.referer_lineEdit.setText(str(self.plugin_add_link_dictionary['referer']))
 
         if 'header' in self.plugin_add_link_dictionary and str(self.plugin_add_link_dictionary['header']) != 'None':
-                self.header_lineEdit.setText(str(self.plugin_add_link_dictionary['header']))
+                self.header_lineEdit.setText(ghermez.headerText(str(self.plugin_add_link_dictionary['header'])))
 
         if 'user_agent' in self.plugin_add_link_dictionary:
             self.user_agent_lineEdit.setText(str(self.plugin_add_link_dictionary['user_agent']))
@@ -18,12 +18,11 @@ from typing import Callable
 from . import globals
 
 try:
-    from PySide6.QtCore import QDir, QPoint, QSettings, QSize, Qt, QThread, Signal
+    from PySide6.QtCore import QDir, QPoint, QSettings, QSize, Qt
     from PySide6.QtGui import QCloseEvent, QIcon, QKeyEvent
     from PySide6.QtWidgets import QFileDialog, QPushButton, QTableWidgetItem, QWidget
 except ImportError:
-    from PyQt5.QtCore import QDir, QPoint, QSettings, QSize, Qt, QThread
-    from PyQt5.QtCore import pyqtSignal as Signal
+    from PyQt5.QtCore import QDir, QPoint, QSettings, QSize, Qt
     from PyQt5.QtGui import QCloseEvent, QIcon, QKeyEvent
     from PyQt5.QtWidgets import QFileDialog, QPushButton, QTableWidgetItem, QWidget
 
@@ -31,32 +30,9 @@ import os
 from copy import deepcopy
 from functools import partial
 
+import ghermez
 from persepolis.gui.text_queue_ui import TextQueue_Ui
-from persepolis.scripts import logger, spider
-
-
-# This thread finds filename
-class QueueSpiderThread(QThread):
-    QUEUESPIDERRETURNEDFILENAME = Signal(str)
-
-    def __init__(self, download_dict: dict[str, str]) -> None:
-        super().__init__()
-        self.dict = download_dict
-
-    def run(self) -> None:
-        try:
-            filename = spider.queueSpider(self.dict)
-            if filename:
-                self.QUEUESPIDERRETURNEDFILENAME.emit(filename)
-            else:
-                logger.LOG_OBJ.error(
-                    "Spider couldn't find download information", exc_info=True)
-
-        except Exception as e:
-            logger.LOG_OBJ.error(
-                "Spider couldn't find download information", exc_info=True)
-
-            logger.LOG_OBJ.error(str(e), exc_info=True)
+from persepolis.scripts import spider
 
 
 class BrowserPluginQueue(TextQueue_Ui):
@@ -77,6 +53,10 @@ class BrowserPluginQueue(TextQueue_Ui):
 
         self.list_of_links.reverse()
 
+        # links that spider must find their file name and their rows in links_table
+        spider_dicts = []
+        spider_rows = []
+
         k = 1
         for link_dict in self.list_of_links:
             # add row to the links_table
@@ -86,12 +66,8 @@ class BrowserPluginQueue(TextQueue_Ui):
             file_name = (link_dict['out'] if link_dict['out'] else '***') if 'out' in link_dict else '***'
 
             if file_name == '***':
-                # spider finds file name
-                new_spider = QueueSpiderThread(link_dict)
-                self.parent.threadPool.append(new_spider)
-                self.parent.threadPool[-1].start()
-                self.parent.threadPool[-1].QUEUESPIDERRETURNEDFILENAME.connect(
-                    partial(self.parent.queueSpiderCallBack, child=self, row_number=len(self.list_of_links) - k))
+                spider_dicts.append(link_dict)
+                spider_rows.append(len(self.list_of_links) - k)
             k = k + 1
 
             item = QTableWidgetItem(file_name)
@@ -110,6 +86,14 @@ class BrowserPluginQueue(TextQueue_Ui):
             # insert link
             self.links_table.setItem(0, 1, item)
 
+        # spider finds file names
+        if spider_dicts:
+            new_spider = spider.QueueSpiderThread(self.parent.persepolis_db, spider_dicts, spider_rows)
+            self.parent.threadPool.append(new_spider)
+            self.parent.threadPool[-1].QUEUESPIDERRETURNEDFILENAME.connect(
+                partial(self.parent.queueSpiderCallBack, child=self))
+            self.parent.threadPool[-1].start()
+
         # get categories name and add them to add_queue_comboBox
         categories_list = self.parent.persepolis_db.categoriesList()
 
@@ -11,7 +11,10 @@
 #    You should have received a copy of the GNU General Public License
 #    along with this program.  If not, see <http://www.gnu.org/licenses/>.
 
+from __future__ import annotations
+
 import os
+import time
 
 import ghermez
 from persepolis.scripts.newopen import readList
@@ -44,6 +47,27 @@ queue_info_folder = os.path.join(config_folder, 'queue_info')
 single_downloads_list_file = os.path.join(category_folder, 'Single Downloads')
 
 
+# units of sizes in version 2, KB, MB and GB are 1024 based too.
+SIZE_UNITS = {'B': 1, 'KB': 1024, 'KiB': 1024, 'MB': 1024 ** 2, 'MiB': 1024 ** 2,
+              'GB': 1024 ** 3, 'GiB': 1024 ** 3, 'TB': 1024 ** 4, 'TiB': 1024 ** 4}
+
+
+# version 2 saved sizes, rate, percent and dates like "1.5 GiB", "100 KiB/s", "45%" and
+# "2017/09/09 , 13:12:26", but data base saves them as numbers.
+# None is returned for unknown values like "***".
+def downloadTableNumber(key: str, value: str) -> str | None:
+    try:
+        if key in ('first_try_date', 'last_try_date'):
+            return str(int(time.mktime(time.strptime(value, '%Y/%m/%d , %H:%M:%S'))))
+        if key == 'percent':
+            return str(int(float(value.rstrip('%'))))
+        value = value[:-2] if value.endswith('/s') else value
+        number, _, unit = value.strip().partition(' ')
+        return str(int(float(number) * SIZE_UNITS[unit or 'B']))
+    except (ValueError, KeyError):
+        return None
+
+
 # this script for compatibility between Version 2 and 3
 
 def compatibility() -> None:
@@ -55,7 +79,10 @@ def compatibility() -> None:
             queues_list = f.readlines()
 
         # remove queues_list_file
-        ghermez.remove(queues_list_file)
+        try:
+            ghermez.remove(queues_list_file)
+        except ghermez.IoError as error:
+            ghermez.sendToLog(str(error), 'ERROR')
     else:
         return
 
@@ -64,6 +91,7 @@ def compatibility() -> None:
         queue_name = line.strip()
         category_list.append(queue_name)
 
+    category_gid_lists = {}
     for category in category_list:
         gid_list = []
 
@@ -88,8 +116,8 @@ def compatibility() -> None:
                          'limit_enable': 'no',
                          'limit_value': '0K',
                          'after_download': 'no',
-                         'gid_list': str(gid_list),
                          }
+        category_gid_lists[category] = gid_list
 
         # add category to data_base
         if category in ('All Downloads', 'Single Downloads'):
@@ -122,6 +150,10 @@ def compatibility() -> None:
                 'last_try_date': download_info_file_list[11],
                 'category': download_info_file_list[12]}
 
+        for key in ('size', 'downloaded_size', 'percent', 'rate', 'first_try_date', 'last_try_date'):
+            download_dict[key] = downloadTableNumber(key, str(download_dict[key]))
+        download_dict = {key: value for key, value in download_dict.items() if value is not None}
+
         add_link_dictionary['gid'] = download_info_file_list[8]
 
         if 'user-agent' in add_link_dictionary:
@@ -162,12 +194,25 @@ def compatibility() -> None:
         persepolis_db.insertInDownloadTable([download_dict])
         persepolis_db.insertInAddLinkTable([add_link_dictionary])
 
+    # downloads are added to the end of categories, so order of categories must be restored
+    for category, gid_list in category_gid_lists.items():
+        try:
+            persepolis_db.updateGidListOfCategory(category, gid_list)
+        except ghermez.DatabaseError as error:
+            ghermez.sendToLog(str(error), 'ERROR')
+
     # close connections
     del persepolis_db
 
     # remove unwanted files and folders
     for file in [download_list_file, download_list_file_active]:
-        ghermez.remove(file)
+        try:
+            ghermez.remove(file)
+        except ghermez.IoError as error:
+            ghermez.sendToLog(str(error), 'ERROR')
 
     for folder in [category_folder, queue_info_folder]:
-        ghermez.removeDir(folder)
+        try:
+            ghermez.removeDir(folder)
+        except ghermez.IoError as error:
+            ghermez.sendToLog(str(error), 'ERROR')
@@ -13,18 +13,13 @@
 
 from __future__ import annotations
 
-import ast
 import os
 import platform
-import subprocess
 import time
-import traceback
-import urllib.parse
-import xmlrpc.client
 from typing import TYPE_CHECKING, Any
 
 import ghermez
-from ghermez import humanReadableSize, moveFile
+from ghermez import moveFile
 from persepolis.constants import APP_NAME, ORG_NAME, OS
 from persepolis.constants.status import DownloadStatus
 from persepolis.scripts.bubble import notifySend
@@ -58,37 +53,35 @@ else:
 # persepolis setting
 persepolis_setting = QSettings(ORG_NAME, APP_NAME)
 
-# host is localhost
-host = 'localhost'
-
 # get port from persepolis_setting
 port = int(persepolis_setting.value('settings/rpc-port'))
 
 # get aria2_path
 aria2_path = persepolis_setting.value('settings/aria2_path')
 
-# xml rpc
-SERVER_URI_FORMAT = 'http://{}:{:d}/rpc'
-server_uri = SERVER_URI_FORMAT.format(host, port)
-server = xmlrpc.client.ServerProxy(server_uri, allow_none=True)
+# get rpc secret. ghermez generates a random secret if it's empty.
+rpc_secret = persepolis_setting.value('settings/rpc-secret')
 
 # start aria2 with RPC
-def startAria() -> str:
-    # return that starting is successful or not!
-    return ghermez.startAria(port, aria2_path)
+def startAria() -> str | None:
+    # return aria2 version if starting is successful, otherwise None.
+    try:
+        return ghermez.startAria(port, aria2_path, rpc_secret)
+    except ghermez.GhermezError as error:
+        ghermez.sendToLog(f"Aria2 didn't start: {error}", 'ERROR')
+        return None
 
 
-# check aria2 release version . Persepolis uses this function to
-# check that aria2 RPC connection is available or not.
-def aria2Version() -> str:
+# Persepolis uses this function to check that aria2 RPC connection is available or not.
+def aria2Responds() -> bool:
     try:
-        answer = server.aria2.getVersion()
-    except Exception:
+        ghermez.aria2Version()
+    except ghermez.GhermezError:
         # write ERROR messages in terminal and log
         ghermez.sendToLog("Aria2 didn't respond!", 'ERROR')
-        answer = 'did not respond'
+        return False
 
-    return answer
+    return True
 
 # this function sends download request to aria2
 def downloadAria(gid: str, parent: QWidget) -> bool | None:
@@ -102,92 +95,18 @@ def downloadAria(gid: str, parent: QWidget) -> bool | None:
         if add_link_dictionary[key] in ['NULL', 'None']:
             add_link_dictionary[key] = None
 
-    link = add_link_dictionary['link']
-    ip = add_link_dictionary['ip']
-    port = add_link_dictionary['port']
-    proxy_user = add_link_dictionary['proxy_user']
-    proxy_passwd = add_link_dictionary['proxy_passwd']
-    download_user = add_link_dictionary['download_user']
-    download_passwd = add_link_dictionary['download_passwd']
-    connections = add_link_dictionary['connections']
-    limit = str(add_link_dictionary['limit_value'])
     start_time = add_link_dictionary['start_time']
-    end_time = add_link_dictionary['end_time']
-    header = add_link_dictionary['header']
-    out = add_link_dictionary['out']
-    user_agent = add_link_dictionary['user_agent']
-    cookies = add_link_dictionary['load_cookies']
-    referer = add_link_dictionary['referer']
-
-    # make header option
-    header_list = []
-    header_list.append('Cookie: ' + str(cookies))
-
-    # convert Mega to Kilo, RPC does not Support floating point numbers.
-    if limit != '0':
-        limit_number = limit[:-1]
-        limit_number = float(limit_number)
-        limit_unit = limit[-1]
-        if limit_unit == 'K':
-            limit_number = round(limit_number)
-        else:
-            limit_number = round(1024*limit_number)
-            limit_unit = 'K'
-        limit = str(limit_number) + limit_unit
-
-    # create header list
-    if header is not None:
-        semicolon_split_header = header.split('; ')
-        for i in semicolon_split_header:
-            equal_split_header = i.split('=', 1)
-            join_header = ':'.join(equal_split_header)
-            if i != '':
-                header_list.append(join_header)
-
-    if len(header_list) == 0:
-        header_list = None
 
     # update status and last_try_date in data_base
     status = DownloadStatus.Scheduled if start_time else DownloadStatus.Waiting
 
     # get last_try_date
-    now_date = nowDate()
+    now = nowTimestamp()
 
     # update data_base
-    download_dict = {'gid': gid, 'status': status, 'last_try_date': now_date}
+    download_dict = {'gid': gid, 'status': status, 'last_try_date': now}
     parent.persepolis_db.updateDownloadTable([download_dict])
 
-    # create ip_port from ip and port in desired format.
-    # for example "127.0.0.1:8118"
-    ip_port = str(ip) + ':' + str(port) if ip else ''
-
-    # call startTime if start_time is available
-    # startTime creates sleep loop if user set start_time
-    # see startTime function for more information.
-    start_time_status = startTime(start_time, gid, parent) if start_time else DownloadStatus.Downloading
-
-    if start_time_status == DownloadStatus.Scheduled:
-        # read limit value again from data_base before starting download!
-        # perhaps user changed this in progress bar window
-        add_link_dictionary = parent.persepolis_db.searchGidInAddLinkTable(gid)
-        limit = add_link_dictionary['limit_value']
-
-        # convert Mega to Kilo, RPC does not Support floating point numbers.
-        if limit != '0':
-            limit_number = limit[:-1]
-            limit_number = float(limit_number)
-            limit_unit = limit[-1]
-            if limit_unit == 'K':
-                limit_number = round(limit_number)
-            else:
-                limit_number = round(1024*limit_number)
-                limit_unit = 'K'
-            limit = str(limit_number) + limit_unit
-
-
-        # set start_time value to None in data_base!
-        parent.persepolis_db.setDefaultGidInAddlinkTable(gid, start_time=True)
-
     # Find download_path_temp from persepolis_setting
     # if download_path_temp and download_path aren't in same partition on hard disk,
     # then create new temp folder in that partition.
@@ -213,107 +132,64 @@ def downloadAria(gid: str, parent: QWidget) -> bool | None:
         # write an error in ghermez
         ghermez.sendToLog('download_path is not found!', 'ERROR')
 
-    if start_time_status != DownloadStatus.Stopped:
-        # send download request to aria2
-        aria_dict = {
-            'gid': gid,
-            'max-tries': str(persepolis_setting.value('settings/max-tries')),
-            'retry-wait': int(persepolis_setting.value('settings/retry-wait')),
-            'timeout': int(persepolis_setting.value('settings/timeout')),
-            'header': header_list,
-            'out': out,
-            'user-agent': user_agent,
-            'referer': referer,
-            'all-proxy': ip_port,
-            'max-download-limit': limit,
-            'all-proxy-user': str(proxy_user),
-            'all-proxy-passwd': str(proxy_passwd),
-            'http-user': str(download_user),
-            'http-passwd': str(download_passwd),
-            'split': '16',
-            'max-connection-per-server': str(connections),
-            'min-split-size': '1M',
-            'continue': 'true',
-            'dir': str(download_path_temp),
-        }
-
-        if str(persepolis_setting.value('settings/dont-check-certificate')) == 'yes':
-            aria_dict['check-certificate'] = 'false'
-
-        if not link.startswith('https'):
-            aria_dict['http-user'] = str(download_user)
-            aria_dict['http-passwd'] = str(download_passwd)
-
-        aria_dict_copy = aria_dict.copy()
-        # remove empty key[value] from aria_dict
-        for aria_dict_key in aria_dict_copy:
-            if aria_dict_copy[aria_dict_key] in [None, 'None', '']:
-                del aria_dict[aria_dict_key]
+    # ghermez reads download information from data_base again,
+    # perhaps user changed limit value in progress bar window.
+    settings = {
+        'max-tries': str(persepolis_setting.value('settings/max-tries')),
+        'retry-wait': str(persepolis_setting.value('settings/retry-wait')),
+        'timeout': str(persepolis_setting.value('settings/timeout')),
+        'dont-check-certificate': str(persepolis_setting.value('settings/dont-check-certificate')),
+        'download_path_temp': str(download_path_temp),
+    }
 
+    # ghermez scheduler sends download request to aria2 when start_time arrives,
+    # and stops download when end_time arrives.
+    # settings are saved in data_base, so download starts after restart too.
+    # if user cancels download before start_time, scheduler doesn't start it.
+    if start_time:
+        ghermez.sendToLog('Download starts at ' + start_time, 'INFO')
         try:
-            answer = server.aria2.addUri([link], aria_dict)
-
-            ghermez.sendToLog(answer + ' Starts', 'INFO')
-            if end_time:
-                endTime(end_time, gid, parent)
-
-        except Exception:
-
+            ghermez.scheduleDownload(parent.persepolis_db, gid, settings)
+        except ghermez.GhermezError:
             # write error status in data_base
             download_dict = {'gid': gid, 'status': DownloadStatus.Error}
             parent.persepolis_db.updateDownloadTable([download_dict])
-
-            # write ERROR messages in log
-            ghermez.sendToLog('Download did not start', 'ERROR')
-            error_message = str(traceback.format_exc())
-            ghermez.sendToLog(error_message, 'ERROR')
-
-            # return False!
             return False
-    else:
-        # if start_time_status is "stopped" it means download Canceled by user
-        ghermez.sendToLog('Download Canceled', 'INFO')
+
         return None
 
-# this function returns list of download information
-def tellActive() -> (tuple[None, None] | tuple[list, list]):
-    # get download information from aria2
+    # ghermez download pool sends download request to aria2 when there is a free slot.
+    # ghermez writes error message in log if download didn't start.
     try:
-        downloads_status = server.aria2.tellActive(
-            ['gid', 'status', 'connections', 'errorCode', 'errorMessage',
-             'downloadSpeed', 'dir', 'totalLength', 'completedLength', 'files'])
-    except Exception:
-        return None, None
-
-    download_status_list = []
-    gid_list = []
-
-    # convert download information in desired format.
-    for download_dict in downloads_status:
-        converted_info_dict = convertDownloadInformation(download_dict)
+        ghermez.submitDownload(parent.persepolis_db, gid, settings)
+    except ghermez.GhermezError:
+        # write error status in data_base
+        download_dict = {'gid': gid, 'status': DownloadStatus.Error}
+        parent.persepolis_db.updateDownloadTable([download_dict])
 
-        # add gid to gid_list
-        gid_list.append(download_dict['gid'])
+        # return False!
+        return False
 
-        # add converted information to download_status_list
-        download_status_list.append(converted_info_dict)
+    return None
 
-    return gid_list, download_status_list
+# this function returns list of download information.
+# download_table_list contains the same downloads in the format of download_db_table.
+# see tellActive in ghermez.
+def tellActive() -> (tuple[None, None, None] | tuple[list, list, list]):
+    # get download information from aria2
+    try:
+        return ghermez.tellActive()
+    except ghermez.GhermezError:
+        return None, None, None
 
 # this function returns download status that specified by gid!
-def tellStatus(gid: str, parent: QWidget) -> (dict[str, Any] | None):
+# download_table_dict is the same status in the format of download_db_table.
+def tellStatus(gid: str, parent: QWidget) -> (tuple[dict[str, Any], dict[str, str]] | tuple[None, None]):
     # get download status from aria2
     try:
-        download_status = server.aria2.tellStatus(
-            gid, ['status', 'connections', 'errorCode', 'errorMessage', 'downloadSpeed',
-                  'connections', 'dir', 'totalLength', 'completedLength', 'files'])
-        download_status['gid'] = str(gid)
-    except Exception:
-        return None
-
-    # convert download_status in desired format
-    converted_info_dict = convertDownloadInformation(download_status)
-
+        converted_info_dict, download_table_dict = ghermez.tellStatus(gid)
+    except ghermez.GhermezError:
+        return None, None
 
     # if download has completed , then move file to the download folder
     if converted_info_dict['status'] == DownloadStatus.Complete:
@@ -333,20 +209,10 @@ def tellStatus(gid: str, parent: QWidget) -> (dict[str, Any] | None):
                 file_name, download_path, persepolis_setting.value('settings/subfolder'))
 
         # find temp download path
-        file_status = str(download_status['files'])
-        file_status = file_status[1:-1]
-        file_status = ast.literal_eval(file_status)
-
-        path = str(file_status['path'])
+        path = str(converted_info_dict['path'])
 
-        # file_name
-        file_name = urllib.parse.unquote(os.path.basename(path))
-
-        # find file_size
-        try:
-            file_size = int(download_status['totalLength'])
-        except ValueError:
-            file_size = None
+        # find file_size. size is not in download_table_dict if aria2 doesn't know it.
+        file_size = int(download_table_dict['size']) if 'size' in download_table_dict else None
 
         # if file is related to VideoFinder thread, don't move it from temp folder...
         video_finder_dictionary = parent.persepolis_db.searchGidInVideoFinderTable(gid)
@@ -359,151 +225,11 @@ def tellStatus(gid: str, parent: QWidget) -> (dict[str, Any] | None):
         add_link_dictionary['download_path'] = file_path
         parent.persepolis_db.updateAddLinkTable([add_link_dictionary])
 
-    # if an error occurred!
-    if converted_info_dict['status'] == DownloadStatus.Error:
-        # add errorMessage to converted_info_dict
-        converted_info_dict['error'] = str(download_status['errorMessage'])
-
-        # remove download from aria2
-        server.aria2.removeDownloadResult(gid)
+    # result of failed download stays in aria2. ghermez download pool removes it,
+    # when download is sent to aria2 again.
 
     # return results in dictionary format
-    return converted_info_dict
-
-# this function converts download information that received from aria2 in desired format.
-# input format must be a dictionary.
-def convertDownloadInformation(download_status: dict[str, str]) -> dict[str, Any]:
-    # find file_name
-    try:
-        # file_status contains name of download file and link of download file
-        file_status = str(download_status['files'])
-        file_status = file_status[1:-1]
-        file_status = ast.literal_eval(file_status)
-        path = str(file_status['path'])
-        file_name = urllib.parse.unquote(os.path.basename(path))
-        if not(file_name):
-            file_name = None
-
-        uris = file_status['uris']
-        uri = uris[0]
-        link = uri['uri']
-
-    except Exception:
-
-        file_name = None
-        link = None
-
-    for i in download_status:
-        if not(download_status[i]):
-            download_status[i] = None
-
-    # find file_size
-    try:
-        file_size = float(download_status['totalLength'])
-    except ValueError:
-        file_size = None
-
-    # find downloaded size
-    try:
-        downloaded = float(download_status['completedLength'])
-    except ValueError:
-        downloaded = None
-
-    # convert file_size and downloaded_size to KiB and MiB and GiB
-    if (downloaded is not None and file_size is not None and file_size != 0):
-        file_size_back = file_size
-
-        # converting file_size to KiB or MiB or GiB
-        size_str = humanReadableSize(file_size)
-        downloaded_back = downloaded
-
-        downloaded_str = humanReadableSize(downloaded)
-
-        # find download percent from file_size and downloaded_size
-        file_size = file_size_back
-        downloaded = downloaded_back
-        percent = int(downloaded * 100 / file_size)
-        percent_str = str(percent) + '%'
-    else:
-        percent_str = None
-        size_str = None
-        downloaded_str = None
-
-    # find download_speed
-    try:
-        download_speed = int(download_status['downloadSpeed'])
-    except ValueError:
-        download_speed = 0
-
-    # convert download_speed to desired units.
-    # and find estimate_time_left
-    if (downloaded is not None and download_speed != 0):
-        estimate_time_left = int((file_size - downloaded)/download_speed)
-
-        # converting file_size to KiB or MiB or GiB
-        download_speed_str = humanReadableSize(download_speed, 'speed') + '/s'
-
-        eta = ''
-        ONE_HOUR = 3600
-        ONE_MIN = 60
-        if estimate_time_left >= ONE_HOUR:
-            eta = eta + str(int(estimate_time_left/3600)) + 'h'
-            estimate_time_left = estimate_time_left % 3600
-            eta = eta + str(int(estimate_time_left/60)) + 'm'
-            estimate_time_left = estimate_time_left % 60
-            eta = eta + str(estimate_time_left) + 's'
-        elif estimate_time_left >= ONE_MIN:
-            eta = eta + str(int(estimate_time_left/60)) + 'm'
-            estimate_time_left = estimate_time_left % 60
-            eta = eta + str(estimate_time_left) + 's'
-        else:
-            eta = eta + str(estimate_time_left) + 's'
-        estimate_time_left_str = eta
-
-    else:
-        download_speed_str = '0'
-        estimate_time_left_str = None
-
-    # find number of connections
-    try:
-        connections_str = str(download_status['connections'])
-    except (UnicodeEncodeError, KeyError):
-        connections_str = None
-
-    # find status of download
-    try:
-        status_str = str(download_status['status'])
-    except (UnicodeEncodeError, KeyError):
-        status_str = None
-
-    # rename active status to downloading
-    if (status_str == 'active'):
-        status_str = DownloadStatus.Downloading
-
-    # rename removed status to stopped
-    if (status_str == 'removed'):
-        status_str = DownloadStatus.Stopped
-
-    if (status_str == 'None'):
-        status_str = None
-
-    # set 0 second for estimate_time_left_str if download is completed.
-    if status_str == 'complete':
-        estimate_time_left_str = '0s'
-
-    # return information in dictionary format
-    return {
-        'gid': download_status['gid'],
-        'file_name': file_name,
-        'status': status_str,
-        'size': size_str,
-        'downloaded_size': downloaded_str,
-        'percent': percent_str,
-        'connections': connections_str,
-        'rate': download_speed_str,
-        'estimate_time_left': estimate_time_left_str,
-        'link': link,
-    }
+    return converted_info_dict, download_table_dict
 
 
 # download complete actions!
@@ -629,123 +355,50 @@ def findDownloadPath(file_name: str, download_path: str, subfolder: str) -> str:
         return download_path
 
 
-# shutdown aria2
-def shutDown() -> bool:
-    try:
-        answer = server.aria2.shutdown()
-        ghermez.sendToLog('Aria2 Shutdown : ' + str(answer), 'INFO')
-        return True
-    except Exception:
-        ghermez.sendToLog('Aria2 Shutdown Error', 'ERROR')
-        return False
-
 # downloadStop stops download completely
 # this function sends remove request to aria2
 # and changes status of download to "stopped" in data_base
-def downloadStop(gid: str, parent: QWidget) -> str:
-    # get download status from data_base
-    download_dict = parent.persepolis_db.searchGidInDownloadTable(gid)
-    status = download_dict['status']
-
-    # if status is "scheduled", then download request has not been sended to aria2!
-    # so no need to send stop request to aria2.
-    # if status in not "scheduled" so stop request must be sended to aria2.
-    if status != DownloadStatus.Scheduled:
-        try:
-            # send remove download request to aria2.
-            # see aria2 documentation for more information.
-            answer = server.aria2.remove(gid)
-            if status == DownloadStatus.Downloading:
-                server.aria2.removeDownloadResult(gid)
-        except Exception:
-            answer = 'None'
-
-        # write a messages in log and terminal
-        ghermez.sendToLog(answer + ' stopped', 'INFO')
-
-    # if download has not been completed yet,
-    # so just chang status of download to "stopped" in data base.
-    else:
-        answer = DownloadStatus.Stopped
-
-    if status != DownloadStatus.Complete:
-        # change start_time end_time and after_download value to None in date base
-        parent.persepolis_db.setDefaultGidInAddlinkTable(gid,
-                                                         start_time=True, end_time=True, after_download=True)
-
-        # change status of download to "stopped" in data base
-        download_dict = {'gid': gid, 'status': DownloadStatus.Stopped}
-        parent.persepolis_db.updateDownloadTable([download_dict])
+# it returns False if aria2 didn't stop download.
+def downloadStop(gid: str, parent: QWidget) -> bool:
+    try:
+        ghermez.downloadStop(parent.persepolis_db, gid)
+    except ghermez.GhermezError:
+        return False
 
-    return answer
+    return True
 
 
 # downloadPause pauses download
-def downloadPause(gid: str) -> str | None:
-    # see aria2 documentation for more information
-
-    # send pause request to aria2 .
+# it returns False if aria2 didn't pause download.
+def downloadPause(gid: str) -> bool:
     try:
-        answer = server.aria2.pause(gid)
-    except Exception:
-        answer = None
+        ghermez.downloadPause(gid)
+    except ghermez.GhermezError:
+        return False
 
-    ghermez.sendToLog(str(answer) + ' paused', 'INFO')
-    return answer
+    return True
 
 
 # downloadUnpause unpauses download
-def downloadUnpause(gid: str) -> str | None:
+# it returns False if aria2 didn't unpause download.
+def downloadUnpause(gid: str) -> bool:
     try:
-        # send unpause request to aria2
-        answer = server.aria2.unpause(gid)
-    except Exception:
-        answer = None
+        ghermez.downloadUnpause(gid)
+    except ghermez.GhermezError:
+        return False
 
-    ghermez.sendToLog(str(answer) + ' unpaused', 'INFO')
+    return True
 
-    return answer
 
 #  limitSpeed limits download speed
-def limitSpeed(gid: str, limit: str) -> None:
-    limit = str(limit)
-    # convert Mega to Kilo, RPC does not Support floating point numbers.
-    if limit != '0':
-        limit_number = limit[:-1]
-        limit_number = float(limit_number)
-        limit_unit = limit[-1]
-        if limit_unit == 'K':
-            limit_number = round(limit_number)
-        else:
-            limit_number = round(1024*limit_number)
-            limit_unit = 'K'
-        limit = str(limit_number) + limit_unit
-
+# ghermez writes error message in log if limitation was unsuccessful.
+def limitSpeed(gid: str, limit: str) -> bool:
     try:
-        server.aria2.changeOption(gid, {'max-download-limit': limit})
-        ghermez.sendToLog('Download speed limit  value is changed', 'INFO')
-
-    except Exception:
-        ghermez.sendToLog('Speed limitation was unsuccessful', 'ERROR')
-
-
-# this function returns GID of active downloads in list format.
-def activeDownloads() -> list[str]:
-    try:
-        answer = server.aria2.tellActive(['gid'])
-    except Exception:
-        answer = []
-
-    active_gids = []
-    for i in answer:
-        # extract gid from dictionary
-        download_dict = i
-        gid = download_dict['gid']
-
-        # add gid to list
-        active_gids.append(gid)
+        ghermez.limitSpeed(gid, limit)
+    except ghermez.GhermezError:
+        return False
 
-    return active_gids
+    return True
 
 
 # This function returns data and time in string format
@@ -753,104 +406,8 @@ def activeDownloads() -> list[str]:
 def nowDate() -> str:
     return time.strftime('%Y/%m/%d , %H:%M:%S')
 
-# sigmaTime gets hours and minutes for input.
-# and converts hours to minutes and returns summation in minutes
-# input format is HH:MM
-def sigmaTime(time: str) -> int:
-    hour, minute = time.split(':')
-    return (int(hour)*60 + int(minute))
-
-# nowTime returns now time in HH:MM format!
-def nowTime() -> int:
-    now_time = time.strftime('%H:%M')
-    return sigmaTime(now_time)
-
-# this function creates sleep time,if user sets "start time" for download.
-def startTime(start_time: str, gid: str, parent: QWidget) -> DownloadStatus:
-    # write some messages
-    ghermez.sendToLog('Download starts at ' + start_time, 'INFO')
-
-    # start_time that specified by user
-    sigma_start = sigmaTime(start_time)
-
-    # get current time
-    sigma_now = nowTime()
-
-    status = DownloadStatus.Scheduled
-
-    # this loop is continuing until download time arrival!
-    while sigma_start != sigma_now:
-        time.sleep(2.1)
-        sigma_now = nowTime()
-
-        # check download status from data_base
-        download_dict = parent.persepolis_db.searchGidInDownloadTable(gid)
-        data_base_download_status = download_dict['status']
-
-        # if data_base_download_status = stopped >> it means that user
-        # canceled download and loop must break!
-        if data_base_download_status == DownloadStatus.Stopped:
-            status = DownloadStatus.Stopped
-            break
-        status = DownloadStatus.Scheduled
-
-    # if user canceled download , then return 'stopped' and if download time arrived then return 'scheduled'!
-    return status
-
-
-def endTime(end_time: str, gid: str, parent: QWidget) -> None:
-    ghermez.sendToLog('End time is activated ' + gid, 'INFO')
-    sigma_end = sigmaTime(end_time)
-
-    # get current time
-    sigma_now = nowTime()
-
-    answer = 'end'
-    # while current time is not equal to end_time, continue the loop
-    while sigma_end != sigma_now:
-
-        # get download status from data_base
-        download_dict = parent.persepolis_db.searchGidInDownloadTable(gid)
-        status = download_dict['status']
-
-        # check download status
-        if status in ('scheduled', 'downloading', 'paused', 'waiting'):
-
-            # download continues!
-            answer = 'continue'
-
-        else:
 
-            # Download completed or stopped by user
-            # so break the loop
-            answer = 'end'
-            ghermez.sendToLog('Download has been finished! ' + str(gid), 'INFO')
-            break
-
-        # get current time
-        sigma_now = nowTime()
-        time.sleep(2.1)
-
-    # Time is up!
-    if answer != 'end':
-        ghermez.sendToLog('Time is up!', 'INFO')
-        answer = downloadStop(gid, parent)
-        i = 0
-        MAX_TIMES = 10
-        # try to stop download 10 times
-        while answer == 'None' and (i < MAX_TIMES):
-            time.sleep(1)
-            answer = downloadStop(gid, parent)
-            i = i + 1
-
-        # If aria2c not respond, so kill it. R.I.P :))
-        if (answer == 'None') and (os_type != OS.WINDOWS):
-
-            subprocess.Popen(['killall', 'aria2c'],
-                             stderr=subprocess.PIPE,
-                             stdout=subprocess.PIPE,
-                             stdin=subprocess.PIPE,
-                             shell=False)
-
-        # change end_time value to None in data_base
-        parent.persepolis_db.setDefaultGidInAddlinkTable(gid, end_time=True)
+# This function returns unix time in string format
+# dates are saved in data_base like this, for example >> 1504950146
+def nowTimestamp() -> str:
+    return str(int(time.time()))
@@ -69,7 +69,6 @@ import sys
 import textwrap
 import time
 import urllib.parse
-from ast import literal_eval
 from copy import deepcopy
 from functools import partial
 from time import sleep
@@ -241,10 +240,13 @@ class StartAria2Thread(QThread):
         # global aria_startup_answer
 
         # check that aria2 is running or not!
-        answer = ghermez.aria2Version()
+        try:
+            answer = ghermez.aria2Version()
+        except ghermez.GhermezError:
+            answer = None
 
         # if Aria2 wasn't started before, so start it!
-        if answer == 'did not respond':
+        if answer is None:
 
             # write in log file.
             ghermez.sendToLog('Starting Aria2', 'INFO')
@@ -254,7 +256,7 @@ class StartAria2Thread(QThread):
 
                 answer = download.startAria()
 
-                if answer == 'did not respond':
+                if answer is None:
 
                     signal_str = 'try again'
                     self.ARIA2RESPONDSIGNAL.emit(signal_str)
@@ -265,7 +267,7 @@ class StartAria2Thread(QThread):
 
         # if Aria2 doesn't respond to Persepolis ,ARIA2RESPONDSIGNAL is
         # emitting no
-        if answer == 'did not respond':
+        if answer is None:
 
             signal_str = 'no'
 
@@ -330,6 +332,14 @@ class CheckDownloadInfoThread(QThread):
                     while globals.checking_flag != CheckingFlag.Normal:
                         sleep(0.2)
 
+                # ghermez sends download events of aria2 as soon as they happen.
+                # aria2 doesn't send events for download progress, so tellActive is still needed.
+                for event in ghermez.pollEvents():
+                    self.downloadEvent(event)
+                    if event['event'] in ('complete', 'bt_complete', 'error', 'stop'):
+                        # write status of finished download in data base immediately.
+                        update_data_base = True
+
                 # lets getting downloads information from aria and putting them in download_status_list!
 
                 # find gid of active downloads first! (get them from data base)
@@ -340,8 +350,9 @@ class CheckDownloadInfoThread(QThread):
                 # download_status_list is a list that contains some dictionaries.
                 # every dictionary contains download information.
                 # gid_list is a list that contains gid of downloads in download_status_list.
+                # download_table_list contains the same information in format of data base.
                 # see download.py file for more information.
-                gid_list, download_status_list = download.tellActive()
+                gid_list, download_status_list, download_table_list = download.tellActive()
 
                 try:
                     for gid in active_gid_list:
@@ -355,9 +366,10 @@ class CheckDownloadInfoThread(QThread):
                         # then perhaps some error occurred.so download information must be in data_base.
                         if gid not in gid_list:
 
-                            returned_dict = download.tellStatus(gid, self.parent)
+                            returned_dict, download_table_dict = download.tellStatus(gid, self.parent)
                             if returned_dict:
                                 download_status_list.append(returned_dict)
+                                download_table_list.append(download_table_dict)
                                 update_data_base = True
                             else:
                                 # check data_base
@@ -386,7 +398,7 @@ class CheckDownloadInfoThread(QThread):
 
                     # update data base!
                     if update_data_base:
-                        self.parent.persepolis_db.updateDownloadTable(download_status_list)
+                        self.parent.persepolis_db.updateDownloadTable(download_table_list)
 
                         # data base is updated 1 time in 5 times.
                         update_data_base = False
@@ -401,25 +413,47 @@ class CheckDownloadInfoThread(QThread):
             globals.shutdown_notification = ShutdownNotification.Ok
             break
 
+    # react to download events of ghermez. see pollEvents in ghermez for more information.
+    def downloadEvent(self, event):
+        gid = event['gid']
+        if event['event'] == 'start' and gid:
+            # downloads that are started by ghermez (queue, scheduler, retry and pool)
+            # must be checked too.
+            try:
+                self.parent.temp_db.insertInSingleTable(gid)
+            except Exception:
+                # release lock
+                self.parent.temp_db.lock = False
+                self.parent.temp_db.updateSingleTable({'gid': gid, 'status': 'active'})
+
+        elif event['event'] == 'disconnected':
+            self.reconnectAria()
+
+        # QueueRunner of queue is finished.
+        elif event['event'] in ('queue_complete', 'queue_stopped'):
+            queue = self.parent.queue_list_dict.get(event['category'])
+            if queue:
+                queue.queue_event = event['event']
+
     # when rpc connection between persepolis and aria is
     # disconnected then aria2_disconnected = 1
     def reconnectAria(self):
         globals.aria2_disconnected = False
-        # check aria2 availability by aria2Version function(see download.py file fore more information)
-        answer = ghermez.aria2Version()
-
-        if answer == 'did not respond':
+        # check aria2 availability by aria2Responds function(see download.py file fore more information)
+        if not download.aria2Responds():
             # so aria2 connection in disconnected!
             # lets try to reconnect aria 5 times!
             for _ in range(5):
                 answer = download.startAria()  # start aria2
-                if answer == 'did not respond':  # check answer
+                if answer is None:  # check answer
                     sleep(2)
                 else:
-                    # emit answer.
-                    # if answer is 'did not respond', it means that reconnecting aria was not successful
+                    # emit aria2 version.
                     self.RECONNECTARIASIGNAL.emit(str(answer))
                     break
+            else:
+                # aria2 didn't start. empty message means failure.
+                self.RECONNECTARIASIGNAL.emit('')
 
 
 # SpiderThread calls spider in spider.py .
@@ -439,7 +473,9 @@ class SpiderThread(QThread):
             file_name, size = spider.spider(self.add_link_dictionary)
 
             # update data base
-            dictionary = {'file_name': file_name, 'size': size, 'gid': self.add_link_dictionary['gid']}
+            dictionary = {'file_name': file_name, 'gid': self.add_link_dictionary['gid']}
+            if size is not None:
+                dictionary['size'] = str(size)
             self.parent.persepolis_db.updateDownloadTable([dictionary])
             dictionary = self.parent.persepolis_db.searchGidInDownloadTable(dictionary['gid'])
 
@@ -472,13 +508,10 @@ class DownloadLink(QThread):
             self.parent.temp_db.updateSingleTable(dictionary)
 
         # if request is not successful then persepolis is checking rpc
-        # connection with ghermez.aria2Version() function
+        # connection with download.aria2Responds() function
         answer = download.downloadAria(self.gid, self.parent)
-        if answer is False:
-            version_answer = ghermez.aria2Version()
-
-            if version_answer == 'did not respond':
-                self.ARIA2NOTRESPOND.emit()
+        if answer is False and not download.aria2Responds():
+            self.ARIA2NOTRESPOND.emit()
 
 # Persepolis download audio and video separately and the muxing them :)
 # VideoFinder do this duty for Persepolis.
@@ -622,6 +655,7 @@ class VideoFinder(QThread):
             complete_dictionary = {'error': error_message,
                                    'final_path': result_dictionary['final_path'],
                                    'final_size': result_dictionary['final_size'],
+                                   'final_file_size': result_dictionary['final_file_size'],
                                    'video_gid': self.video_finder_dictionary['video_gid'],
                                    'audio_gid': self.video_finder_dictionary['audio_gid'],
                                    'download_path': self.video_finder_dictionary['download_path'],
@@ -652,12 +686,45 @@ class Queue(QThread):
     # this signal emitted when download status of queue changes to stop
     REFRESHTOOLBARSIGNAL = Signal(str)
 
-    def __init__(self, category, start_time, end_time, parent) -> None:
+    # ghermez QueueRunner sends downloads of queue to aria2 and applies speed limit of queue.
+    # end time of queue is not set for downloads, ghermez scheduler pauses downloads
+    # of category when its end time arrives and resumes them at its start time.
+    def __init__(self, category, start_time, parent) -> None:
         super().__init__()
         self.category = str(category)
         self.parent = parent
         self.start_time = start_time
-        self.end_time = end_time
+
+        # queue_complete or queue_stopped event of QueueRunner.
+        # see CheckDownloadInfoThread.downloadEvent
+        self.queue_event = None
+
+    # wait until start_time arrives or user stops queue.
+    def waitForStartTime(self):
+        start_hour, start_minute = (int(x) for x in self.start_time.split(':'))
+        while not(self.stop):
+            if int(time.strftime('%H')) == start_hour and int(time.strftime('%M')) == start_minute:
+                break
+            sleep(1)
+
+    # write speed limit of queue in data base. QueueRunner reads it from there.
+    def writeLimit(self):
+        queue_info_dict = {'category': self.category}
+        if self.limit:
+            # get limitation value
+            self.limit_comboBox_value = self.parent.limit_comboBox.currentText()
+            self.limit_spinBox_value = self.parent.limit_spinBox.value()
+            if self.limit_comboBox_value == 'KiB/s':
+                limit = str(self.limit_spinBox_value) + 'K'
+            else:
+                limit = str(self.limit_spinBox_value) + 'M'
+
+            queue_info_dict['limit_enable'] = 'yes'
+            queue_info_dict['limit_value'] = limit
+        else:
+            queue_info_dict['limit_enable'] = 'no'
+
+        self.parent.persepolis_db.updateCategoryTable([queue_info_dict])
 
     def run(self):
         self.start = True
@@ -665,350 +732,163 @@ class Queue(QThread):
         self.limit = False
         self.limit_changed = False
         self.after = False
-        self.break_for_loop = False
 
-        queue_counter = 0
+        # speed limit of previous run of queue is not valid anymore.
+        self.writeLimit()
 
         # this list contains gid_list of all active video finder in queue.
         video_finder_list = []
 
-        # queue repeats 5 times!
-        # and every time loads queue list again!
-        # It is helps for checking new downloads in queue
-        # and retrying failed downloads.
-        for counter in range(5):
-
-            # read downloads information from data base
-            download_table_dict = self.parent.persepolis_db.returnItemsInDownloadTable(self.category)
-            category_table_dict = self.parent.persepolis_db.searchCategoryInCategoryTable(self.category)
-
-            gid_list = literal_eval(category_table_dict['gid_list'])
-
-            # sort downloads top to the bottom of the list OR bottom to the top
-            if not(self.parent.reverse_checkBox.isChecked()):
-                gid_list.reverse()
-
-            # check that if user set start time
-            if self.start_time and counter == 0:
-
-                # find first download
-                # set start time for first download in queue
-                # status of first download must not be complete
-                for gid in gid_list:
-
-                    # get download information dictionary
-                    dictionary = download_table_dict[gid]
-
-                    # find status of download
-                    status = dictionary['status']
-
-                    if status != DownloadStatus.Complete:
-                        # We find first item! GREAT!
-                        add_link_dict = {'gid': gid}
-
-                        # set start_time for this download
-                        add_link_dict['start_time'] = self.start_time
+        # if gid is related to video finder, so start  Video Finder thread for checking status
+        # check video_finder_threads_dict, perhaps a thread started before for this gid
+        for gid in self.parent.persepolis_db.gidListOfCategory(self.category):
+            if gid in self.parent.all_video_finder_gid_list:
 
-                        # write changes in data base
-                        self.parent.persepolis_db.updateAddLinkTable([add_link_dict])
+                video_finder_dictionary = self.parent.persepolis_db.searchGidInVideoFinderTable(gid)
 
-                        # delete add_link_dict
-                        del add_link_dict
+                if video_finder_dictionary['video_gid'] not in self.parent.video_finder_threads_dict:
 
-                        # job is done! break the loop
-                        break
-
-            for gid in gid_list:
-
-                # if gid is related to video finder, so start  Video Finder thread for checking status
-                # check video_finder_threads_dict, perhaps a thread started before for this gid
-                if gid in self.parent.all_video_finder_gid_list:
-
-                    video_finder_dictionary = self.parent.persepolis_db.searchGidInVideoFinderTable(gid)
-
-                    if video_finder_dictionary['video_gid'] not in self.parent.video_finder_threads_dict:
+                    # start new video finder thread
+                    video_finder_gid_list = [video_finder_dictionary['video_gid'],
+                                             video_finder_dictionary['audio_gid']]
 
-                        # start new video finder thread
-                        video_finder_gid_list = [video_finder_dictionary['video_gid'],
-                                                 video_finder_dictionary['audio_gid']]
+                    new_video_finder = VideoFinder(video_finder_dictionary, self.parent)
+                    self.parent.threadPool.append(new_video_finder)
+                    self.parent.threadPool[-1].start()
+                    self.parent.threadPool[-1].VIDEOFINDERCOMPLETED.connect(self.parent.videoFinderCompleted)
 
-                        new_video_finder = VideoFinder(video_finder_dictionary, self.parent)
-                        self.parent.threadPool.append(new_video_finder)
-                        self.parent.threadPool[-1].start()
-                        self.parent.threadPool[-1].VIDEOFINDERCOMPLETED.connect(self.parent.videoFinderCompleted)
+                    # add thread to video_finder_threads_dict
+                    self.parent.video_finder_threads_dict[video_finder_dictionary['video_gid']] = new_video_finder
 
-                        # add thread to video_finder_threads_dict
-                        self.parent.video_finder_threads_dict[video_finder_dictionary['video_gid']] = new_video_finder
+                    video_finder_list.append(video_finder_gid_list)
 
-                        video_finder_list.append(video_finder_gid_list)
+        # check that if user set start time
+        if self.start_time:
+            self.waitForStartTime()
 
-                add_link_dict = {'gid': gid}
+        # user can set sleep time between download items in queue.
+        # see preferences window!
+        wait_queue_list = self.parent.persepolis_setting.value('settings/wait-queue')
+        wait_queue = int(wait_queue_list[0]) * 3600 + int(wait_queue_list[1]) * 60
 
-                # find download information
-                dictionary = download_table_dict[gid]
+        settings = {
+            'max-tries': str(self.parent.persepolis_setting.value('settings/max-tries')),
+            'retry-wait': str(self.parent.persepolis_setting.value('settings/retry-wait')),
+            'timeout': str(self.parent.persepolis_setting.value('settings/timeout')),
+            'dont-check-certificate': str(self.parent.persepolis_setting.value('settings/dont-check-certificate')),
+            'download_path_temp': str(self.parent.persepolis_setting.value('settings/download_path_temp')),
+        }
 
-                # if download was completed, continue the loop
-                # with the next iteration of the loop!
-                # We don't want to download it two times :)
-                if dictionary['status'] == DownloadStatus.Complete:
-                    continue
+        if not(self.stop):
+            runner = ghermez.QueueRunner(self.parent.persepolis_db, self.category, settings, wait=wait_queue)
+            runner.start()
 
-                queue_counter = queue_counter + 1
-
-                # change status of download to waiting
-                status = DownloadStatus.Waiting
-                dictionary['status'] = status
+            # wait until QueueRunner sends queue_complete or queue_stopped event.
+            stop_sent = False
+            while self.queue_event is None:
+                sleep(1)
+                if self.stop and not(stop_sent):
+                    # it means user stopped queue
+                    runner.stop()
+                    stop_sent = True
+
+                if self.limit_changed:
+                    # user limited speed or canceled limitation
+                    self.writeLimit()
+                    self.limit_changed = False
 
-                if self.end_time:
+        # it means user stopped queue
+        if self.queue_event != 'queue_complete':
 
-                    # it means user was set end time for download
-                    # set end_hour and end_minute
-                    add_link_dict['end_time'] = self.end_time
+            for video_finder_gid_list in video_finder_list:
 
-                # user can set sleep time between download items in queue.
-                # see preferences window!
-                # find wait_queue value
-                wait_queue_list = self.parent.persepolis_setting.value('settings/wait-queue')
-                wait_queue_hour = int(wait_queue_list[0])
-                wait_queue_minute = int(wait_queue_list[1])
+                video_gid = video_finder_gid_list[0]
 
-                # check if user set sleep time between downloads in queue in setting window.
-                # if queue_counter is 1 , it means we are in the first download item in queue.
-                # and no need to wait for first item.
-                if (wait_queue_hour != 0 or wait_queue_minute != 0) and queue_counter != 1:
-                    now_time_hour = int(time.strftime('%H'))
-                    now_time_minute = int(time.strftime('%M'))
-                    now_time_second = int(time.strftime('%S'))
+                video_finder_dictionary = self.parent.persepolis_db.searchGidInVideoFinderTable(video_gid)
 
-                    # add extra minute if we are in second half of minute
-                    if now_time_second > 30:  # noqa: PLR2004
-                        now_time_minute = now_time_minute + 1
+                # tell video finder thread to stop checking
+                if video_finder_dictionary and (video_finder_dictionary['video_completed'] == 'no' or
+                                                video_finder_dictionary['audio_completed'] == 'no'):
 
-                    # hour value can not be more than 23 and minute value can not be more than 59.
-                    sigma_minute = wait_queue_minute + now_time_minute
-                    sigma_hour = wait_queue_hour + now_time_hour
-                    if sigma_minute > 59:  # noqa: PLR2004
-                        sigma_minute = sigma_minute - 60
-                        sigma_hour = sigma_hour + 1
+                    video_finder_dictionary['checking'] = 'no'
+                    self.parent.persepolis_db.updateVideoFinderTable([video_finder_dictionary])
 
-                    if sigma_hour > 23:  # noqa: PLR2004
-                        sigma_hour = sigma_hour - 24
+                    video_finder_thread = self.parent.video_finder_threads_dict[video_gid]
+                    video_finder_thread.checking = 'no'
 
-                    # setting sigma_hour and sigma_minute for download's start time!
-                    add_link_dict['start_time'] = str(sigma_hour) + ':' + str(sigma_minute)
+            if self.after:
+                # It means user activated shutdown before and now user
+                # stopped queue . so after download must be canceled
+                self.parent.after_checkBox.setChecked(False)
 
-                # write changes in data base
-                self.parent.persepolis_db.updateAddLinkTable([add_link_dict])
+            self.start = False
+            self.stop = True
+            self.limit = False
+            self.limit_changed = False
 
-                # delete add_link_dict
-                del add_link_dict
+            if str(self.parent.category_tree.currentIndex().data()) == str(self.category):
+                self.REFRESHTOOLBARSIGNAL.emit(self.category)
 
-                # start new thread for download
-                new_download = DownloadLink(gid, self.parent)
-                self.parent.threadPool.append(new_download)
-                self.parent.threadPool[-1].start()
-                self.parent.threadPool[-1].ARIA2NOTRESPOND.connect(self.parent.aria2NotRespond)
-                sleep(3)
+            # show notification
+            notifySend(QCoreApplication.translate('mainwindow_src_ui_tr', APP_NAME.capitalize()),
+                       QCoreApplication.translate('mainwindow_src_ui_tr', 'Queue Stopped!'),
+                       10000, 'no', parent=self.parent)
 
-                # limit download speed if user limited speed for previous download
-                if self.limit:
-                    self.limit_changed = True
+            # write message in log
+            ghermez.sendToLog('Queue stopped', 'INFO')
 
-                # continue loop until download has finished
-                while status in (DownloadStatus.Downloading,
-                                 DownloadStatus.Waiting,
-                                 DownloadStatus.Paused,
-                                 DownloadStatus.Scheduled):
+            return
 
+        # if queue finished :
+        self.start = False
+
+        # this section is sending shutdown signal to the shutdown script(if user
+        # select shutdown for after download)
+        if self.after:
+            # downloads were completed and video finder started Muxing
+            # wait until the end of muxing
+            # don't turn of the computer.
+            for video_finder_gid_list in video_finder_list:
+                video_finder_thread = self.parent.video_finder_threads_dict.get(video_finder_gid_list[0])
+                while video_finder_thread and video_finder_thread.active == 'yes':
                     sleep(1)
-                    dictionary = self.parent.persepolis_db.searchGidInDownloadTable(gid)
-
-                    status = dictionary['status']
-
-                    if status == DownloadStatus.Error:
-                        error = 'error'
-                        # write error_message in log file
-                        error_message = 'Download failed - GID : '\
-                            + str(gid)\
-                            + '- Message : '\
-                            + error
-
-                        ghermez.sendToLog(error_message, 'ERROR')
-
-                    elif status == DownloadStatus.Complete:
-                        complete_message = 'Download complete - GID : '\
-                            + str(gid)
-
-                        # write in log the complete_message
-                        ghermez.sendToLog(complete_message, 'INFO')
-
-                        # check that is this related to video finder thread or not.
-                        if gid in self.parent.all_video_finder_gid_list:
-
-                            # find related thread
-                            for video_list in video_finder_list:
-
-                                if gid in video_list:
-
-                                    video_gid = video_list[0]
-
-                                    if video_gid in self.parent.video_finder_threads_dict:
-                                        video_finder_thread = self.parent.video_finder_threads_dict[video_gid]
-
-                                        # check the video and audio and muxing_status
-                                        if video_finder_thread.video_completed == 'yes' and \
-                                        video_finder_thread.audio_completed == 'yes':
-
-                                            # wait until end of muxing
-                                            while video_finder_thread.active == 'yes':
-                                                sleep(0.5)
-
-                                break
-
-                    if self.stop:
-                        # it means user stopped queue
-                        answer = download.downloadStop(gid, self.parent)
-
-                        # if aria2 did not respond , then this function is checking
-                        # for aria2 availability , and if aria2 disconnected then
-                        # aria2Disconnected is executed
-                        if answer == 'None':
-                            version_answer = ghermez.aria2Version()
-                            if version_answer == 'did not respond':
-                                self.parent.aria2Disconnected()
-                        status = DownloadStatus.Stopped
-
-                    if self.limit and status == DownloadStatus.Downloading and self.limit_changed:
-                        # It means user want to limit download speed
-                        # get limitation value
-                        self.limit_comboBox_value = self.parent.limit_comboBox.currentText()
-                        self.limit_spinBox_value = self.parent.limit_spinBox.value()
-                        if self.limit_comboBox_value == 'KiB/s':
-                            limit = str(self.limit_spinBox_value) + 'K'
-                        else:
-                            limit = str(self.limit_spinBox_value) + 'M'
-
-                        # apply limitation
-                        ghermez.limitSpeed(gid, limit)
-
-                        # done!
-                        self.limit_changed = False
-
-                    if not(self.limit) and status == DownloadStatus.Downloading and self.limit_changed:
-                        # speed limitation is canceled by user!
-                        # cancel limitation
-                        ghermez.limitSpeed(gid, '0')
-
-                        # done!
-                        self.limit_changed = False
-
-                # it means queue stopped at end time or user stopped queue
-                if status == DownloadStatus.Stopped:
 
-                    for video_finder_gid_list in video_finder_list:
+            # shutdown aria2c
+            answer = ghermez.shutDown()
 
-                        video_gid = video_finder_gid_list[0]
+            # KILL aria2c if didn't respond. R.I.P :))
+            if not(answer) and (os_type != OS.WINDOWS):
 
-                        video_finder_dictionary = self.parent.persepolis_db.searchGidInVideoFinderTable(video_gid)
+                subprocess.Popen(['killall', 'aria2c'],
+                                 stderr=subprocess.PIPE,
+                                 stdout=subprocess.PIPE,
+                                 stdin=subprocess.PIPE,
+                                 shell=False)
 
-                        if video_finder_dictionary:
+            # write 'shutdown' value for this category in temp_db
+            shutdown_dict = {'category': self.category, 'shutdown': 'shutdown'}
+            self.parent.temp_db.updateQueueTable(shutdown_dict)
 
-                            # tell video finder thread to stop checking
-                            if video_finder_dictionary['video_completed'] == 'no' or \
-                                video_finder_dictionary['audio_completed'] == 'no':
+            # show a notification about system is shutting down now!
+            notifySend(QCoreApplication.translate('mainwindow_src_ui_tr', 'Persepolis is shutting down'),
+                       QCoreApplication.translate('mainwindow_src_ui_tr', 'your system in 20 seconds'),
+                       15000, 'warning', parent=self.parent)
 
-                                video_finder_dictionary['checking'] = 'no'
-                                self.parent.persepolis_db.updateVideoFinderTable([video_finder_dictionary])
+        # show notification for queue completion
+        notifySend(QCoreApplication.translate('mainwindow_src_ui_tr', APP_NAME.capitalize()),
+                   QCoreApplication.translate('mainwindow_src_ui_tr', 'Queue completed!'),
+                   10000, 'queue', parent=self.parent)
 
-                                video_finder_thread = self.parent.video_finder_threads_dict[video_gid]
-                                video_finder_thread.checking = 'no'
+        # write a message in log
+        ghermez.sendToLog('Queue completed', 'INFO')
 
-                            elif not(self.stop) and self.after and \
-                                video_finder_dictionary['muxing_status'] == 'started':
-                                # downloads were completed and video finder started Muxing
-                                # wait until the end of muxing
-                                # don't turn of the computer.
-                                # video finder will be deleted from data base when muxing ended.
-                                # so check data base every second
-
-                                video_finder_thread = self.parent.video_finder_threads_dict[
-                                    video_finder_dictionary['video_gid']
-                                ]
-
-                                while video_finder_thread.active == 'yes':
-                                    sleep(1)
-
-                    if self.stop and self.after:
-                        # It means user activated shutdown before and now user
-                        # stopped queue . so after download must be canceled
-                        self.parent.after_checkBox.setChecked(False)
-
-                    self.stop = True
-                    self.limit = False
-                    self.limit_changed = False
-
-                    # it means that break outer "for" loop
-                    self.break_for_loop = True
-
-                    if str(self.parent.category_tree.currentIndex().data()) == str(self.category):
-                        self.REFRESHTOOLBARSIGNAL.emit(self.category)
-
-                    # show notification
-                    notifySend(QCoreApplication.translate('mainwindow_src_ui_tr', APP_NAME.capitalize()),
-                               QCoreApplication.translate('mainwindow_src_ui_tr', 'Queue Stopped!'),
-                               10000, 'no', parent=self.parent)
-
-                    # write message in log
-                    ghermez.sendToLog('Queue stopped', 'INFO')
-
-                    break
-
-            if self.break_for_loop:
-                break
-
-        if self.start:
-            # if queue finished :
-            self.start = False
-
-            # this section is sending shutdown signal to the shutdown script(if user
-            # select shutdown for after download)
-            if self.after:
-                # shutdown aria2c
-                answer = ghermez.shutDown()
-
-                # KILL aria2c if didn't respond. R.I.P :))
-                if not(answer) and (os_type != OS.WINDOWS):
-
-                    subprocess.Popen(['killall', 'aria2c'],
-                                     stderr=subprocess.PIPE,
-                                     stdout=subprocess.PIPE,
-                                     stdin=subprocess.PIPE,
-                                     shell=False)
-
-                # write 'shutdown' value for this category in temp_db
-                shutdown_dict = {'category': self.category, 'shutdown': 'shutdown'}
-                self.parent.temp_db.updateQueueTable(shutdown_dict)
-
-                # show a notification about system is shutting down now!
-                notifySend(QCoreApplication.translate('mainwindow_src_ui_tr', 'Persepolis is shutting down'),
-                           QCoreApplication.translate('mainwindow_src_ui_tr', 'your system in 20 seconds'),
-                           15000, 'warning', parent=self.parent)
-
-            # show notification for queue completion
-            notifySend(QCoreApplication.translate('mainwindow_src_ui_tr', APP_NAME.capitalize()),
-                       QCoreApplication.translate('mainwindow_src_ui_tr', 'Queue completed!'),
-                       10000, 'queue', parent=self.parent)
-
-            # write a message in log
-            ghermez.sendToLog('Queue completed', 'INFO')
-
-            self.stop = True
-            self.limit = False
-            self.limit_changed = False
-            self.after = False
+        self.stop = True
+        self.limit = False
+        self.limit_changed = False
+        self.after = False
 
-            if str(self.parent.category_tree.currentIndex().data()) == str(self.category):
-                self.REFRESHTOOLBARSIGNAL.emit(self.category)
+        if str(self.parent.category_tree.currentIndex().data()) == str(self.category):
+            self.REFRESHTOOLBARSIGNAL.emit(self.category)
 
 
 # CheckingThread have 2 duty!
@@ -1156,7 +1036,11 @@ class MoveThread(QThread):
             # find file_name
             self.file_name = os.path.basename(self.old_file_path)
 
-            self.move = ghermez.moveFile(self.old_file_path, self.new_folder_path)
+            try:
+                self.move = ghermez.moveFile(self.old_file_path, self.new_folder_path)
+            except ghermez.IoError as error:
+                ghermez.sendToLog(str(error), 'ERROR')
+                self.move = False
 
             # if moving is not successful, notify user.
             if not(self.move):
@@ -1296,6 +1180,29 @@ class MainWindow(MainWindow_Ui):
         # see data_base.py for more information.
         self.persepolis_db.setDBTablesToDefaultValue()
 
+        # start ghermez scheduler for start time and end time of downloads and queues.
+        ghermez.startScheduler(self.persepolis_db)
+
+        # failed downloads are sent to aria2 again by ghermez, if error is temporary.
+        # retried downloads continue in the same folder, so download_path_temp is a fallback.
+        ghermez.configureRetry(self.persepolis_db, {
+            'max-tries': str(self.persepolis_setting.value('settings/max-tries')),
+            'retry-wait': str(self.persepolis_setting.value('settings/retry-wait')),
+            'timeout': str(self.persepolis_setting.value('settings/timeout')),
+            'dont-check-certificate': str(self.persepolis_setting.value('settings/dont-check-certificate')),
+            'download_path_temp': str(self.persepolis_setting.value('settings/download_path_temp')),
+        })
+
+        # downloads are sent to aria2 by ghermez download pool, when there is a free slot for them.
+        try:
+            ghermez.configurePool(
+                self.persepolis_db,
+                int(self.persepolis_setting.value('settings/max-active-downloads')),
+                int(self.persepolis_setting.value('settings/downloads-per-host')))
+        except ghermez.GhermezError:
+            # ghermez writes error message in log.
+            pass
+
         # get queues name from data base
         queues_list = self.persepolis_db.categoriesList()
 
@@ -1315,8 +1222,7 @@ class MainWindow(MainWindow_Ui):
         download_table_dict = self.persepolis_db.returnItemsInDownloadTable()
 
         # read gid_list from date base
-        category_dict = self.persepolis_db.searchCategoryInCategoryTable('All Downloads')
-        gid_list = literal_eval(category_dict['gid_list'])
+        gid_list = self.persepolis_db.gidListOfCategory('All Downloads')
 
         keys_list = ['file_name',
                      'status',
@@ -1616,10 +1522,9 @@ class MainWindow(MainWindow_Ui):
 
     def reconnectAria(self, message):
         # this function is executing if RECONNECTARIASIGNAL is emitted by CheckingThread .
-        # if message is 'did not respond' then a message(Persepolis can not connect to Aria2) shown
-        # if message is not 'did not respond' , it means that reconnecting
-        # Aria2 was successful.
-        if message == 'did not respond':
+        # message is version of aria2 if reconnecting Aria2 was successful.
+        # if message is empty, then a message(Persepolis can not connect to Aria2) shown.
+        if not message:
             self.statusbar.showMessage(QCoreApplication.translate('mainwindow_src_ui_tr', 'Error...'))
             notifySend(QCoreApplication.translate('mainwindow_src_ui_tr', 'Persepolis can not connect to Aria2'),
                        QCoreApplication.translate('mainwindow_src_ui_tr', 'Restart Persepolis'),
@@ -2139,8 +2044,7 @@ class MainWindow(MainWindow_Ui):
             my_gid = str(my_gid)
 
             # check my_gid used before or not!
-            category_dict = self.persepolis_db.searchCategoryInCategoryTable('All Downloads')
-            gid_list = literal_eval(category_dict['gid_list'])
+            gid_list = self.persepolis_db.gidListOfCategory('All Downloads')
 
             if my_gid not in gid_list:
                 break
@@ -2569,8 +2473,9 @@ class MainWindow(MainWindow_Ui):
 
         status = DownloadStatus.Waiting if not download_later else DownloadStatus.Stopped
 
-        # get now time and date
+        # get now time and date, data base saves it in unix time
         date = ghermez.nowDate()
+        now = download.nowTimestamp()
 
         download_dict = {'file_name': file_name,
                 'status': status,
@@ -2582,8 +2487,8 @@ class MainWindow(MainWindow_Ui):
                 'estimate_time_left': '***',
                 'gid': gid,
                 'link': add_link_dictionary['link'],
-                'first_try_date': date,
-                'last_try_date': date,
+                'first_try_date': now,
+                'last_try_date': now,
                 'category': category}
 
         # write information in data_base
@@ -2679,14 +2584,13 @@ class MainWindow(MainWindow_Ui):
             # download thread must be created !
             if download_status == DownloadStatus.Paused:
 
-                answer = ghermez.downloadUnpause(gid)
+                answer = download.downloadUnpause(gid)
 
                 # if aria2 did not respond , then this function checks for aria2
                 # availability , and if aria2 disconnected then aria2Disconnected is
                 # called.
                 if not(answer):
-                    version_answer = ghermez.aria2Version()
-                    if version_answer == 'did not respond':
+                    if not download.aria2Responds():
                         self.aria2Disconnected()
                         notifySend(QCoreApplication.translate('mainwindow_src_ui_tr', 'Aria2 disconnected!'),
                                    QCoreApplication.translate('mainwindow_src_ui_tr',
@@ -2795,9 +2699,8 @@ class MainWindow(MainWindow_Ui):
             # if aria2 did not respond , then this function is checking for aria2
             # availability , and if aria2 disconnected then aria2Disconnected is
             # executed
-            if answer == 'None':
-                version_answer = ghermez.aria2Version()
-                if version_answer == 'did not respond':
+            if not answer:
+                if not download.aria2Responds():
                     self.aria2Disconnected()
                     notifySend(QCoreApplication.translate('mainwindow_src_ui_tr', 'Aria2 disconnected!'),
                                QCoreApplication.translate('mainwindow_src_ui_tr',
@@ -2828,13 +2731,12 @@ class MainWindow(MainWindow_Ui):
             gid = self.download_table.item(selected_row_return, 8).text()
 
             # send pause request to aria2
-            answer = ghermez.downloadPause(gid)
+            answer = download.downloadPause(gid)
 
             # if aria2 did not respond , then check aria2 availability!
             # and if aria2 disconnected then call aria2Disconnected
             if not(answer):
-                version_answer = ghermez.aria2Version()
-                if version_answer == 'did not respond':
+                if not download.aria2Responds():
                     self.aria2Disconnected()
                     download.downloadStop(gid, self)
                     notifySend(QCoreApplication.translate('mainwindow_src_ui_tr', 'Aria2 disconnected!'),
@@ -3196,9 +3098,8 @@ class MainWindow(MainWindow_Ui):
             # if aria2 did not respond , then this function is checking for
             # aria2 availability , and if aria2 disconnected then
             # aria2Disconnected is executed
-            if answer == 'None':
-                version_answer = ghermez.aria2Version()
-                if version_answer == 'did not respond':
+            if not answer:
+                if not download.aria2Responds():
                     self.aria2Disconnected()
 
     # this method creates Preferences window
@@ -3455,10 +3356,14 @@ class MainWindow(MainWindow_Ui):
             if file_name != '***' and status != DownloadStatus.Complete:
                 file_name_path = os.path.join(
                     globals.temp_download_folder,  str(file_name))
-                ghermez.remove(file_name_path)  # remove file
-
                 file_name_aria = file_name_path + '.aria2'
-                ghermez.remove(file_name_aria)  # remove file.aria
+
+                # remove file and file.aria
+                for path in [file_name_path, file_name_aria]:
+                    try:
+                        ghermez.remove(path)
+                    except ghermez.IoError as error:
+                        ghermez.sendToLog(str(error), 'ERROR')
 
         # tell the CheckDownloadInfoThread that job is done!
         globals.checking_flag = CheckingFlag.Normal
@@ -3604,11 +3509,13 @@ class MainWindow(MainWindow_Ui):
                     globals.temp_download_folder, str(file_name))
 
                 # remove file : file_name_path
-                ghermez.remove(file_name_path)
-
-                # remove aria2 download information file : file_name_aria
+                # and aria2 download information file : file_name_aria
                 file_name_aria = file_name_path + '.aria2'
-                ghermez.remove(file_name_aria)
+                for path in [file_name_path, file_name_aria]:
+                    try:
+                        ghermez.remove(path)
+                    except ghermez.IoError as error:
+                        ghermez.sendToLog(str(error), 'ERROR')
 
             # remove downloaded file, if download is completed
             if status == DownloadStatus.Complete:
@@ -3618,11 +3525,17 @@ class MainWindow(MainWindow_Ui):
                 if dictionary:
                     file_path = dictionary['download_path']
 
-                    remove_answer = ghermez.remove(file_path)
-
-                    if remove_answer == 'no':
-                        notifySend(str(file_path), QCoreApplication.translate('mainwindow_src_ui_tr', 'Not Found'),
+                    try:
+                        removed = ghermez.remove(file_path)
+                    except ghermez.IoError as error:
+                        ghermez.sendToLog(str(error), 'ERROR')
+                        notifySend(str(file_path),
+                                   QCoreApplication.translate('mainwindow_src_ui_tr', 'Operation was not successful!'),
                                    5000, 'warning', parent=self)
+                    else:
+                        if not removed:
+                            notifySend(str(file_path), QCoreApplication.translate('mainwindow_src_ui_tr', 'Not Found'),
+                                       5000, 'warning', parent=self)
 
             # remove row from download_table
             self.download_table.removeRow(row)
@@ -3700,14 +3613,8 @@ class MainWindow(MainWindow_Ui):
             j = j + 1
 
         # save sorted list (gid_sorted_list) in data base
-        category_dict = {'category': current_category_tree_text}
-
-        # update gid_list
         gid_sorted_list.reverse()
-        category_dict['gid_list'] = gid_sorted_list
-
-        # update category_db_table
-        self.persepolis_db.updateCategoryTable([category_dict])
+        self.persepolis_db.updateGidListOfCategory(current_category_tree_text, gid_sorted_list)
 
         # tell the CheckDownloadInfoThread that job is done!
         globals.checking_flag = CheckingFlag.Normal
@@ -3798,15 +3705,8 @@ class MainWindow(MainWindow_Ui):
             j = j + 1
 
         # save sorted list (gid_sorted_list) in data base
-        category_dict = {'category': current_category_tree_text}
-
-        # update gid_list
         gid_sorted_list.reverse()
-
-        category_dict['gid_list'] = gid_sorted_list
-
-        # update category_db_table
-        self.persepolis_db.updateCategoryTable([category_dict])
+        self.persepolis_db.updateGidListOfCategory(current_category_tree_text, gid_sorted_list)
 
         # tell the CheckDownloadInfoThread that job is done!
         globals.checking_flag = CheckingFlag.Normal
@@ -3893,15 +3793,8 @@ class MainWindow(MainWindow_Ui):
             j = j + 1
 
         # save sorted list (gid_sorted_list) in data base
-        category_dict = {'category': current_category_tree_text}
-
-        # update gid_list
         gid_sorted_list.reverse()
-
-        category_dict['gid_list'] = gid_sorted_list
-
-        # update category_db_table
-        self.persepolis_db.updateCategoryTable([category_dict])
+        self.persepolis_db.updateGidListOfCategory(current_category_tree_text, gid_sorted_list)
 
         # tell the CheckDownloadInfoThread that job is done!
         globals.checking_flag = CheckingFlag.Normal
@@ -3987,15 +3880,8 @@ class MainWindow(MainWindow_Ui):
             j = j + 1
 
         # save sorted list (gid_list) in data base
-        category_dict = {'category': current_category_tree_text}
-
-        # update gid_sorted_list
         gid_sorted_list.reverse()
-
-        category_dict['gid_list'] = gid_sorted_list
-
-        # update category_db_table
-        self.persepolis_db.updateCategoryTable([category_dict])
+        self.persepolis_db.updateGidListOfCategory(current_category_tree_text, gid_sorted_list)
 
         # tell the CheckDownloadInfoThread that job is done!
         globals.checking_flag = CheckingFlag.Normal
@@ -4084,15 +3970,8 @@ class MainWindow(MainWindow_Ui):
             j = j + 1
 
         # save sorted list (gid_list) in data base
-        category_dict = {'category': current_category_tree_text}
-
-        # update gid_sorted_list
         gid_sorted_list.reverse()
-
-        category_dict['gid_list'] = gid_sorted_list
-
-        # update category_db_table
-        self.persepolis_db.updateCategoryTable([category_dict])
+        self.persepolis_db.updateGidListOfCategory(current_category_tree_text, gid_sorted_list)
 
         # tell the CheckDownloadInfoThread that job is done!
         globals.checking_flag = CheckingFlag.Normal
@@ -4137,7 +4016,6 @@ class MainWindow(MainWindow_Ui):
                     'limit_enable': 'no',
                     'limit_value': '0K',
                     'after_download': 'no',
-                    'gid_list': '[]',
                     }
 
             # insert new category in data base
@@ -4218,8 +4096,9 @@ class MainWindow(MainWindow_Ui):
 
         download_table_list = []
 
-        # get now time and date
+        # get now time and date, data base saves it in unix time
         date = ghermez.nowDate()
+        now = download.nowTimestamp()
 
         # add dictionary of downloads to data base
         for add_link_dictionary in add_link_dictionary_list:
@@ -4253,8 +4132,8 @@ class MainWindow(MainWindow_Ui):
                           'estimate_time_left': '***',
                           'gid': gid,
                           'link': add_link_dictionary['link'],
-                          'first_try_date': date,
-                          'last_try_date': date,
+                          'first_try_date': now,
+                          'last_try_date': now,
                           'category': category}
 
             download_table_dict_list.append(dictionary)
@@ -4371,8 +4250,7 @@ class MainWindow(MainWindow_Ui):
             download_table_dict = self.persepolis_db.returnItemsInDownloadTable(current_category_tree_text)
 
         # get gid_list
-        category_dict = self.persepolis_db.searchCategoryInCategoryTable(current_category_tree_text)
-        gid_list = literal_eval(category_dict['gid_list'])
+        gid_list = self.persepolis_db.gidListOfCategory(current_category_tree_text)
 
         keys_list = ['file_name',
                      'status',
@@ -4665,19 +4543,18 @@ class MainWindow(MainWindow_Ui):
 
             # read start_time value
             start_time = self.start_time_qDataTimeEdit.text()
+            queue_info_dict['start_time'] = str(start_time)
         else:
             queue_info_dict['start_time_enable'] = 'no'
             start_time = None
 
         # check that if user checked end_checkBox or not.
+        # ghermez scheduler reads start_time and end_time from data base.
         if self.end_checkBox.isChecked():
             queue_info_dict['end_time_enable'] = 'yes'
-
-            # read end_time value
-            end_time = self.end_time_qDateTimeEdit.text()
+            queue_info_dict['end_time'] = str(self.end_time_qDateTimeEdit.text())
         else:
             queue_info_dict['end_time_enable'] = 'no'
-            end_time = None
 
         # reverse_checkBox
         if self.reverse_checkBox.isChecked():
@@ -4689,8 +4566,7 @@ class MainWindow(MainWindow_Ui):
         self.persepolis_db.updateCategoryTable([queue_info_dict])
 
         # create new Queue thread
-        new_queue = Queue(current_category_tree_text, start_time,
-                          end_time, self)
+        new_queue = Queue(current_category_tree_text, start_time, self)
 
         self.queue_list_dict[current_category_tree_text] = new_queue
         self.queue_list_dict[current_category_tree_text].start()
@@ -4797,31 +4673,6 @@ class MainWindow(MainWindow_Ui):
                 self.persepolis_db.updateDownloadTable([download_dict])
                 self.persepolis_db.setDefaultGidInAddlinkTable(gid, start_time=True, end_time=True, after_download=True)
 
-                # delete item from gid_list in current_category
-                current_category_dict = self.persepolis_db.searchCategoryInCategoryTable(current_category)
-
-                # get gid_list
-                current_category_gid_list = literal_eval(current_category_dict['gid_list'])
-
-                # delete item
-                current_category_gid_list = current_category_gid_list.remove(gid)
-
-                # update category_db_table
-                self.persepolis_db.updateCategoryTable([current_category_dict])
-
-                # add item to gid_list of new_category
-                # get category_dict from data base
-                new_category_dict = self.persepolis_db.searchCategoryInCategoryTable(new_category)
-
-                # get gid_list
-                new_category_gid_list = literal_eval(new_category_dict['gid_list'])
-
-                # add gid of item to gid_list
-                new_category_gid_list = new_category_gid_list.append(gid)
-
-                # update category_db_table
-                self.persepolis_db.updateCategoryTable([new_category_dict])
-
                 # update category in download_table
                 current_category_tree_text = str(globals.current_category_tree_index.data())
 
@@ -5157,9 +5008,7 @@ class MainWindow(MainWindow_Ui):
         current_category_tree_text = str(globals.current_category_tree_index.data())
 
         # get gid_list from data base
-        category_dict = self.persepolis_db.searchCategoryInCategoryTable(current_category_tree_text)
-
-        gid_list = literal_eval(category_dict['gid_list'])
+        gid_list = self.persepolis_db.gidListOfCategory(current_category_tree_text)
 
         # find selected rows
         rows_list = self.userSelectedRows()
@@ -5217,7 +5066,7 @@ class MainWindow(MainWindow_Ui):
         self.download_table.setSelectionMode(QAbstractItemView.ExtendedSelection)
 
         # update data base
-        self.persepolis_db.updateCategoryTable([category_dict])
+        self.persepolis_db.updateGidListOfCategory(current_category_tree_text, gid_list)
 
     # this method is called if user pressed moveDownSelected action
     # this method is substituting selected download item with lower download item
@@ -5248,10 +5097,7 @@ class MainWindow(MainWindow_Ui):
         current_category_tree_text = str(globals.current_category_tree_index.data())
 
         # get gid_list from data base
-        category_dict = self.persepolis_db.searchCategoryInCategoryTable(
-            current_category_tree_text)
-
-        gid_list = literal_eval(category_dict['gid_list'])
+        gid_list = self.persepolis_db.gidListOfCategory(current_category_tree_text)
 
         rows_list.reverse()
 
@@ -5310,7 +5156,7 @@ class MainWindow(MainWindow_Ui):
         self.download_table.setSelectionMode(QAbstractItemView.ExtendedSelection)
 
         # update data base
-        self.persepolis_db.updateCategoryTable([category_dict])
+        self.persepolis_db.updateGidListOfCategory(current_category_tree_text, gid_list)
 
     # this method is called if user pressed moveSelectedDownloads action
     # this method moves download files to another destination.
@@ -5376,12 +5222,13 @@ class MainWindow(MainWindow_Ui):
 
     # see browser_plugin_queue.py file
 
-    def queueSpiderCallBack(self, filename, child, row_number):
+    def queueSpiderCallBack(self, filename, row_number, duplicate, child):
         item = QTableWidgetItem(str(filename))
 
         # add checkbox to the item
+        # duplicate links are in download list already, so they are unchecked.
         item.setFlags(Qt.ItemIsUserCheckable | Qt.ItemIsEnabled)
-        if child.links_table.item(int(row_number), 0).checkState() == 2:  # noqa: PLR2004
+        if child.links_table.item(int(row_number), 0).checkState() == 2 and not duplicate:  # noqa: PLR2004
             item.setCheckState(Qt.Checked)
         else:
             item.setCheckState(Qt.Unchecked)
@@ -5540,8 +5387,9 @@ class MainWindow(MainWindow_Ui):
             status = DownloadStatus.Waiting if not download_later and \
                 gid == add_link_dictionary_list[0]['gid'] else DownloadStatus.Stopped
 
-            # get now time and date
+            # get now time and date, data base saves it in unix time
             date = ghermez.nowDate()
+            now = download.nowTimestamp()
 
             dictionary = {'file_name': file_name,
                           'status': status,
@@ -5553,8 +5401,8 @@ class MainWindow(MainWindow_Ui):
                           'estimate_time_left': '***',
                           'gid': gid,
                           'link': add_link_dictionary['link'],
-                          'first_try_date': date,
-                          'last_try_date': date,
+                          'first_try_date': now,
+                          'last_try_date': now,
                           'category': category}
 
             # write information in data_base
@@ -5700,8 +5548,11 @@ class MainWindow(MainWindow_Ui):
             audio_file_path = audio_add_link_dictionary['download_path']
             video_file_path = video_add_link_dictionary['download_path']
 
-            remove_answer = ghermez.remove(audio_file_path)
-            remove_answer = ghermez.remove(video_file_path)  # noqa: F841
+            for path in [audio_file_path, video_file_path]:
+                try:
+                    ghermez.remove(path)
+                except ghermez.IoError as error:
+                    ghermez.sendToLog(str(error), 'ERROR')
 
             # remove audio row from download_table
             if row is not None:
@@ -5725,8 +5576,12 @@ class MainWindow(MainWindow_Ui):
             video_download_table_dict['file_name'] = urllib.parse.unquote(
                 os.path.basename(complete_dictionary['final_path']))
 
-            # update data base
-            self.persepolis_db.updateDownloadTable([video_download_table_dict])
+            # update data base, it saves size in byte
+            final_file_size = str(complete_dictionary['final_file_size'])
+            self.persepolis_db.updateDownloadTable([{'gid': complete_dictionary['video_gid'],
+                                                     'file_name': video_download_table_dict['file_name'],
+                                                     'size': final_file_size,
+                                                     'downloaded_size': final_file_size}])
 
             # update download_table
             # find row
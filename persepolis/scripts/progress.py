@@ -119,14 +119,13 @@ class ProgressWindow(ProgressWindow_Ui):
     def resumePushButtonPressed(self, _button: QPushButton) -> None:
 
         if self.status == DownloadStatus.Paused:
-            answer = ghermez.downloadUnpause(self.gid)
+            answer = download.downloadUnpause(self.gid)
 
             # if aria2 did not respond , then this function is checking for aria2
             # availability , and if aria2 disconnected then aria2Disconnected is
             # executed
             if not(answer):
-                version_answer = ghermez.aria2Version()
-                if version_answer == 'did not respond':
+                if not download.aria2Responds():
                     self.parent.aria2Disconnected()
                     notifySend(QCoreApplication.translate('progress_src_ui_tr', 'Aria2 disconnected!'),
                                QCoreApplication.translate('progress_src_ui_tr',
@@ -140,14 +139,13 @@ class ProgressWindow(ProgressWindow_Ui):
     def pausePushButtonPressed(self, _button: QPushButton) -> None:
 
         if self.status == DownloadStatus.Downloading:
-            answer = ghermez.downloadPause(self.gid)
+            answer = download.downloadPause(self.gid)
 
             # if aria2 did not respond , then this function is checking for aria2
             # availability , and if aria2 disconnected then aria2Disconnected is
             # executed
             if not(answer):
-                version_answer = ghermez.aria2Version()
-                if version_answer == 'did not respond':
+                if not download.aria2Responds():
                     self.parent.aria2Disconnected()
                     download.downloadStop(self.gid, self.parent)
                     notifySend('Aria2 disconnected!', 'Persepolis is trying to connect! be patient!',
@@ -169,9 +167,8 @@ class ProgressWindow(ProgressWindow_Ui):
         # if aria2 did not respond , then this function is checking for aria2
         # availability , and if aria2 disconnected then aria2Disconnected is
         # executed
-        if answer == 'None':
-            version_answer = ghermez.aria2Version()
-            if version_answer == 'did not respond':
+        if not answer:
+            if not download.aria2Responds():
                 self.parent.aria2Disconnected()
                 notifySend(QCoreApplication.translate('progress_src_ui_tr', 'Aria2 disconnected!'),
                            QCoreApplication.translate('progress_src_ui_tr',
@@ -192,7 +189,7 @@ class ProgressWindow(ProgressWindow_Ui):
             # check download status is "scheduled" or not!
             if self.status != DownloadStatus.Scheduled:
                 # tell aria2 for unlimited speed
-                ghermez.limitSpeed(self.gid, '0')
+                download.limitSpeed(self.gid, '0')
             else:
                 # update limit value in data_base
                 add_link_dictionary = {'gid': self.gid, 'limit_value': '0'}
@@ -293,7 +290,7 @@ class ProgressWindow(ProgressWindow_Ui):
         # if download was started before , send the limit_speed request to aria2 .
         # else save the request in data_base
         if self.status != DownloadStatus.Scheduled:
-            ghermez.limitSpeed(self.gid, limit_value)
+            download.limitSpeed(self.gid, limit_value)
         else:
             # update limit value in data_base
             add_link_dictionary = {'gid': self.gid, 'limit_value': limit_value}
@@ -26,6 +26,7 @@ except ImportError:
 
 import os
 
+import ghermez
 from persepolis.gui.addlink_ui import AddLinkWindow_Ui
 from persepolis.scripts.check_proxy import getProxy
 
@@ -241,7 +242,7 @@ class PropertiesWindow(AddLinkWindow_Ui):
             self.referer_lineEdit.setText(str(self.add_link_dictionary_1['referer']))
 
         if self.add_link_dictionary_1['header']:
-            self.header_lineEdit.setText(str(self.add_link_dictionary_1['header']))
+            self.header_lineEdit.setText(ghermez.headerText(str(self.add_link_dictionary_1['header'])))
 
         if self.add_link_dictionary_1['user_agent']:
             self.user_agent_lineEdit.setText(str(self.add_link_dictionary_1['user_agent']))
@@ -454,15 +455,15 @@ class PropertiesWindow(AddLinkWindow_Ui):
         # it means category changed and data base must be updated.
         if new_category != self.current_category:
 
-            self.download_table_dict_1['category'] = new_category
             # update data base
-            self.parent.persepolis_db.updateDownloadTable([self.download_table_dict_1])
+            dictionary = {'gid': self.gid_1, 'category': new_category}
+            self.parent.persepolis_db.updateDownloadTable([dictionary])
 
             if self.video_finder_dictionary:
 
                 # category for audio and video must be same as each other
-                self.download_table_dict_2['category'] = new_category
-                self.parent.persepolis_db.updateDownloadTable([self.download_table_dict_2])
+                dictionary = {'gid': self.gid_2, 'category': new_category}
+                self.parent.persepolis_db.updateDownloadTable([dictionary])
 
         # if any thing in add_link_dictionary_1 is changed,then update data base!
         for key in self.add_link_dictionary_1:
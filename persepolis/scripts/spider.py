@@ -13,202 +13,112 @@
 
 from __future__ import annotations
 
-from http.cookies import SimpleCookie
+from typing import Iterator
 
-import requests
-from requests.cookies import cookiejar_from_dict
+try:
+    from PySide6.QtCore import QThread, Signal
+except ImportError:
+    from PyQt5.QtCore import QThread
+    from PyQt5.QtCore import pyqtSignal as Signal
 
-from ghermez import humanReadableSize
+import ghermez
+from persepolis.scripts import logger
 
-# for more information about "requests" library , please see
-# http://docs.python-requests.org/en/master/
+# ghermez.spider sends a HEAD request (or a GET request for first byte, if server
+# refuses HEAD) with proxy, authentication, cookies, referer and user agent of
+# add_link_dictionary and follows redirects.
+# see ghermez.pyi for the returned dictionary.
 
 
-# spider function finds name of file and file size from header
-def spider(add_link_dictionary: dict[str, str]) -> tuple[str, str | None]:
-
-    # get user's download request from add_link_dictionary
-    link = add_link_dictionary['link']
-    ip = add_link_dictionary['ip']
-    port = add_link_dictionary['port']
-    proxy_user = add_link_dictionary['proxy_user']
-    proxy_passwd = add_link_dictionary['proxy_passwd']
-    download_user = add_link_dictionary['download_user']
-    download_passwd = add_link_dictionary['download_passwd']
-    header = add_link_dictionary['header']
-    out = add_link_dictionary['out']
-    user_agent = add_link_dictionary['user_agent']
-    raw_cookies = add_link_dictionary['load_cookies']
-    referer = add_link_dictionary['referer']
-
-    # define a requests session
-    requests_session = requests.Session()
-    if ip:
-        ip_port = 'http://' + str(ip) + ':' + str(port)
-        if proxy_user:
-            ip_port = 'http://' + proxy_user + ':' + proxy_passwd + '@' + ip_port
-        # set proxy to the session
-        requests_session.proxies = {'http': ip_port}
-
-    if download_user:
-        # set download user pass to the session
-        requests_session.auth = (download_user, download_passwd)
-
-    # set cookies
-    if raw_cookies:
-        cookie = SimpleCookie()
-        cookie.load(raw_cookies)
-
-        cookies = {key: morsel.value for key, morsel in cookie.items()}
-        requests_session.cookies = cookiejar_from_dict(cookies)
-
-    # set referer
-    if referer:
-        requests_session.headers.update({'referer': referer})  # setting referer to the session
-
-    # set user_agent
-    if user_agent:
-        requests_session.headers.update({'user-agent': user_agent})  # setting user_agent to the session
-
-    # find headers
+# ghermez.spider with empty values for missing keys.
+# it returns None if server couldn't be reached.
+def findHeaders(add_link_dictionary: dict[str, str]) -> ghermez.SpiderResult | None:
+    add_link_dict = {key: str(value) for key, value in add_link_dictionary.items() if value is not None}
     try:
-        response = requests_session.head(link)
-        header = response.headers
-    except Exception:
-        header = {}
-
-    filename = None
-    file_size = None
-    if 'Content-Disposition' in header:  # checking if filename is available
-        content_disposition = header['Content-Disposition']
-        if content_disposition.find('filename') != -1:
-            filename_splited = content_disposition.split('filename=')
-            filename_splited = filename_splited[-1]
+        return ghermez.spider(add_link_dict)
+    except ghermez.GhermezError:
+        return None
 
-            # getting file name in desired format
-            filename = filename_splited[1:-1]
 
-    if not(filename):
-        filename = link.split('/')[-1]
+# file name from last part of link
+def linkFileName(link: str) -> str:
+    return link.split('/')[-1]
 
-    # if user set file name before in add_link_dictionary['out'],
-    # then set "out" for filename
-    if out:
-        filename = out
 
-    # check if file_size is available
-    if 'Content-Length' in header:
-        file_size = int(header['Content-Length'])
+# spider function finds name of file and file size in byte from header
+def spider(add_link_dictionary: dict[str, str]) -> tuple[str, int | None]:
+    link = add_link_dictionary['link']
+    spider_result = findHeaders(add_link_dictionary)
 
-        # converting file_size to KiB or MiB or GiB
-        file_size = humanReadableSize(file_size)
+    if spider_result is None:
+        # if user set file name before in add_link_dictionary['out'],
+        # then set "out" for filename
+        return add_link_dictionary.get('out') or linkFileName(link), None
 
-    return filename, file_size
+    # ghermez uses "out" for file_name if user set it.
+    filename = spider_result['file_name'] or linkFileName(link)
+    return filename, spider_result['size']
 
 
 # this function finds and returns file name for links.
 def queueSpider(add_link_dictionary: dict[str, str]) -> str:
-    # get download information from add_link_dictionary
-    for i in ['link', 'header', 'out', 'user_agent', 'load_cookies', 'referer']:
-        if i not in add_link_dictionary:
-            add_link_dictionary[i] = None
+    # queue doesn't use proxy and download user of add_link_dictionary
+    keys = ['link', 'header', 'user_agent', 'load_cookies', 'referer']
+    spider_result = findHeaders({key: add_link_dictionary.get(key) for key in keys})
 
     link = add_link_dictionary['link']
-    header = add_link_dictionary['header']
-    user_agent = add_link_dictionary['user_agent']
-    raw_cookies = add_link_dictionary['load_cookies']
-    referer = add_link_dictionary['referer']
-
-    requests_session = requests.Session()  # defining a requests Session
-
-    if raw_cookies:  # set cookies
-        cookie = SimpleCookie()
-        cookie.load(raw_cookies)
-
-        cookies = {key: morsel.value for key, morsel in cookie.items()}
-        requests_session.cookies = cookiejar_from_dict(cookies)
-
-    if referer:
-        # set referer to the session
-        requests_session.headers.update({'referer': referer})
-
-    if user_agent:
-        # set user_agent to the session
-        requests_session.headers.update({'user-agent': user_agent})
-
-    # find headers
-    try:
-        response = requests_session.head(link)
-        header = response.headers
-    except Exception:
-        header = {}
-    filename = None
-    if 'Content-Disposition' in header:  # checking if filename is available
-        content_disposition = header['Content-Disposition']
-        if content_disposition.find('filename') != -1:
-            filename_splited = content_disposition.split('filename=')
-            filename_splited = filename_splited[-1]
-            # getting file name in desired format
-            filename = filename_splited[1:-1]
-
-    if not(filename):
-        filename = link.split('/')[-1]
-
-    return filename
+    if spider_result is None:
+        return linkFileName(link)
+    return spider_result['file_name'] or linkFileName(link)
 
 
 def addLinkSpider(add_link_dictionary: dict[str, str]) -> tuple[str | None, str | None]:
-    # get user's download information from add_link_dictionary
-    for i in ['link', 'header', 'out', 'user_agent', 'load_cookies', 'referer']:
-        if i not in add_link_dictionary:
-            add_link_dictionary[i] = None
-
-    link = add_link_dictionary['link']
-    header = add_link_dictionary['header']
-    user_agent = add_link_dictionary['user_agent']
-    raw_cookies = add_link_dictionary['load_cookies']
-    referer = add_link_dictionary['referer']
-
-    requests_session = requests.Session()  # defining a requests Session
-
-    if raw_cookies:  # set cookies
-        cookie = SimpleCookie()
-        cookie.load(raw_cookies)
-
-        cookies = {key: morsel.value for key, morsel in cookie.items()}
-        requests_session.cookies = cookiejar_from_dict(cookies)
-
-    if referer:
-        # set referer to the session
-        requests_session.headers.update({'referer': referer})
-
-    if user_agent:
-        # set user_agent to the session
-        requests_session.headers.update({'user-agent': user_agent})
-
-    # find headers
-    try:
-        response = requests_session.head(link)
-        header = response.headers
-    except Exception:
-        header = {}
-
-    # find file size
-    file_size = None
-    if 'Content-Length' in header:  # checking if file_size is available
-        file_size = int(header['Content-Length'])
-
-        # converting file_size to KiB or MiB or GiB
-        file_size = str(humanReadableSize(file_size))
-
-    # find file name
-    file_name = None
-    if 'Content-Disposition' in header:  # checking if filename is available
-        content_disposition = header['Content-Disposition']
-        if content_disposition.find('filename') != -1:
-            filename_splited = content_disposition.split('filename=')
-            filename_splited = filename_splited[-1]
-            # getting file name in desired format
-            file_name = str(filename_splited[1:-1])
-
-    return file_name, file_size  # If no Content-Length ? fixed it.
+    # file name is found from Content-Disposition only, link name is added by add link window.
+    keys = ['link', 'header', 'user_agent', 'load_cookies', 'referer']
+    spider_result = findHeaders({key: add_link_dictionary.get(key) for key in keys})
+
+    if spider_result is None:
+        return None, None
+
+    return spider_result['server_file_name'], spider_result['file_size']
+
+
+# queueSpiderMany checks links of add_link_dictionaries concurrently.
+# it yields (index, file_name, duplicate) as soon as a link is checked.
+# index is position of link in add_link_dictionaries and duplicate is True
+# if link is in data base or repeated in add_link_dictionaries.
+def queueSpiderMany(persepolis_db: ghermez.DataBase,
+                    add_link_dictionaries: list[dict[str, str]]) -> Iterator[tuple[int, str, bool]]:
+    keys = ['link', 'header', 'user_agent', 'load_cookies', 'referer']
+    add_link_dicts = [{key: str(add_link_dictionary[key]) for key in keys if add_link_dictionary.get(key) is not None}
+                      for add_link_dictionary in add_link_dictionaries]
+
+    for spider_item in ghermez.spiderMany(persepolis_db, add_link_dicts):
+        spider_result = spider_item['result']
+        file_name = spider_result['file_name'] if spider_result else None
+        yield spider_item['index'], file_name or linkFileName(spider_item['link']), spider_item['duplicate']
+
+
+# This thread finds filename of links of text queue and browser plugin queue windows.
+# QUEUESPIDERRETURNEDFILENAME is emitted with (file_name, row, duplicate) for every link.
+class QueueSpiderThread(QThread):
+    QUEUESPIDERRETURNEDFILENAME = Signal(str, int, bool)
+
+    def __init__(self, persepolis_db: ghermez.DataBase, download_dicts: list[dict[str, str]], rows: list[int]) -> None:
+        super().__init__()
+        self.persepolis_db = persepolis_db
+        self.dicts = download_dicts
+        # row of every download_dict in links_table
+        self.rows = rows
+
+    def run(self) -> None:
+        try:
+            # ghermez checks links concurrently and returns them as soon as they are checked.
+            for index, filename, duplicate in queueSpiderMany(self.persepolis_db, self.dicts):
+                self.QUEUESPIDERRETURNEDFILENAME.emit(filename, self.rows[index], duplicate)
+
+        except Exception as e:
+            # write error in log
+            logger.LOG_OBJ.error(
+                "Spider couldn't find download information", exc_info=True)
+            logger.LOG_OBJ.error(str(e), exc_info=True)
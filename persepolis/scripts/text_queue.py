@@ -18,45 +18,20 @@ from typing import Callable
 from . import globals
 
 try:
-    from PySide6.QtCore import QDir, QPoint, QSettings, QSize, Qt, QThread, Signal
+    from PySide6.QtCore import QDir, QPoint, QSettings, QSize, Qt
     from PySide6.QtGui import QCloseEvent, QIcon, QKeyEvent
     from PySide6.QtWidgets import QCheckBox, QFileDialog, QPushButton, QTableWidgetItem, QWidget
 except ImportError:
-    from PyQt5.QtCore import QDir, QPoint, QSettings, QSize, Qt, QThread
-    from PyQt5.QtCore import pyqtSignal as Signal
+    from PyQt5.QtCore import QDir, QPoint, QSettings, QSize, Qt
     from PyQt5.QtGui import QCloseEvent, QIcon, QKeyEvent
     from PyQt5.QtWidgets import QCheckBox, QFileDialog, QPushButton, QTableWidgetItem, QWidget
 
 import os
 from functools import partial
 
+import ghermez
 from persepolis.gui.text_queue_ui import TextQueue_Ui
-from persepolis.scripts import logger, spider
-
-# This thread finds filename
-
-
-class QueueSpiderThread(QThread):
-    QUEUESPIDERRETURNEDFILENAME = Signal(str)
-
-    def __init__(self, download_dict: dict[str, str]) -> None:
-        super().__init__()
-        self.dict = download_dict
-
-    def run(self) -> None:
-        try:
-            filename = spider.queueSpider(self.dict)
-            if filename:
-                self.QUEUESPIDERRETURNEDFILENAME.emit(filename)
-            else:
-                logger.LOG_OBJ.error(
-                    "Spider couldn't find download information", exc_info=True)
-
-        except Exception as e:
-            # write error in log
-            logger.LOG_OBJ.error(
-                "Spider couldn't find download information", exc_info=True)
-            logger.LOG_OBJ.error(str(e), exc_info=True)
+from persepolis.scripts import spider
 
 
 class TextQueue(TextQueue_Ui):
@@ -75,33 +50,29 @@ class TextQueue(TextQueue_Ui):
         globals.icons = ':/' + \
             str(self.persepolis_setting.value('settings/icons')) + '/'
 
-        # read text file lines and put links in list format.
+        # read text file and find links in it.
+        # text file can be a list of links, HTML or bookmarks file.
+        # ghermez expands ranges like file[001-120].jpg and removes duplicate links.
         with open(self.file_path) as f:
-            f_links_list = f.readlines()
+            link_list = ghermez.extractLinks(f.read())
 
-        f_links_list.reverse()
+        link_list.reverse()
 
-        # check links! links must be started with http or https or ftp
-        link_list = []
-        for link in f_links_list:
-            text = link.strip()
-            if ('tp:/' in text[2:6] or 'tps:/' in text[2:7]):
-                link_list.append(text)
+        # links that spider must find their file name and their rows in links_table
+        spider_dicts = []
+        spider_rows = []
 
         k = 1
-        for link in link_list:
+        for link_entry in link_list:
             self.links_table.insertRow(0)
 
             # file_name
-            file_name = '***'
-            link_dict = {'link': link}
+            link = link_entry['link']
+            file_name = link_entry['out'] if link_entry['out'] else '***'
 
-            # spider finds file name
-            new_spider = QueueSpiderThread(link_dict)
-            self.parent.threadPool.append(new_spider)
-            self.parent.threadPool[-1].start()
-            self.parent.threadPool[-1].QUEUESPIDERRETURNEDFILENAME.connect(
-                partial(self.parent.queueSpiderCallBack, child=self, row_number=len(link_list) - k))
+            if file_name == '***':
+                spider_dicts.append({'link': link})
+                spider_rows.append(len(link_list) - k)
             k = k + 1
 
             item = QTableWidgetItem(file_name)
@@ -118,6 +89,14 @@ class TextQueue(TextQueue_Ui):
             item = QTableWidgetItem(str(link))
             self.links_table.setItem(0, 1, item)
 
+        # spider finds file names
+        if spider_dicts:
+            new_spider = spider.QueueSpiderThread(self.parent.persepolis_db, spider_dicts, spider_rows)
+            self.parent.threadPool.append(new_spider)
+            self.parent.threadPool[-1].QUEUESPIDERRETURNEDFILENAME.connect(
+                partial(self.parent.queueSpiderCallBack, child=self))
+            self.parent.threadPool[-1].start()
+
         # get categories name and add them to add_queue_comboBox
         categories_list = self.parent.persepolis_db.categoriesList()
 
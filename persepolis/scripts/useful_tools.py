@@ -160,7 +160,8 @@ def muxer(parent: QWidget, video_finder_dictionary: dict[str, str]) -> dict[str,
     result_dictionary = {'error': 'no_error',
                          'ffmpeg_error_message': None,
                          'final_path': None,
-                         'final_size': None}
+                         'final_size': None,
+                         'final_file_size': None}
 
     # find file path
     video_file_dictionary = parent.persepolis_db.searchGidInAddLinkTable(video_finder_dictionary['video_gid'])
@@ -289,6 +290,7 @@ def muxer(parent: QWidget, video_finder_dictionary: dict[str, str]) -> dict[str,
 
                 result_dictionary['final_path'] = final_path_plus_name
                 result_dictionary['final_size'] = humanReadableSize(final_file_size)
+                result_dictionary['final_file_size'] = final_file_size
 
             else:
                 result_dictionary['error'] = 'ffmpeg error'
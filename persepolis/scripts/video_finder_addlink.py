@@ -177,9 +177,11 @@ class FileSizeFetcherThread(QThread):
         self.key = thread_key
 
     def run(self) -> None:
+        # spider returns size in bytes. 0 and None mean size is unknown.
         spider_file_size = spider(self.dictionary)[1]
+        file_size = ghermez.humanReadableSize(spider_file_size) if spider_file_size else None
         self.FOUND.emit({'thread_key': self.key,
-                         'file_size': spider_file_size})
+                         'file_size': file_size})
 
 
 class VideoFinderAddLink(AddLinkWindow):
@@ -588,10 +590,10 @@ class VideoFinderAddLink(AddLinkWindow):
             except Exception as ex:
                 ghermez.sendToLog(ex, 'ERROR')
 
-    def findFileSize(self, result: dict[str, str]) -> None:
+    def findFileSize(self, result: dict[str, str | None]) -> None:
         try:
             item_id = self.threadPool[str(result['thread_key'])]['item_id']
-            if result['file_size'] and result['file_size'] != '0':
+            if result['file_size']:
                 text = self.media_comboBox.itemText(item_id)
                 self.media_comboBox.setItemText(item_id, '{} - {}'.format(text, result['file_size']))
         except Exception as ex:
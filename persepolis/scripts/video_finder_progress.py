@@ -131,13 +131,12 @@ class VideoFinderProgressWindow(VideoFinderProgressWindow_Ui):
 
     def resumePushButtonPressed(self, _button: QPushButton) -> None:
         if self.status == DownloadStatus.Paused:
-            answer = ghermez.downloadUnpause(self.gid)
+            answer = download.downloadUnpause(self.gid)
             # if aria2 did not respond , then this function is checking for aria2
             # availability , and if aria2 disconnected then aria2Disconnected is
             # executed
             if not(answer):
-                version_answer = ghermez.aria2Version()
-                if version_answer == 'did not respond':
+                if not download.aria2Responds():
                     self.parent.aria2Disconnected()
                     notifySend(QCoreApplication.translate('progress_src_ui_tr', 'Aria2 disconnected!'),
                                QCoreApplication.translate('progress_src_ui_tr',
@@ -151,14 +150,13 @@ class VideoFinderProgressWindow(VideoFinderProgressWindow_Ui):
     def pausePushButtonPressed(self, _button: QPushButton) -> None:
 
         if self.status == DownloadStatus.Downloading:
-            answer = ghermez.downloadPause(self.gid)
+            answer = download.downloadPause(self.gid)
 
             # if aria2 did not respond , then this function is checking for aria2
             # availability , and if aria2 disconnected then aria2Disconnected is
             # executed
             if not(answer):
-                version_answer = ghermez.aria2Version()
-                if version_answer == 'did not respond':
+                if not download.aria2Responds():
                     self.parent.aria2Disconnected()
                     download.downloadStop(self.gid, self.parent)
                     notifySend('Aria2 disconnected!', 'Persepolis is trying to connect! be patient!',
@@ -181,9 +179,8 @@ class VideoFinderProgressWindow(VideoFinderProgressWindow_Ui):
         # if aria2 did not respond , then this function is checking for aria2
         # availability , and if aria2 disconnected then aria2Disconnected is
         # executed
-        if answer == 'None':
-            version_answer = ghermez.aria2Version()
-            if version_answer == 'did not respond':
+        if not answer:
+            if not download.aria2Responds():
                 self.parent.aria2Disconnected()
                 notifySend(QCoreApplication.translate('progress_src_ui_tr', 'Aria2 disconnected!'),
                            QCoreApplication.translate('progress_src_ui_tr',
@@ -210,7 +207,7 @@ class VideoFinderProgressWindow(VideoFinderProgressWindow_Ui):
                 if status != 'scheduled':
 
                     # tell aria2 for unlimited speed
-                    ghermez.limitSpeed(gid, '0')
+                    download.limitSpeed(gid, '0')
 
                 else:
                     # update limit value in data_base
@@ -331,7 +328,7 @@ class VideoFinderProgressWindow(VideoFinderProgressWindow_Ui):
 
             if status != DownloadStatus.Scheduled:
 
-                ghermez.limitSpeed(self.gid, limit_value)
+                download.limitSpeed(self.gid, limit_value)
             else:
                 # update limit value in data_base
                 add_link_dictionary = {'gid': gid, 'limit_value': limit_value}
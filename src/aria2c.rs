@@ -3,8 +3,12 @@
 use std::{
     collections::HashMap,
     ffi::OsStr,
+    fs,
+    future::Future,
+    io::{self, BufRead, BufReader},
     path::{Path, PathBuf},
-    process::{Command, Stdio},
+    process::{Child, Command, Stdio},
+    sync::{Mutex, Once},
     thread, time,
 };
 
@@ -17,226 +21,577 @@ use std::os::windows::process::CommandExt;
 use chrono::Local;
 use log::{error, info};
 use once_cell::sync::Lazy;
-use pyo3::prelude::*;
-use serde_json::{from_value, to_value, Map};
-use tokio::{runtime::Runtime, sync::RwLock};
+use percent_encoding::percent_decode_str;
+use pyo3::{
+    prelude::*,
+    types::{IntoPyDict, PyDict},
+};
+use rand::{distributions::Alphanumeric, thread_rng, Rng};
+use serde_json::{from_value, Map, Value};
+use tokio::{runtime::Runtime, sync::RwLock, time::timeout};
 
-use aria2_ws::{Client, TaskOptions};
+use aria2_ws::{response::GlobalStat, Client, Error as Aria2WsError, TaskOptions};
 
 use crate::{
+    database::DataBase,
+    error::GhermezError,
+    events::{forgetGid, pumpEvents},
+    options::{convertLimit, DownloadOptions},
+    pool::cancelPending,
     response::{CustomStatus, ValuesToString as _},
-    useful_tools::{humanReadableSize, round},
+    useful_tools::{humanReadableSize, DATE_FORMAT},
 };
 
 static SERVER_URL: Lazy<RwLock<String>> = Lazy::new(|| RwLock::new(String::new()));
 
-// start aria2 with RPC
+// secret token of aria2 RPC server. aria2 rejects calls without it.
+static RPC_SECRET: Lazy<RwLock<Option<String>>> = Lazy::new(|| RwLock::new(None));
+
+// one tokio runtime for the whole session.
+// every exported function blocks on it instead of building a new runtime per call.
+pub(crate) static RUNTIME: Lazy<Runtime> = Lazy::new(|| Runtime::new().unwrap());
+
+// websocket client that is shared by all exported functions.
+// aria2_ws::Client reconnects by itself if the websocket drops.
+static CLIENT: Lazy<RwLock<Option<Client>>> = Lazy::new(|| RwLock::new(None));
+
+// aria2 doesn't answer while the websocket is reconnecting,
+// so rpc calls give up after this time instead of blocking the GUI.
+pub(crate) const RPC_TIMEOUT: time::Duration = time::Duration::from_secs(5);
+
+// return the shared client and connect to aria2 if there is no client yet.
+async fn sharedClient() -> Result<Client, Aria2WsError> {
+    if let Some(client) = CLIENT.read().await.as_ref() {
+        return Ok(client.clone());
+    }
+
+    let mut shared_client = CLIENT.write().await;
+    // another thread may have connected while we were waiting for the lock
+    if let Some(client) = shared_client.as_ref() {
+        return Ok(client.clone());
+    }
+
+    let server_url = SERVER_URL.read().await;
+    let rpc_secret = RPC_SECRET.read().await;
+    let client = Client::connect(&server_url, rpc_secret.as_deref()).await?;
+    *shared_client = Some(client.clone());
+
+    // send download events of this client to python side
+    tokio::spawn(pumpEvents(client.subscribe_notifications()));
+    Ok(client)
+}
+
+// return the shared client without connecting to aria2.
+pub(crate) async fn currentClient() -> Option<Client> {
+    CLIENT.read().await.clone()
+}
+
+// run an rpc call with the shared client.
+// the client is dropped if aria2 doesn't answer, so the next call connects again.
+pub(crate) async fn asyncRpc<T, F, Fut>(call: F) -> Result<T, GhermezError>
+where
+    F: FnOnce(Client) -> Fut,
+    Fut: Future<Output = Result<T, Aria2WsError>>,
+{
+    let client = sharedClient().await?;
+    let answer = match timeout(RPC_TIMEOUT, call(client)).await {
+        Ok(answer) => answer,
+        Err(_) => Err(Aria2WsError::WebsocketClosed {
+            message: "aria2 didn't answer in time".to_string(),
+        }),
+    };
+    if let Err(Aria2WsError::WebsocketIo { .. } | Aria2WsError::WebsocketClosed { .. }) = answer {
+        CLIENT.write().await.take();
+    }
+    Ok(answer?)
+}
+
+// run an rpc call with the shared client on the shared runtime.
+fn rpc<T, F, Fut>(call: F) -> Result<T, GhermezError>
+where
+    F: FnOnce(Client) -> Fut,
+    Fut: Future<Output = Result<T, Aria2WsError>>,
+{
+    RUNTIME.block_on(asyncRpc(call))
+}
+
+// connect to aria2 RPC server and keep the connection for next calls.
 #[pyfunction]
-#[pyo3(signature = (port, _aria2_path=None))]
-pub fn startAria(port: u16, _aria2_path: Option<String>) -> Option<String> {
-    Runtime::new().unwrap().handle().block_on(async {
-        let mut tmp = SERVER_URL.write().await;
-        *tmp = format!("ws://127.0.0.1:{port}/jsonrpc");
+pub fn connect() -> Result<(), GhermezError> {
+    RUNTIME
+        .block_on(sharedClient())
+        .inspect_err(|e| error!("Couldn't connect to aria2: {e}"))?;
+    Ok(())
+}
+
+// close the shared connection to aria2 RPC server.
+#[pyfunction]
+pub fn disconnect() {
+    RUNTIME.block_on(async {
+        CLIENT.write().await.take();
     });
+}
 
-    #[cfg(target_os = "linux")]
-    let _child = match Command::new("aria2c")
-        .arg("--no-conf")
-        .arg("--enable-rpc")
-        .arg(format!("--rpc-listen-port={}", port))
-        .arg("--rpc-allow-origin-all")
-        .arg("--quiet=true")
-        .stdin(Stdio::inherit())
-        .stdout(Stdio::inherit())
-        .spawn()
-    {
-        Err(why) => panic!("couldn't spawn aria2c: {:?}", why),
-        Ok(child) => child,
-    };
+// aria2c child process and the arguments that it was started with.
+// Aria2Process keeps the child handle, so aria2c can be restarted if it crashes
+// and killed if it doesn't answer the shutdown request.
+pub struct Aria2Process {
+    program: PathBuf,
+    port: u16,
+    secret: String,
+    child: Child,
+    // number of times that aria2c is restarted after crash.
+    restarts: u32,
+}
 
-    #[cfg(target_os = "macos")]
-    {
-        let aria2d = if _aria2_path.is_none()
-            || _aria2_path
-                .as_ref()
-                .is_some_and(|x| x.is_empty() || !Path::new(&x).is_file())
-        {
-            let aria2 = env::current_dir().unwrap().join("aria2c.exe");
-            aria2.to_str().unwrap().to_string()
-        } else {
-            _aria2_path.as_ref().unwrap().to_string()
+impl Aria2Process {
+    // spawn aria2c with RPC enabled on port.
+    // RPC calls must send secret as token.
+    pub fn spawn(program: PathBuf, port: u16, secret: String) -> io::Result<Self> {
+        let child = Self::command(&program, port, &secret).spawn()?;
+        let mut process = Self {
+            program,
+            port,
+            secret,
+            child,
+            restarts: 0,
         };
-        if !Path::new(&aria2d).exists() {
-            error!("Aria2 does not exist in the current path!");
-            return None;
-        }
+        process.captureStderr();
+        Ok(process)
+    }
 
-        let _child = match Command::new(aria2d)
+    fn command(program: &Path, port: u16, secret: &str) -> Command {
+        let mut command = Command::new(program);
+        command
             .arg("--no-conf")
             .arg("--enable-rpc")
             .arg(format!("--rpc-listen-port={}", port))
+            .arg(format!("--rpc-secret={}", secret))
             .arg("--rpc-allow-origin-all")
             .arg("--quiet=true")
-            .stdin(Stdio::inherit())
-            .stdout(Stdio::inherit())
-            .stderr(Stdio::inherit())
-            .spawn()
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped());
+
+        // NO_WINDOW option avoids opening additional CMD window in MS Windows.
+        #[cfg(target_os = "windows")]
         {
-            Err(why) => panic!("couldn't spawn aria2c: {:?}", why),
-            Ok(child) => child,
+            const NO_WINDOW: u32 = 0x08000000;
+            command.creation_flags(NO_WINDOW);
+        }
+
+        command
+    }
+
+    // write aria2c error output in log file line by line.
+    fn captureStderr(&mut self) {
+        if let Some(stderr) = self.child.stderr.take() {
+            let pid = self.child.id();
+            thread::spawn(move || {
+                for line in BufReader::new(stderr).lines().map_while(Result::ok) {
+                    if !line.trim().is_empty() {
+                        error!("aria2c[{pid}]: {line}");
+                    }
+                }
+            });
+        }
+    }
+
+    pub fn is_running(&mut self) -> bool {
+        matches!(self.child.try_wait(), Ok(None))
+    }
+
+    // start aria2c again with the same arguments if it has exited.
+    // returns true if aria2c is restarted.
+    pub fn restart_if_crashed(&mut self) -> io::Result<bool> {
+        let status = match self.child.try_wait()? {
+            Some(status) => status,
+            None => return Ok(false),
         };
+        error!("aria2c exited unexpectedly ({status}), restarting it");
+
+        self.child = Self::command(&self.program, self.port, &self.secret).spawn()?;
+        self.restarts += 1;
+        self.captureStderr();
+        info!("aria2c is restarted {} time(s)", self.restarts);
+        Ok(true)
+    }
+
+    // wait for aria2c to exit. returns false if it's still running after timeout.
+    pub fn wait_exit(&mut self, timeout: time::Duration) -> bool {
+        let started = time::Instant::now();
+        while started.elapsed() < timeout {
+            if !self.is_running() {
+                return true;
+            }
+            thread::sleep(time::Duration::from_millis(100));
+        }
+        !self.is_running()
+    }
+
+    pub fn kill(&mut self) {
+        if let Err(e) = self.child.kill() {
+            error!("Couldn't kill aria2c: {e}");
+        }
+        // reap the child
+        let _ = self.child.wait();
+    }
+}
+
+// aria2c process that is started by startAria.
+// supervisor stops watching aria2c when shutDown takes it out.
+static ARIA2_PROCESS: Lazy<Mutex<Option<Aria2Process>>> = Lazy::new(|| Mutex::new(None));
+
+static SUPERVISOR: Once = Once::new();
+
+const SUPERVISOR_INTERVAL: time::Duration = time::Duration::from_secs(1);
+
+// how long startAria waits for aria2 to answer after spawning it.
+const READY_TIMEOUT: time::Duration = time::Duration::from_secs(10);
+
+// how long shutDown waits for aria2c to exit before killing it.
+const SHUTDOWN_TIMEOUT: time::Duration = time::Duration::from_secs(5);
+
+// watch aria2c and restart it if it crashes.
+fn startSupervisor() {
+    SUPERVISOR.call_once(|| {
+        thread::spawn(|| loop {
+            thread::sleep(SUPERVISOR_INTERVAL);
+
+            let restarted = match ARIA2_PROCESS.lock().unwrap().as_mut() {
+                Some(process) => process.restart_if_crashed(),
+                None => Ok(false),
+            };
+
+            match restarted {
+                Ok(true) => {
+                    if waitForAria2(READY_TIMEOUT).is_none() {
+                        error!("Aria2 didn't respond after restart!");
+                    }
+                }
+                Ok(false) => {}
+                Err(e) => error!("couldn't restart aria2c: {e}"),
+            }
+        });
+    });
+}
+
+// poll aria2 version until aria2 answers or timeout passes.
+fn waitForAria2(timeout: time::Duration) -> Option<String> {
+    let started = time::Instant::now();
+    while started.elapsed() < timeout {
+        if let Ok(version) = rpc(|client| async move { client.get_version().await }) {
+            return Some(version.version);
+        }
+        thread::sleep(time::Duration::from_millis(200));
     }
+    None
+}
 
-    #[cfg(target_os = "windows")]
+// find aria2c executable path.
+fn findAria2(_aria2_path: Option<String>) -> Option<PathBuf> {
+    #[cfg(not(any(target_os = "windows", target_os = "macos")))]
     {
-        let aria2d = if _aria2_path.is_none()
-            || _aria2_path
-                .as_ref()
-                .is_some_and(|x| x.is_empty() || !Path::new(&x).is_file())
-        {
-            let aria2 = env::current_dir().unwrap().join("aria2c.exe");
-            aria2.to_str().unwrap().to_string()
-        } else {
-            _aria2_path.as_ref().unwrap().to_string()
+        Some(PathBuf::from("aria2c"))
+    }
+
+    #[cfg(any(target_os = "windows", target_os = "macos"))]
+    {
+        let aria2d = match _aria2_path {
+            Some(x) if !x.is_empty() && Path::new(&x).is_file() => PathBuf::from(x),
+            _ => env::current_dir().unwrap().join("aria2c.exe"),
         };
-        println!("aria2d: {aria2d}");
-        if !Path::new(&aria2d).exists() {
+        info!("aria2d: {}", aria2d.display());
+        if !aria2d.exists() {
             error!("Aria2 does not exist in the current path!");
             return None;
         }
+        Some(aria2d)
+    }
+}
 
-        // NO_WINDOW option avoids opening additional CMD window in MS Windows.
-        const NO_WINDOW: u32 = 0x08000000;
+// generate a random secret token for aria2 RPC server.
+fn generateSecret() -> String {
+    thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(32)
+        .map(char::from)
+        .collect()
+}
 
-        let _child = match Command::new(aria2d)
-            .arg("--no-conf")
-            .arg("--enable-rpc")
-            .arg(format!("--rpc-listen-port={}", port))
-            .arg("--rpc-allow-origin-all")
-            .arg("--quiet=true")
-            .stdin(Stdio::inherit())
-            .stdout(Stdio::inherit())
-            .stderr(Stdio::inherit())
-            .creation_flags(NO_WINDOW)
-            .spawn()
-        {
-            Err(why) => panic!("couldn't spawn aria2c: {:?}", why),
-            Ok(child) => child,
-        };
-    }
+// return secret token of aria2 RPC server.
+// python side needs it for sending requests to aria2.
+#[pyfunction]
+pub fn rpcSecret() -> Option<String> {
+    RUNTIME.block_on(async { RPC_SECRET.read().await.clone() })
+}
+
+// start aria2 with RPC
+// a random secret is generated for this session if rpc_secret is empty.
+#[pyfunction]
+#[pyo3(signature = (port, aria2_path=None, rpc_secret=None))]
+pub fn startAria(
+    port: u16,
+    aria2_path: Option<String>,
+    rpc_secret: Option<String>,
+) -> Result<String, GhermezError> {
+    let secret = match rpc_secret {
+        Some(secret) if !secret.is_empty() => secret,
+        _ => generateSecret(),
+    };
 
-    thread::sleep(time::Duration::from_secs(2));
+    RUNTIME.block_on(async {
+        let mut tmp = SERVER_URL.write().await;
+        *tmp = format!("ws://127.0.0.1:{port}/jsonrpc");
+        *RPC_SECRET.write().await = Some(secret.clone());
+        // old connection belongs to the previous server
+        CLIENT.write().await.take();
+    });
+
+    let aria2d = findAria2(aria2_path).ok_or_else(|| {
+        GhermezError::Io(io::Error::new(
+            io::ErrorKind::NotFound,
+            "aria2 does not exist in the current path",
+        ))
+    })?;
+
+    // kill aria2c that is started before, so new one can listen on port.
+    // lock is kept, so supervisor doesn't restart the old one meanwhile.
+    let mut aria2_process = ARIA2_PROCESS.lock().unwrap();
+    if let Some(mut old_process) = aria2_process.take() {
+        old_process.kill();
+    }
+    let process = Aria2Process::spawn(aria2d, port, secret)
+        .inspect_err(|why| error!("couldn't spawn aria2c: {why}"))?;
+    *aria2_process = Some(process);
+    drop(aria2_process);
+    startSupervisor();
 
     // check that starting is successful or not!
-    let answer = aria2Version();
-    Some(answer)
+    match waitForAria2(READY_TIMEOUT) {
+        Some(version) => Ok(version),
+        None => aria2Version(),
+    }
 }
 
 // check aria2 release version . Ghermez uses this function to
 // check that aria2 RPC connection is available or not.
 #[pyfunction]
-pub fn aria2Version() -> String {
-    let version = Runtime::new().unwrap().handle().block_on(async {
-        let server_url = SERVER_URL.read().await;
-        match Client::connect(&server_url, None).await {
-            Ok(client) => client.get_version().await,
-            Err(e) => Err(e),
-        }
-    });
+pub fn aria2Version() -> Result<String, GhermezError> {
+    let version = rpc(|client| async move { client.get_version().await })
+        // write ERROR messages in terminal and log
+        .inspect_err(|e| error!("Aria2 didn't respond! {e}"))?;
+    Ok(version.version)
+}
 
-    match version {
-        Ok(v) => v.version,
-        Err(_) => {
-            // write ERROR messages in terminal and log
-            error!("Aria2 didn't respond!");
-            "did not respond".to_string()
-        }
-    }
+// this function sends download request to aria2 and returns gid of download.
+// options is a dictionary of aria2 options, see options.rs.
+#[pyfunction]
+#[pyo3(signature = (uris, options=None))]
+pub fn addDownload(
+    uris: Vec<String>,
+    options: Option<DownloadOptions>,
+) -> Result<String, GhermezError> {
+    let gid =
+        rpc(|client| async move { client.add_uri(uris, options.map(|o| o.0), None, None).await })
+            .inspect_err(|e| error!("Download did not start: {e}"))?;
+    info!("{gid} Starts");
+    Ok(gid)
 }
 
-fn _download_aria(url: &str) -> String {
-    let gid = Runtime::new().unwrap().handle().block_on(async {
-        let server_url = SERVER_URL.read().await;
-        let client = Client::connect(&server_url, None).await.unwrap();
-        let options = TaskOptions::default();
+// send download request for gid in addlink_db_table to aria2.
+// download options are loaded from data base and settings.
+// settings contains ghermez settings, see DownloadOptions::fromAddLink.
+// without settings, ghermez default settings are used.
+#[pyfunction]
+#[pyo3(signature = (database, gid, settings=None))]
+pub fn startDownload(
+    database: PyRef<DataBase>,
+    gid: &str,
+    settings: Option<HashMap<String, String>>,
+) -> Result<String, GhermezError> {
+    let Some(add_link_dict) = database.searchGidInAddLinkTable(gid)? else {
+        error!("{gid} is not in addlink_db_table");
+        return Err(GhermezError::Database(format!(
+            "{gid} is not in addlink_db_table"
+        )));
+    };
+    let link = add_link_dict.get("link").cloned().unwrap_or_default();
+    let options = DownloadOptions::fromAddLink(&add_link_dict, &settings.unwrap_or_default());
+    addDownload(vec![link], Some(options))
+}
 
+// add a BitTorrent download from .torrent file in path.
+#[pyfunction]
+#[pyo3(signature = (path, options=None))]
+pub fn addTorrent(path: PathBuf, options: Option<DownloadOptions>) -> Result<String, GhermezError> {
+    let torrent =
+        fs::read(&path).inspect_err(|e| error!("Couldn't read {}: {e}", path.display()))?;
+    let gid = rpc(|client| async move {
         client
-            .add_uri(vec![url.to_string()], Some(options.clone()), None, None)
+            .add_torrent(torrent, None, options.map(|o| o.0), None, None)
             .await
-            .unwrap()
-    });
-    gid
+    })
+    .inspect_err(|e| error!("Torrent download did not start: {e}"))?;
+    info!("{gid} Starts");
+    Ok(gid)
 }
 
-type GidList = Vec<String>;
-type DownloadStatusList = Vec<HashMap<String, Option<String>>>;
-
-// this function returns list of download information
+// add a Metalink download from .metalink or .meta4 file in path.
 #[pyfunction]
-pub fn tellActive() -> (Option<GidList>, Option<DownloadStatusList>) {
-    let args = vec![
-        "gid".to_string(),
-        "status".to_string(),
-        "connections".to_string(),
-        "errorCode".to_string(),
-        "errorMessage".to_string(),
-        "downloadSpeed".to_string(),
-        "dir".to_string(),
-        "totalLength".to_string(),
-        "completedLength".to_string(),
-        "files".to_string(),
-    ];
-    // get download information from aria2
-    let downloads_status_result = Runtime::new().unwrap().handle().block_on(async {
-        let server_url = SERVER_URL.read().await;
-        match Client::connect(&server_url, None).await {
-            Ok(client) => client.custom_tell_active(Some(args)).await,
-            Err(e) => Err(e),
-        }
-    });
+#[pyo3(signature = (path, options=None))]
+pub fn addMetalink(
+    path: PathBuf,
+    options: Option<DownloadOptions>,
+) -> Result<String, GhermezError> {
+    let metalink =
+        fs::read(&path).inspect_err(|e| error!("Couldn't read {}: {e}", path.display()))?;
+    let gid = rpc(|client| async move {
+        client
+            .add_metalink(metalink, options.map(|o| o.0), None, None)
+            .await
+    })
+    .inspect_err(|e| error!("Metalink download did not start: {e}"))?;
+    info!("{gid} Starts");
+    Ok(gid)
+}
 
-    let downloads_status: Vec<CustomStatus> = match downloads_status_result {
-        Ok(downloads_status) => from_value(to_value(downloads_status).unwrap()).unwrap(),
-        Err(_) => return (None, None),
-    };
+type GidList = Vec<String>;
+pub(crate) type DownloadStatus = HashMap<String, Option<String>>;
+type DownloadStatusList = Vec<DownloadStatus>;
+
+// keys of download information that ghermez asks aria2 for.
+fn statusKeys() -> Vec<String> {
+    [
+        "gid",
+        "status",
+        "connections",
+        "errorCode",
+        "errorMessage",
+        "downloadSpeed",
+        "dir",
+        "totalLength",
+        "completedLength",
+        "files",
+    ]
+    .into_iter()
+    .map(String::from)
+    .collect()
+}
 
-    let mut download_status_list = vec![];
-    let mut gid_list = vec![];
+// download information in the format of download_db_table, without unknown values.
+// None values don't change data base.
+type DownloadTableDict = HashMap<String, String>;
+
+// parse list of download information that received from aria2.
+fn parseDownloadList(
+    downloads_status: Vec<Map<String, Value>>,
+) -> Result<Vec<CustomStatus>, GhermezError> {
+    downloads_status
+        .into_iter()
+        .map(|download_dict| Ok(from_value(Value::Object(download_dict))?))
+        .collect()
+}
 
-    // convert download information in desired format.
-    for download_dict in downloads_status {
-        let converted_info_dict = convertDownloadInformation(download_dict.clone());
+// convert list of download information that received from aria2 in desired format.
+fn convertDownloadList(
+    downloads_status: Vec<Map<String, Value>>,
+) -> Result<DownloadStatusList, GhermezError> {
+    Ok(parseDownloadList(downloads_status)?
+        .into_iter()
+        .map(convertDownloadInformation)
+        .collect())
+}
 
-        // add gid to gid_list
-        gid_list.push(download_dict.gid);
+// this function returns list of download information.
+// download_table_list contains the same downloads in the format of download_db_table.
+#[pyfunction]
+pub fn tellActive() -> Result<(GidList, DownloadStatusList, Vec<DownloadTableDict>), GhermezError> {
+    // get download information from aria2
+    let answer = rpc(|client| async move { client.custom_tell_active(Some(statusKeys())).await })
+        .and_then(parseDownloadList)
+        .inspect_err(|e| error!("Couldn't get active downloads from aria2: {e}"))?;
+
+    let gid_list = answer
+        .iter()
+        .map(|download_status| download_status.gid.clone())
+        .collect();
+    let download_table_list = answer
+        .iter()
+        .cloned()
+        .map(|download_status| tableDict(downloadTableInformation(download_status)))
+        .collect();
+    let download_status_list = answer.into_iter().map(convertDownloadInformation).collect();
+
+    Ok((gid_list, download_status_list, download_table_list))
+}
 
-        // add converted information to download_status_list
-        download_status_list.push(converted_info_dict);
+// this function returns download status that specified by gid!
+// download_table_dict is the same status in the format of download_db_table.
+// "error" key contains error message of aria2, if download has failed,
+// and "path" is the path of first file of download in download_path_temp.
+#[pyfunction]
+pub fn tellStatus(gid: &str) -> Result<(DownloadStatus, DownloadTableDict), GhermezError> {
+    let download_status = RUNTIME.block_on(customStatus(gid))?;
+    let download_table_dict = tableDict(downloadTableInformation(download_status.clone()));
+
+    let error_message = download_status.error_message.clone();
+    let path = download_status
+        .files
+        .first()
+        .map(|file| file.path.clone())
+        .filter(|path| !path.is_empty());
+    let mut converted_info_dict = convertDownloadInformation(download_status);
+
+    // add errorMessage to converted_info_dict if an error occurred!
+    if converted_info_dict
+        .get("status")
+        .is_some_and(|status| status.as_deref() == Some("error"))
+    {
+        converted_info_dict.insert("error".to_string(), error_message);
     }
+    converted_info_dict.insert("path".to_string(), path);
 
-    (Some(gid_list), Some(download_status_list))
+    Ok((converted_info_dict, download_table_dict))
 }
 
-fn _tellStatus(gid: &str) -> Map<String, serde_json::Value> {
-    let args = vec![
-        "status".to_string(),
-        "connections".to_string(),
-        "errorCode".to_string(),
-        "errorMessage".to_string(),
-        "downloadSpeed".to_string(),
-        "connections".to_string(),
-        "dir".to_string(),
-        "totalLength".to_string(),
-        "completedLength".to_string(),
-        "files".to_string(),
-    ];
-    let status = Runtime::new().unwrap().handle().block_on(async {
-        let server_url = SERVER_URL.read().await;
-        let client = Client::connect(&server_url, None).await.unwrap();
-        client.custom_tell_status(gid, Some(args)).await.unwrap()
-    });
+// get download information of gid from aria2.
+async fn customStatus(gid: &str) -> Result<CustomStatus, GhermezError> {
+    asyncRpc(|client| async move { client.custom_tell_status(gid, Some(statusKeys())).await })
+        .await
+        .and_then(|download_dict| Ok(from_value(Value::Object(download_dict))?))
+        .inspect_err(|e| error!("Couldn't get status of {gid} from aria2: {e}"))
+}
+
+// get download status of gid from aria2 in the format of download_db_table.
+pub(crate) async fn downloadTableStatus(gid: &str) -> Result<DownloadStatus, GhermezError> {
+    Ok(downloadTableInformation(customStatus(gid).await?))
+}
 
-    status
+// this function returns information of waiting and paused downloads.
+// offset and num are used like aria2.tellWaiting.
+#[pyfunction]
+pub fn tellWaiting(offset: i32, num: i32) -> Result<DownloadStatusList, GhermezError> {
+    rpc(|client| async move {
+        client
+            .custom_tell_waiting(offset, num, Some(statusKeys()))
+            .await
+    })
+    .and_then(convertDownloadList)
+    .inspect_err(|e| error!("Couldn't get waiting downloads from aria2: {e}"))
+}
+
+// this function returns information of stopped downloads (complete, error, removed).
+// offset and num are used like aria2.tellStopped.
+#[pyfunction]
+pub fn tellStopped(offset: i32, num: i32) -> Result<DownloadStatusList, GhermezError> {
+    rpc(|client| async move {
+        client
+            .custom_tell_stopped(offset, num, Some(statusKeys()))
+            .await
+    })
+    .and_then(convertDownloadList)
+    .inspect_err(|e| error!("Couldn't get stopped downloads from aria2: {e}"))
 }
 
 // this function converts download information that received from aria2 in desired format.
@@ -245,16 +600,20 @@ fn convertDownloadInformation(download_status: CustomStatus) -> HashMap<String,
     // find file_name
     let file_status = download_status.files;
     // file_status contains name of download file and link of download file
-    let path = &file_status[0].path;
-    let file_name = if !path.is_empty() {
-        Path::new(&path)
-            .file_name()
-            .map(|parent| parent.to_str().unwrap().to_string())
-    } else {
-        None
-    };
-
-    let link = Some(file_status[0].uris[0].uri.to_owned());
+    let file_name = file_status
+        .first()
+        .filter(|file| !file.path.is_empty())
+        .and_then(|file| Path::new(&file.path).file_name())
+        .map(|name| {
+            percent_decode_str(&name.to_string_lossy())
+                .decode_utf8_lossy()
+                .to_string()
+        });
+
+    let link = file_status
+        .first()
+        .and_then(|file| file.uris.first())
+        .map(|uri| uri.uri.to_owned());
 
     // find file_size
     let file_size = download_status.total_length;
@@ -265,10 +624,8 @@ fn convertDownloadInformation(download_status: CustomStatus) -> HashMap<String,
     let size_str;
     let downloaded_str;
     // convert file_size and downloaded_size to KiB and MiB and GiB
-    if file_size != 0 {
-        // find download percent from file_size and downloaded_size
-        let percent = downloaded as f32 * 100.0 / file_size as f32;
-
+    // find download percent from file_size and downloaded_size
+    if let Some(percent) = (downloaded * 100).checked_div(file_size) {
         // converting file_size to KiB or MiB or GiB
         size_str = Some(humanReadableSize(file_size as f32, "file_size"));
         downloaded_str = Some(humanReadableSize(downloaded as f32, "file_size"));
@@ -288,25 +645,23 @@ fn convertDownloadInformation(download_status: CustomStatus) -> HashMap<String,
     let mut estimate_time_left_str;
     let download_speed_str;
     if file_size != 0 && download_speed != 0 {
-        let mut estimate_time_left = (file_size - downloaded) as f32 / download_speed as f32;
+        let estimate_time_left = file_size.saturating_sub(downloaded) / download_speed;
 
         // converting file_size to KiB or MiB or GiB
-        download_speed_str = Some(humanReadableSize(download_speed as f32, "speed") + "/s");
-
-        let mut eta = String::new();
-        if estimate_time_left >= 3600.0 {
-            eta += &(format!("{}", estimate_time_left / 3600.0) + "h");
-            estimate_time_left %= 3600.0;
-            eta += &(format!("{}", estimate_time_left / 60.0) + "m");
-            estimate_time_left %= 60.0;
-            eta += &(format!("{}", estimate_time_left) + "s");
-        } else if estimate_time_left >= 60.0 {
-            eta += &(format!("{}", estimate_time_left / 60.0) + "m");
-            estimate_time_left %= 60.0;
-            eta += &(format!("{}", estimate_time_left) + "s");
+        download_speed_str = Some(speedString(download_speed));
+
+        let (hours, minutes, seconds) = (
+            estimate_time_left / 3600,
+            estimate_time_left % 3600 / 60,
+            estimate_time_left % 60,
+        );
+        let eta = if hours > 0 {
+            format!("{hours}h{minutes}m{seconds}s")
+        } else if minutes > 0 {
+            format!("{minutes}m{seconds}s")
         } else {
-            eta += &(format!("{}", estimate_time_left) + "s");
-        }
+            format!("{seconds}s")
+        };
         estimate_time_left_str = Some(eta);
     } else {
         download_speed_str = Some("0".to_string());
@@ -348,6 +703,34 @@ fn convertDownloadInformation(download_status: CustomStatus) -> HashMap<String,
     ])
 }
 
+// download_dict without None values.
+fn tableDict(download_dict: DownloadStatus) -> DownloadTableDict {
+    download_dict
+        .into_iter()
+        .filter_map(|(key, value)| Some((key, value?)))
+        .collect()
+}
+
+// convertDownloadInformation for data base. sizes and rate are numbers of aria2 in byte
+// and byte per second and percent is from 0 to 100, see downloadValues.
+fn downloadTableInformation(download_status: CustomStatus) -> DownloadStatus {
+    let file_size = download_status.total_length;
+    let downloaded = download_status.completed_length;
+    let download_speed = download_status.download_speed;
+
+    let mut download_dict = convertDownloadInformation(download_status);
+    if file_size != 0 {
+        download_dict.insert("size".to_string(), Some(file_size.to_string()));
+        download_dict.insert("downloaded_size".to_string(), Some(downloaded.to_string()));
+        download_dict.insert(
+            "percent".to_string(),
+            Some((downloaded * 100 / file_size).to_string()),
+        );
+    }
+    download_dict.insert("rate".to_string(), Some(download_speed.to_string()));
+    download_dict
+}
+
 // this function returns folder of download according to file extension
 #[pyfunction]
 pub fn findDownloadPath(file_name: &str, download_path: PathBuf, subfolder: &str) -> PathBuf {
@@ -358,7 +741,7 @@ pub fn findDownloadPath(file_name: &str, download_path: PathBuf, subfolder: &str
     let mut file_extension = Path::new(file_name)
         .extension()
         .and_then(OsStr::to_str)
-        .unwrap()
+        .unwrap_or_default()
         // convert extension letters to lower case
         // for example "JPG" will be converted in "jpg"
         .to_lowercase();
@@ -420,12 +803,11 @@ pub fn findDownloadPath(file_name: &str, download_path: PathBuf, subfolder: &str
 // shutdown aria2
 #[pyfunction]
 pub fn shutDown() -> bool {
-    let answer = Runtime::new().unwrap().handle().block_on(async {
-        let server_url = SERVER_URL.read().await;
-        let client = Client::connect(&server_url, None).await.unwrap();
-        client.shutdown().await
-    });
-    match answer {
+    // take aria2c out of supervisor first, so it will not be restarted.
+    let process = ARIA2_PROCESS.lock().unwrap().take();
+
+    let answer = rpc(|client| async move { client.shutdown().await });
+    let mut result = match answer {
         Ok(_) => {
             info!("Aria2 Shutdown: Ok");
             true
@@ -434,98 +816,241 @@ pub fn shutDown() -> bool {
             error!("Aria2 Shutdown Error: {e}");
             false
         }
+    };
+
+    // If aria2c not respond, so kill it. R.I.P :))
+    if let Some(mut process) = process {
+        if !process.wait_exit(SHUTDOWN_TIMEOUT) {
+            error!("aria2c didn't exit, killing it");
+            process.kill();
+            result = true;
+        }
+    }
+
+    disconnect();
+    result
+}
+
+// downloadStop stops download completely and writes "stopped" status in data base.
+// it returns gid of stopped download. an error is raised if aria2 couldn't stop it,
+// and data base is not changed in this case.
+#[pyfunction]
+pub fn downloadStop(database: PyRef<DataBase>, gid: &str) -> Result<String, GhermezError> {
+    RUNTIME.block_on(stopDownload(&database, gid))
+}
+
+// stop download and write "stopped" status in data base.
+// it's used by downloadStop and scheduler.
+pub(crate) async fn stopDownload(database: &DataBase, gid: &str) -> Result<String, GhermezError> {
+    // get download status from data_base
+    let status = database
+        .searchGidInDownloadTable(gid)?
+        .and_then(|download_dict| download_dict.get("status").cloned());
+
+    // if status is "scheduled" or download is waiting in pool, then download request
+    // has not been sended to aria2! so no need to send stop request to aria2.
+    if status.as_deref() != Some("scheduled") && !cancelPending(gid) {
+        removeDownload(gid)
+            .await
+            .inspect_err(|e| error!("Couldn't stop {gid}: {e}"))?;
+        info!("{gid} stopped");
+    }
+
+    if status.as_deref() != Some("complete") {
+        // change start_time end_time and after_download value to None in date base
+        database.setDefaultGidInAddlinkTable(gid, true, true, true)?;
+
+        // change status of download to "stopped" in data base
+        database.updateDownloadTable(vec![HashMap::from([("gid", gid), ("status", "stopped")])])?;
+    }
+
+    Ok(gid.to_string())
+}
+
+// send remove request to aria2 and clear the download result.
+// downloads that are finished already or aria2 doesn't know are removed too.
+// see aria2 documentation for more information.
+async fn removeDownload(gid: &str) -> Result<String, GhermezError> {
+    match asyncRpc(|client| async move { client.remove(gid).await }).await {
+        Ok(()) => {
+            // remove is finished in background, so result may not be there yet.
+            let _ =
+                asyncRpc(|client| async move { client.remove_download_result(gid).await }).await;
+            forgetGid(gid);
+            Ok(gid.to_string())
+        }
+        // aria2 refuses to remove a download that is finished already.
+        // in this case just its result must be removed.
+        // otherwise aria2 couldn't stop download gracefully, so force it.
+        Err(GhermezError::Aria2Rpc { .. }) => {
+            let keys = Some(vec!["status".to_string()]);
+            let status =
+                match asyncRpc(|client| async move { client.custom_tell_status(gid, keys).await })
+                    .await
+                {
+                    Ok(status) => status,
+                    // aria2 doesn't know gid, so there is nothing to stop
+                    Err(GhermezError::Aria2Rpc { .. }) => return Ok(gid.to_string()),
+                    Err(e) => return Err(e),
+                };
+            let finished = status
+                .get("status")
+                .and_then(Value::as_str)
+                .is_some_and(|status| ["complete", "error", "removed"].contains(&status));
+            if finished {
+                asyncRpc(|client| async move { client.remove_download_result(gid).await }).await?;
+                forgetGid(gid);
+            } else {
+                asyncRpc(|client| async move { client.force_remove(gid).await }).await?;
+            }
+            Ok(gid.to_string())
+        }
+        Err(e) => Err(e),
     }
 }
 
 // downloadPause pauses download
 #[pyfunction]
-pub fn downloadPause(gid: &str) -> Option<String> {
+pub fn downloadPause(gid: &str) -> Result<(), GhermezError> {
     // see aria2 documentation for more information
 
     // send pause request to aria2.
-    let answer = Runtime::new().unwrap().handle().block_on(async {
-        let server_url = SERVER_URL.read().await;
-        let client = Client::connect(&server_url, None).await.unwrap();
-        client.pause(gid).await
-    });
-    info!("{answer:?} paused");
-    match answer {
-        Ok(_) => Some("Ok".to_string()),
-        Err(_) => None,
-    }
+    rpc(|client| async move { client.pause(gid).await })
+        .inspect_err(|e| error!("Couldn't pause {gid}: {e}"))?;
+    info!("{gid} paused");
+    Ok(())
 }
 
 // downloadUnpause unpauses download
 #[pyfunction]
-pub fn downloadUnpause(gid: &str) -> Option<String> {
+pub fn downloadUnpause(gid: &str) -> Result<(), GhermezError> {
     // send unpause request to aria2
-    let answer = Runtime::new().unwrap().handle().block_on(async {
-        let server_url = SERVER_URL.read().await;
-        let client = Client::connect(&server_url, None).await.unwrap();
-        client.unpause(gid).await
-    });
-    info!("{answer:?} paused");
-    match answer {
-        Ok(_) => Some("Ok".to_string()),
-        Err(_) => None,
-    }
+    rpc(|client| async move { client.unpause(gid).await })
+        .inspect_err(|e| error!("Couldn't unpause {gid}: {e}"))?;
+    info!("{gid} unpaused");
+    Ok(())
 }
 
 // limitSpeed limits download speed
 #[pyfunction]
-pub fn limitSpeed(gid: &str, limit: &str) {
-    let mut editedlimit = limit.to_string();
-    // convert Mega to Kilo, RPC does not Support floating point numbers.
-    if limit != "0" {
-        let mut limit_number: f32 = limit[0..limit.len() - 1].parse().unwrap();
-        let mut limit_unit = limit.chars().last().unwrap();
-        if limit_unit == 'K' {
-            limit_number = round(limit_number, 0);
-        } else {
-            limit_number = round(1024.0 * limit_number, 0);
-            limit_unit = 'K';
-        }
-        editedlimit = format!("{limit_number}{limit_unit}");
-    }
-
+pub fn limitSpeed(gid: &str, limit: &str) -> Result<(), GhermezError> {
     let options = TaskOptions {
-        max_download_limit: Some(editedlimit),
+        max_download_limit: Some(convertLimit(limit)),
         ..Default::default()
     };
 
-    let answer = Runtime::new().unwrap().handle().block_on(async {
-        let server_url = SERVER_URL.read().await;
-        let client = Client::connect(&server_url, None).await.unwrap();
-        client.change_option(gid, options).await
-    });
+    rpc(|client| async move { client.change_option(gid, options).await })
+        .inspect_err(|e| error!("Speed limitation was unsuccessful: {e}"))?;
+    info!("Download speed limit value is changed");
+    Ok(())
+}
 
-    match answer {
-        Ok(_) => info!("Download speed limit value is changed"),
-        Err(_) => error!("Speed limitation was unsuccessful"),
+// pauseAll pauses all active and waiting downloads.
+// if force is true, aria2 doesn't wait for contacting servers before pausing.
+#[pyfunction]
+#[pyo3(signature = (force=false))]
+pub fn pauseAll(force: bool) -> Result<(), GhermezError> {
+    if force {
+        rpc(|client| async move { client.force_pause_all().await })
+    } else {
+        rpc(|client| async move { client.pause_all().await })
     }
+    .inspect_err(|e| error!("Couldn't pause downloads: {e}"))?;
+    info!("All downloads paused");
+    Ok(())
+}
+
+// resumeAll unpauses all paused downloads
+#[pyfunction]
+pub fn resumeAll() -> Result<(), GhermezError> {
+    rpc(|client| async move { client.unpause_all().await })
+        .inspect_err(|e| error!("Couldn't resume downloads: {e}"))?;
+    info!("All downloads resumed");
+    Ok(())
+}
+
+// setGlobalSpeedLimit limits overall download speed and upload speed.
+// "0" removes the limit. limits are like limitSpeed, for example "1.5M" or "100K"
+#[pyfunction]
+#[pyo3(signature = (limit, upload_limit=None))]
+pub fn setGlobalSpeedLimit(limit: &str, upload_limit: Option<&str>) -> Result<(), GhermezError> {
+    let mut options = TaskOptions::default();
+    options.extra_options.insert(
+        "max-overall-download-limit".to_string(),
+        Value::String(convertLimit(limit)),
+    );
+    if let Some(upload_limit) = upload_limit {
+        options.extra_options.insert(
+            "max-overall-upload-limit".to_string(),
+            Value::String(convertLimit(upload_limit)),
+        );
+    }
+
+    rpc(|client| async move { client.change_global_option(options).await })
+        .inspect_err(|e| error!("Global speed limitation was unsuccessful: {e}"))?;
+    info!("Global speed limit value is changed");
+    Ok(())
 }
 
 // this function returns GID of active downloads in list format.
 #[pyfunction]
-pub fn activeDownloads() -> Vec<String> {
-    let answer = Runtime::new().unwrap().handle().block_on(async {
-        let server_url = SERVER_URL.read().await;
-        let client = Client::connect(&server_url, None).await.unwrap();
+pub fn activeDownloads() -> Result<Vec<String>, GhermezError> {
+    let answer = rpc(|client| async move {
         client
             .custom_tell_active(Some(vec!["gid".to_string()]))
             .await
-    });
+    })
+    .inspect_err(|e| error!("Couldn't get active downloads from aria2: {e}"))?;
+
+    // add gid to list
+    let active_gids = answer
+        .iter()
+        .filter_map(|download_dict| download_dict.get("gid")?.as_str())
+        .map(String::from)
+        .collect();
+    Ok(active_gids)
+}
 
-    let answer = match answer {
-        Ok(answer) => answer,
-        Err(_) => vec![],
-    };
-    let mut active_gids = vec![];
-    for download_dict in answer {
-        // add gid to list
-        active_gids.push(download_dict.get("gid").unwrap().to_string());
+// this function returns global statistics of aria2 in dictionary format.
+// download_speed, upload_speed and num_* keys are raw numbers (speeds in bytes/s)
+// and download_rate and upload_rate are human readable, for example "1.5 MiB/s"
+#[pyfunction]
+pub fn globalStat(py: Python<'_>) -> Result<&PyDict, GhermezError> {
+    let global_stat = rpc(|client| async move { client.get_global_stat().await })
+        .inspect_err(|e| error!("Couldn't get global statistics from aria2: {e}"))?;
+    Ok(globalStatDict(py, &global_stat))
+}
+
+fn globalStatDict<'py>(py: Python<'py>, global_stat: &GlobalStat) -> &'py PyDict {
+    [
+        ("download_speed", global_stat.download_speed.into_py(py)),
+        ("upload_speed", global_stat.upload_speed.into_py(py)),
+        (
+            "download_rate",
+            speedString(global_stat.download_speed).into_py(py),
+        ),
+        (
+            "upload_rate",
+            speedString(global_stat.upload_speed).into_py(py),
+        ),
+        ("num_active", global_stat.num_active.into_py(py)),
+        ("num_waiting", global_stat.num_waiting.into_py(py)),
+        ("num_stopped", global_stat.num_stopped.into_py(py)),
+        (
+            "num_stopped_total",
+            global_stat.num_stopped_total.into_py(py),
+        ),
+    ]
+    .into_py_dict(py)
+}
+
+// convert speed in bytes/s to human readable format like convertDownloadInformation
+pub(crate) fn speedString(speed: u64) -> String {
+    if speed == 0 {
+        "0".to_string()
+    } else {
+        humanReadableSize(speed as f32, "speed") + "/s"
     }
-    active_gids
 }
 
 // This function returns data and time in string format
@@ -533,21 +1058,251 @@ pub fn activeDownloads() -> Vec<String> {
 #[pyfunction]
 pub fn nowDate() -> String {
     let now = Local::now();
-    now.format("%Y/%m/%d , %H:%M:%S").to_string()
+    now.format(DATE_FORMAT).to_string()
 }
 
-// sigmaTime gets hours and minutes for input.
-// and converts hours to minutes and returns summation in minutes
-// input format is HH:MM
-fn _sigmaTime(time: String) -> i32 {
-    let splitedTime: Vec<&str> = time.split(':').collect();
-    let hour: i32 = splitedTime[0].parse().unwrap();
-    let minute: i32 = splitedTime[1].parse().unwrap();
-    hour * 60 + minute
+// unix time of now, dates are saved in data base like this.
+pub(crate) fn nowTimestamp() -> i64 {
+    Local::now().timestamp()
 }
 
-// nowTime returns now time in HH:MM format!
-fn _nowTime() -> i32 {
-    let now_time = Local::now().format("%H:%M");
-    _sigmaTime(now_time.to_string())
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use std::{fs, os::unix::fs::PermissionsExt};
+
+    use super::*;
+
+    // write a shell script that stands in for aria2c.
+    // it appends its arguments to args.txt and then runs body.
+    fn stubAria2(name: &str, body: &str) -> (PathBuf, PathBuf) {
+        let folder = std::env::temp_dir().join(format!("ghermez-{name}-{}", std::process::id()));
+        fs::create_dir_all(&folder).unwrap();
+        let args_file = folder.join("args.txt");
+        let _ = fs::remove_file(&args_file);
+
+        let program = folder.join("aria2c");
+        fs::write(
+            &program,
+            format!(
+                "#!/bin/sh\necho \"$@\" >> {}\n{body}\n",
+                args_file.display()
+            ),
+        )
+        .unwrap();
+        fs::set_permissions(&program, fs::Permissions::from_mode(0o755)).unwrap();
+        (program, args_file)
+    }
+
+    fn waitForLines(file: &Path, count: usize) -> Vec<String> {
+        let started = time::Instant::now();
+        loop {
+            let lines: Vec<String> = fs::read_to_string(file)
+                .unwrap_or_default()
+                .lines()
+                .map(String::from)
+                .collect();
+            if lines.len() >= count || started.elapsed() > time::Duration::from_secs(5) {
+                return lines;
+            }
+            thread::sleep(time::Duration::from_millis(50));
+        }
+    }
+
+    #[test]
+    fn stop_download_updates_data_base_only_when_stopped() {
+        let database = DataBase::open(":memory:").unwrap();
+        database.createTables().unwrap();
+        let download = |gid, status| {
+            HashMap::from([
+                ("file_name", "a.zip"),
+                ("status", status),
+                ("percent", "0%"),
+                ("connections", "0"),
+                ("estimate_time_left", "0"),
+                ("gid", gid),
+                ("link", "http://example.com/a.zip"),
+                ("category", "Single Downloads"),
+            ])
+        };
+        database
+            .insertInDownloadTable(vec![
+                download("s1", "scheduled"),
+                download("d1", "downloading"),
+            ])
+            .unwrap();
+        let status =
+            |gid: &str| database.statusesOfCategory("Single Downloads").unwrap()[gid].clone();
+
+        // scheduled download is not sent to aria2 yet
+        assert_eq!(
+            RUNTIME.block_on(stopDownload(&database, "s1")).unwrap(),
+            "s1"
+        );
+        assert_eq!(status("s1"), "stopped");
+
+        // aria2 is not running in tests, so download isn't stopped
+        assert!(RUNTIME.block_on(stopDownload(&database, "d1")).is_err());
+        assert_eq!(status("d1"), "downloading");
+    }
+
+    #[test]
+    fn spawn_passes_rpc_arguments() {
+        let (program, args_file) = stubAria2("spawn", "exec sleep 30");
+        let mut process = Aria2Process::spawn(program, 6999, "s3cr3t".to_string()).unwrap();
+
+        let lines = waitForLines(&args_file, 1);
+        assert_eq!(lines.len(), 1);
+        assert!(lines[0].contains("--enable-rpc"));
+        assert!(lines[0].contains("--rpc-listen-port=6999"));
+        assert!(lines[0].contains("--rpc-secret=s3cr3t"));
+        assert!(process.is_running());
+
+        process.kill();
+        assert!(!process.is_running());
+    }
+
+    #[test]
+    fn crashed_process_is_restarted_with_same_port_and_secret() {
+        let (program, args_file) = stubAria2("crash", "exit 1");
+        let mut process = Aria2Process::spawn(program, 6998, generateSecret()).unwrap();
+
+        assert!(process.wait_exit(time::Duration::from_secs(5)));
+        assert!(process.restart_if_crashed().unwrap());
+        assert_eq!(process.restarts, 1);
+
+        let lines = waitForLines(&args_file, 2);
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0], lines[1]);
+    }
+
+    #[test]
+    fn running_process_is_not_restarted() {
+        let (program, args_file) = stubAria2("running", "exec sleep 30");
+        let mut process = Aria2Process::spawn(program, 6997, generateSecret()).unwrap();
+        waitForLines(&args_file, 1);
+
+        assert!(!process.restart_if_crashed().unwrap());
+        assert_eq!(process.restarts, 0);
+        assert!(!process.wait_exit(time::Duration::from_millis(300)));
+
+        process.kill();
+    }
+
+    #[test]
+    fn generated_secret_is_random() {
+        let secret = generateSecret();
+        assert_eq!(secret.len(), 32);
+        assert!(secret.chars().all(|c| c.is_ascii_alphanumeric()));
+        assert_ne!(secret, generateSecret());
+    }
+
+    #[test]
+    fn convert_download_list() {
+        let answer = serde_json::json!([
+            {
+                "gid": "a1b2c3d4e5f60718",
+                "status": "removed",
+                "connections": "0",
+                "errorCode": "0",
+                "downloadSpeed": "0",
+                "dir": "/tmp",
+                "totalLength": "0",
+                "completedLength": "0",
+                "files": []
+            },
+            {
+                "gid": "0123456789abcdef",
+                "status": "active",
+                "connections": "4",
+                "downloadSpeed": "1024",
+                "dir": "/tmp",
+                "totalLength": "4096",
+                "completedLength": "2048",
+                "files": [{
+                    "index": "1",
+                    "path": "/tmp/file.zip",
+                    "length": "4096",
+                    "completedLength": "2048",
+                    "selected": "true",
+                    "uris": []
+                }]
+            }
+        ]);
+        let list = convertDownloadList(from_value(answer.clone()).unwrap()).unwrap();
+
+        assert_eq!(list[0]["status"].as_deref(), Some("stopped"));
+        assert_eq!(list[0]["file_name"], None);
+        assert_eq!(list[0]["link"], None);
+        assert_eq!(list[1]["status"].as_deref(), Some("downloading"));
+        assert_eq!(list[1]["file_name"].as_deref(), Some("file.zip"));
+        assert_eq!(list[1]["percent"].as_deref(), Some("50%"));
+
+        // data base gets numbers of aria2
+        let download_status = from_value(answer[1].clone()).unwrap();
+        let download_dict = downloadTableInformation(download_status);
+        assert_eq!(download_dict["size"].as_deref(), Some("4096"));
+        assert_eq!(download_dict["downloaded_size"].as_deref(), Some("2048"));
+        assert_eq!(download_dict["percent"].as_deref(), Some("50"));
+        assert_eq!(download_dict["rate"].as_deref(), Some("1024"));
+        assert_eq!(download_dict["status"].as_deref(), Some("downloading"));
+
+        // aria2 answer without required keys is an error, not a panic
+        let answer = serde_json::json!([{ "gid": "a1b2c3d4e5f60718" }]);
+        assert!(convertDownloadList(from_value(answer).unwrap()).is_err());
+    }
+
+    #[test]
+    fn file_name_and_estimate_time_left() {
+        let answer = serde_json::json!({
+            "gid": "0123456789abcdef",
+            "status": "active",
+            "connections": "1",
+            "downloadSpeed": "1000",
+            "dir": "/tmp",
+            "totalLength": "4000000",
+            "completedLength": "338000",
+            "files": [{
+                "index": "1",
+                "path": "/tmp/my%20file.zip",
+                "length": "4000000",
+                "completedLength": "338000",
+                "selected": "true",
+                "uris": []
+            }]
+        });
+        let download_dict = convertDownloadInformation(from_value(answer).unwrap());
+        assert_eq!(download_dict["file_name"].as_deref(), Some("my file.zip"));
+        assert_eq!(download_dict["percent"].as_deref(), Some("8%"));
+        // 3662 seconds
+        assert_eq!(
+            download_dict["estimate_time_left"].as_deref(),
+            Some("1h1m2s")
+        );
+    }
+
+    #[test]
+    fn global_stat_dict() {
+        pyo3::prepare_freethreaded_python();
+        Python::with_gil(|py| {
+            let global_stat = GlobalStat {
+                download_speed: 1536 * 1024,
+                upload_speed: 0,
+                num_active: 2,
+                num_waiting: 1,
+                num_stopped: 3,
+                num_stopped_total: 5,
+            };
+            let dict = globalStatDict(py, &global_stat);
+            let get = |key: &str| dict.get_item(key).unwrap();
+
+            assert_eq!(get("download_speed").extract::<u64>().unwrap(), 1572864);
+            assert_eq!(
+                get("download_rate").extract::<String>().unwrap(),
+                "1.5 MiB/s"
+            );
+            assert_eq!(get("upload_rate").extract::<String>().unwrap(), "0");
+            assert_eq!(get("num_active").extract::<i32>().unwrap(), 2);
+            assert_eq!(get("num_stopped_total").extract::<i32>().unwrap(), 5);
+        });
+    }
 }
@@ -0,0 +1,307 @@
+#![allow(non_snake_case)]
+
+use std::{fs, path::Path, time};
+
+use log::info;
+use pyo3::prelude::*;
+
+use crate::{error::GhermezError, pool::hostOf};
+
+// a cookie of Netscape cookies.txt file or Cookie header
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cookie {
+    // empty domain matches every host, cookies of a Cookie header have no domain
+    pub domain: String,
+    pub include_subdomains: bool,
+    pub path: String,
+    // cookie is only sent over https
+    pub secure: bool,
+    // unix time, 0 is a session cookie
+    pub expires: i64,
+    pub name: String,
+    pub value: String,
+}
+
+// cookies that are stored in load_cookies column of addlink_db_table
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum CookieOption {
+    // path of a cookies.txt file, it's given to aria2 as load-cookies option
+    File(String),
+    // value of Cookie header, for example "a=b; c=d"
+    Header(String),
+}
+
+// parse Netscape cookies.txt. every line has 7 fields that are separated by tab:
+// domain, include subdomains, path, secure, expires, name and value.
+// lines that start with # are comments, except "#HttpOnly_" lines that are cookies.
+pub fn parseCookiesTxt(text: &str) -> Vec<Cookie> {
+    text.lines()
+        .filter_map(|line| {
+            let line = line.trim_end_matches('\r');
+            let line = match line.strip_prefix("#HttpOnly_") {
+                Some(line) => line,
+                None if line.starts_with('#') => return None,
+                None => line,
+            };
+            let fields: Vec<&str> = line.split('\t').collect();
+            let [domain, include_subdomains, path, secure, expires, name, value] = fields[..]
+            else {
+                return None;
+            };
+            if name.is_empty() {
+                return None;
+            }
+            Some(Cookie {
+                domain: domain.trim_start_matches('.').to_lowercase(),
+                include_subdomains: include_subdomains.eq_ignore_ascii_case("TRUE")
+                    || domain.starts_with('.'),
+                path: path.to_string(),
+                secure: secure.eq_ignore_ascii_case("TRUE"),
+                expires: expires.trim().parse().unwrap_or(0),
+                name: name.to_string(),
+                value: value.to_string(),
+            })
+        })
+        .collect()
+}
+
+// parse value of Cookie header like "a=b; c=d". "Cookie:" prefix is optional.
+pub fn parseCookieHeader(header: &str) -> Vec<Cookie> {
+    let header = header.trim();
+    let header = match header.split_once(':') {
+        Some((name, rest)) if name.trim().eq_ignore_ascii_case("cookie") => rest,
+        _ => header,
+    };
+    header
+        .split(';')
+        .filter_map(|pair| {
+            let (name, value) = pair.split_once('=')?;
+            let name = name.trim();
+            (!name.is_empty()).then(|| Cookie {
+                domain: String::new(),
+                include_subdomains: true,
+                path: "/".to_string(),
+                secure: false,
+                expires: 0,
+                name: name.to_string(),
+                value: value.trim().to_string(),
+            })
+        })
+        .collect()
+}
+
+// load_cookies can be path of a cookies.txt file, content of a cookies.txt file
+// or value of a Cookie header that is copied from browser.
+pub(crate) fn loadCookies(load_cookies: &str) -> Result<Vec<Cookie>, GhermezError> {
+    if Path::new(load_cookies).is_file() {
+        return Ok(parseCookiesTxt(&fs::read_to_string(load_cookies)?));
+    }
+    if load_cookies.contains('\t') {
+        return Ok(parseCookiesTxt(load_cookies));
+    }
+    Ok(parseCookieHeader(load_cookies))
+}
+
+fn domainMatches(cookie: &Cookie, host: &str) -> bool {
+    if cookie.domain.is_empty() || cookie.domain == host {
+        return true;
+    }
+    cookie.include_subdomains
+        && host
+            .strip_suffix(&cookie.domain)
+            .is_some_and(|prefix| prefix.ends_with('.'))
+}
+
+// path of cookie must be path of link or one of its parent folders
+fn pathMatches(cookie: &Cookie, path: &str) -> bool {
+    let cookie_path = if cookie.path.is_empty() {
+        "/"
+    } else {
+        cookie.path.as_str()
+    };
+    match path.strip_prefix(cookie_path) {
+        Some(rest) => cookie_path.ends_with('/') || rest.is_empty() || rest.starts_with('/'),
+        None => false,
+    }
+}
+
+// path of link without query, for example "/a/b" for "http://host/a/b?c=d"
+fn linkPath(link: &str) -> String {
+    let rest = link.split_once("://").map_or(link, |(_, rest)| rest);
+    let path = rest.split(['?', '#']).next().unwrap_or_default();
+    match path.find('/') {
+        Some(start) => path[start..].to_string(),
+        None => "/".to_string(),
+    }
+}
+
+pub(crate) fn unixTime() -> i64 {
+    time::SystemTime::now()
+        .duration_since(time::UNIX_EPOCH)
+        .map_or(0, |x| x.as_secs() as i64)
+}
+
+// cookies that browser sends to link. domain, path, secure flag and expiry time
+// of cookies are checked. now is unix time.
+pub fn cookiesFor(cookies: &[Cookie], link: &str, now: i64) -> Vec<Cookie> {
+    let Some(host) = hostOf(link) else {
+        return vec![];
+    };
+    let path = linkPath(link);
+    let https = link.to_lowercase().starts_with("https://");
+    cookies
+        .iter()
+        .filter(|cookie| {
+            domainMatches(cookie, &host)
+                && pathMatches(cookie, &path)
+                && (!cookie.secure || https)
+                && (cookie.expires == 0 || cookie.expires > now)
+        })
+        .cloned()
+        .collect()
+}
+
+// value of Cookie header, None if there is no cookie
+pub fn cookieHeader(cookies: &[Cookie]) -> Option<String> {
+    let pairs: Vec<String> = cookies
+        .iter()
+        .map(|cookie| format!("{}={}", cookie.name, cookie.value))
+        .collect();
+    (!pairs.is_empty()).then(|| pairs.join("; "))
+}
+
+// write cookies in Netscape format. cookies without domain get host of link.
+pub fn cookiesTxt(cookies: &[Cookie], host: &str) -> String {
+    let mut text = "# Netscape HTTP Cookie File\n".to_string();
+    for cookie in cookies {
+        let domain = if cookie.domain.is_empty() {
+            host
+        } else {
+            cookie.domain.as_str()
+        };
+        let flag = |x: bool| if x { "TRUE" } else { "FALSE" };
+        let domain_prefix = if cookie.include_subdomains { "." } else { "" };
+        text.push_str(&format!(
+            "{domain_prefix}{domain}\t{}\t{}\t{}\t{}\t{}\t{}\n",
+            flag(cookie.include_subdomains),
+            cookie.path,
+            flag(cookie.secure),
+            cookie.expires,
+            cookie.name,
+            cookie.value
+        ));
+    }
+    text
+}
+
+// cookies of a download. a cookies.txt file is given to aria2 as it is,
+// because aria2 chooses cookies of every request after redirects itself.
+// other cookies are filtered for link and sent in Cookie header.
+pub(crate) fn cookieOption(load_cookies: &str, link: &str) -> Option<CookieOption> {
+    if Path::new(load_cookies).is_file() {
+        return Some(CookieOption::File(load_cookies.to_string()));
+    }
+    let cookies = loadCookies(load_cookies).ok()?;
+    cookieHeader(&cookiesFor(&cookies, link, unixTime())).map(CookieOption::Header)
+}
+
+// writeCookiesFile writes cookies of link in Netscape cookies.txt format to path.
+// this file can be used as load-cookies option of aria2 or cookies option of youtube-dl.
+// load_cookies is path or content of a cookies.txt file or value of Cookie header.
+// it returns number of cookies that are written.
+#[pyfunction]
+pub fn writeCookiesFile(load_cookies: &str, link: &str, path: &str) -> Result<usize, GhermezError> {
+    let cookies = cookiesFor(&loadCookies(load_cookies)?, link, unixTime());
+    let host = hostOf(link).unwrap_or_default();
+    fs::write(path, cookiesTxt(&cookies, &host))?;
+    info!("{} cookies of {host} are written in {path}", cookies.len());
+    Ok(cookies.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const COOKIES_TXT: &str = "# Netscape HTTP Cookie File\n\
+        .example.com\tTRUE\t/\tFALSE\t0\tsession\t1\n\
+        example.com\tFALSE\t/files\tTRUE\t4000000000\tsecure\t2\n\
+        #HttpOnly_.example.com\tTRUE\t/\tFALSE\t1000\told\t3\n\
+        other.com\tFALSE\t/\tFALSE\t0\tother\t4\n\
+        broken line\n";
+
+    fn names(cookies: &[Cookie]) -> Vec<&str> {
+        cookies.iter().map(|cookie| cookie.name.as_str()).collect()
+    }
+
+    #[test]
+    fn parse_cookies_txt() {
+        let cookies = parseCookiesTxt(COOKIES_TXT);
+        assert_eq!(names(&cookies), vec!["session", "secure", "old", "other"]);
+        assert_eq!(cookies[0].domain, "example.com");
+        assert!(cookies[0].include_subdomains);
+        assert!(cookies[1].secure);
+        assert_eq!(cookies[1].expires, 4000000000);
+    }
+
+    #[test]
+    fn parse_cookie_header() {
+        let cookies = parseCookieHeader("Cookie: a=b; token=x=y;; =z");
+        assert_eq!(names(&cookies), vec!["a", "token"]);
+        assert_eq!(cookies[1].value, "x=y");
+        assert_eq!(parseCookieHeader("a=b"), parseCookieHeader("cookie:a=b"));
+        assert!(parseCookieHeader("None").is_empty());
+    }
+
+    #[test]
+    fn cookies_are_filtered_for_link() {
+        let cookies = parseCookiesTxt(COOKIES_TXT);
+        let now = 2000;
+        let found = |link| names(&cookiesFor(&cookies, link, now)).join(",");
+        assert_eq!(found("https://example.com/files/a.zip"), "session,secure");
+        assert_eq!(found("http://example.com/files/a.zip"), "session");
+        assert_eq!(found("https://dl.example.com/files/a.zip"), "session");
+        assert_eq!(found("https://example.com/filesystem"), "session");
+        assert_eq!(found("https://notexample.com/"), "");
+        assert_eq!(found("http://other.com/x"), "other");
+        assert_eq!(
+            cookieHeader(&cookiesFor(&cookies, "https://example.com/files", now)).as_deref(),
+            Some("session=1; secure=2")
+        );
+        assert_eq!(cookieHeader(&[]), None);
+    }
+
+    #[test]
+    fn write_cookies_txt() {
+        let cookies = parseCookieHeader("a=b");
+        let text = cookiesTxt(&cookies, "example.com");
+        assert_eq!(
+            text,
+            "# Netscape HTTP Cookie File\n.example.com\tTRUE\t/\tFALSE\t0\ta\tb\n"
+        );
+        assert_eq!(
+            parseCookiesTxt(&text)[0],
+            Cookie {
+                domain: "example.com".to_string(),
+                ..cookies[0].clone()
+            }
+        );
+    }
+
+    #[test]
+    fn cookie_option_of_file_and_header() {
+        let path = std::env::temp_dir().join(format!("ghermez_cookies_{}.txt", std::process::id()));
+        fs::write(&path, COOKIES_TXT).unwrap();
+        let path = path.to_str().unwrap();
+        assert_eq!(
+            cookieOption(path, "http://example.com/"),
+            Some(CookieOption::File(path.to_string()))
+        );
+        fs::remove_file(path).unwrap();
+
+        assert_eq!(
+            cookieOption("a=b; c=d", "http://example.com/"),
+            Some(CookieOption::Header("a=b; c=d".to_string()))
+        );
+        assert_eq!(cookieOption(COOKIES_TXT, "http://nothing.com/"), None);
+    }
+}
@@ -1,15 +1,628 @@
 #![allow(non_snake_case)]
+// pyo3 0.19 macros trigger this lint on newer compilers
+#![allow(non_local_definitions)]
 
 use std::{
     collections::HashMap,
+    fs,
+    path::Path,
     sync::{Arc, Mutex},
 };
 
+use log::{info, warn};
 use pyo3::prelude::*;
 use regex::Regex;
-use rusqlite::Connection;
+use rusqlite::{
+    functions::FunctionFlags, params_from_iter, types::Value, Connection, OptionalExtension, Row,
+};
+
+use crate::{
+    aria2c::speedString,
+    error::GhermezError,
+    options::{headerJson, headerPairs},
+    pool::hostOf,
+    search::{queryDownloads, DownloadQuery, DownloadRow},
+    useful_tools::{
+        dateToTimestamp, determineConfigFolder, humanReadableSize, sizeInBytes, timestampToDate,
+    },
+};
+
+// all gids, video gids and audio gids of video_finder_db_table
+type VideoFinderGids = (Vec<String>, Vec<String>, Vec<String>);
+
+// columns of download_db_table in order
+const DOWNLOAD_COLUMNS: [&str; 13] = [
+    "file_name",
+    "status",
+    "size",
+    "downloaded_size",
+    "percent",
+    "connections",
+    "rate",
+    "estimate_time_left",
+    "gid",
+    "link",
+    "first_try_date",
+    "last_try_date",
+    "category",
+];
+
+// values of a download in the order of DOWNLOAD_COLUMNS. sizes and rate are byte and byte
+// per second, percent is from 0 to 100 and dates are unix time, like aria2 answers them.
+// missing keys and unknown values like "***" are NULL.
+fn downloadValues(dict: &HashMap<&str, &str>) -> Vec<Value> {
+    DOWNLOAD_COLUMNS
+        .iter()
+        .map(|column| match (*column, dict.get(column)) {
+            (_, None) => Value::Null,
+            (
+                "size" | "downloaded_size" | "percent" | "rate" | "first_try_date"
+                | "last_try_date",
+                Some(text),
+            ) => text.parse().map_or(Value::Null, Value::Integer),
+            (_, Some(text)) => Value::Text(text.to_string()),
+        })
+        .collect()
+}
+
+// row of download_db_table as dictionary. sizes, rate and dates are formatted
+// like download table of main window, unknown values are "***".
+fn downloadDict(row: &Row) -> rusqlite::Result<HashMap<&'static str, String>> {
+    DOWNLOAD_COLUMNS
+        .iter()
+        .enumerate()
+        .map(|(i, column)| {
+            let value = match *column {
+                "size" | "downloaded_size" => row
+                    .get::<_, Option<i64>>(i)?
+                    .map(|size| humanReadableSize(size as f32, "file_size")),
+                "percent" => row
+                    .get::<_, Option<i64>>(i)?
+                    .map(|percent| format!("{percent}%")),
+                "rate" => row
+                    .get::<_, Option<i64>>(i)?
+                    .map(|rate| speedString(rate.max(0) as u64)),
+                "first_try_date" | "last_try_date" => {
+                    row.get::<_, Option<i64>>(i)?.and_then(timestampToDate)
+                }
+                _ => Some(row.get(i)?),
+            };
+            Ok((*column, value.unwrap_or_else(|| "***".to_string())))
+        })
+        .collect()
+}
+
+// convert headers of old rows from "name1=value1; name2=value2" to JSON.
+// rows that are converted before are not changed.
+fn migrateHeaders(connection: &Connection, table: &str) -> Result<(), GhermezError> {
+    let mut stmt = connection.prepare(&format!(
+        "SELECT ID, header FROM {table} WHERE header IS NOT NULL"
+    ))?;
+    let rows = stmt
+        .query_map([], |row| {
+            Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?))
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+    for (id, header) in rows {
+        let json = headerJson(&header);
+        if json != header {
+            connection.execute(
+                &format!("UPDATE {table} SET header = ?1 WHERE ID = ?2"),
+                (json, id),
+            )?;
+        }
+    }
+    Ok(())
+}
+
+// add gid to the end of category, if it's not in category
+fn appendCategoryItem(
+    connection: &Connection,
+    category: &str,
+    gid: &str,
+) -> Result<(), GhermezError> {
+    connection
+        .prepare_cached(
+            "
+        INSERT OR IGNORE INTO category_item (category, gid, position)
+        SELECT ?1, ?2, coalesce(max(position) + 1, 0) FROM category_item WHERE category = ?1
+        ",
+        )?
+        .execute([category, gid])?;
+    Ok(())
+}
+
+// old versions saved items of category in gid_list column like "['gid1', 'gid2']".
+// they are moved to category_item and gid_list is set to NULL. then downloads that are
+// missed in their category or 'All Downloads' are added to the end of it.
+fn migrateGidLists(connection: &Connection) -> Result<(), GhermezError> {
+    let mut stmt = connection
+        .prepare("SELECT category, gid_list FROM category_db_table WHERE gid_list IS NOT NULL")?;
+    let rows = stmt
+        .query_map([], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+    let re = Regex::new(r"[0-9A-Za-z]+").unwrap();
+    for (category, gid_list) in rows {
+        for gid in re.find_iter(&gid_list) {
+            connection.execute(
+                "
+                INSERT OR IGNORE INTO category_item (category, gid, position)
+                SELECT ?1, gid, (SELECT coalesce(max(position) + 1, 0) FROM category_item WHERE category = ?1)
+                FROM download_db_table WHERE gid = ?2
+                ",
+                [category.as_str(), gid.as_str()],
+            )?;
+        }
+    }
+    connection.execute("UPDATE category_db_table SET gid_list = NULL", ())?;
+
+    // items must be in category of download and 'All Downloads', and nowhere else
+    connection.execute(
+        "
+        DELETE FROM category_item WHERE category != 'All Downloads' AND category IS NOT
+        (SELECT category FROM download_db_table WHERE gid = category_item.gid)
+        ",
+        (),
+    )?;
+    let mut stmt = connection.prepare(
+        "
+        SELECT download_db_table.category, gid FROM download_db_table
+        JOIN category_db_table ON download_db_table.category = category_db_table.category
+        ORDER BY download_db_table.ROWID
+        ",
+    )?;
+    let rows = stmt
+        .query_map([], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+    for (category, gid) in rows {
+        appendCategoryItem(connection, &category, &gid)?;
+        appendCategoryItem(connection, "All Downloads", &gid)?;
+    }
+    Ok(())
+}
+
+// a step of database schema. version of a data base is saved in PRAGMA user_version,
+// so data base of every older release is migrated to the last version.
+struct Migration {
+    version: i32,
+    description: &'static str,
+    // apply must be idempotent, data bases of old releases may have a part of it.
+    apply: fn(&Connection) -> Result<(), GhermezError>,
+}
+
+// migrations of ghermez.db in order. released migrations must not be changed,
+// a new migration must be added to the end instead.
+const MIGRATIONS: [Migration; 8] = [
+    Migration {
+        version: 1,
+        description: "create tables",
+        apply: createTablesV1,
+    },
+    Migration {
+        version: 2,
+        description: "change KB, MB and GB to KiB, MiB and GiB",
+        apply: correctUnits,
+    },
+    Migration {
+        version: 3,
+        description: "add retry_db_table",
+        apply: createRetryTable,
+    },
+    Migration {
+        version: 4,
+        description: "save headers as JSON",
+        apply: |connection| migrateHeaders(connection, "addlink_db_table"),
+    },
+    Migration {
+        version: 5,
+        description: "move gid_list of categories to category_item",
+        apply: createCategoryItemTable,
+    },
+    Migration {
+        version: 6,
+        description: "save sizes, rates and dates of downloads as numbers",
+        apply: numericDownloadColumns,
+    },
+    Migration {
+        version: 7,
+        description: "add full text search of downloads",
+        apply: createDownloadSearchTable,
+    },
+    Migration {
+        version: 8,
+        description: "add schedule_db_table",
+        apply: createScheduleTable,
+    },
+];
+
+// migrations of plugins.db
+const PLUGINS_MIGRATIONS: [Migration; 2] = [
+    Migration {
+        version: 1,
+        description: "create tables",
+        apply: createPluginsTable,
+    },
+    Migration {
+        version: 2,
+        description: "save headers as JSON",
+        apply: |connection| migrateHeaders(connection, "plugins_db_table"),
+    },
+];
+
+// run migrations that are newer than version of data base. every migration runs in
+// a transaction with its new user_version, so an interrupted migration is run again
+// next time. a copy of data base is saved in "<data base>.v<version>.bak" before migrating.
+fn migrate(connection: &mut Connection, migrations: &[Migration]) -> Result<(), GhermezError> {
+    let version: i32 = connection.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+    let last_version = migrations.last().map_or(0, |migration| migration.version);
+    if version > last_version {
+        warn!("Data base version {version} is newer than this version of ghermez ({last_version})");
+        return Ok(());
+    }
+    if version == last_version {
+        return Ok(());
+    }
+    backupDataBase(connection, version)?;
+
+    // tables may be rebuilt in migrations, foreign keys are checked after migrating
+    connection.pragma_update(None, "foreign_keys", false)?;
+    let answer = migrations
+        .iter()
+        .filter(|migration| migration.version > version)
+        .try_for_each(|migration| {
+            let transaction = connection.transaction()?;
+            (migration.apply)(&transaction)?;
+            transaction.pragma_update(None, "user_version", migration.version)?;
+            transaction.commit()?;
+            info!(
+                "Data base is migrated to version {}: {}",
+                migration.version, migration.description
+            );
+            Ok::<_, GhermezError>(())
+        });
+    connection.pragma_update(None, "foreign_keys", true)?;
+    answer?;
+
+    let violations: i64 =
+        connection.query_row("SELECT count(*) FROM pragma_foreign_key_check", [], |row| {
+            row.get(0)
+        })?;
+    if violations > 0 {
+        warn!("Data base has {violations} rows with broken foreign keys after migration");
+    }
+    Ok(())
+}
+
+// copy data base before migration, unless it's a new or in memory data base.
+fn backupDataBase(connection: &Connection, version: i32) -> Result<(), GhermezError> {
+    let path = connection.path().unwrap_or_default().to_string();
+    let tables: i64 =
+        connection.query_row("SELECT count(*) FROM sqlite_master", [], |row| row.get(0))?;
+    if path.is_empty() || tables == 0 {
+        return Ok(());
+    }
+    let backup = format!("{path}.v{version}.bak");
+    if Path::new(&backup).exists() {
+        fs::remove_file(&backup)?;
+    }
+    connection.execute("VACUUM INTO ?1", [&backup])?;
+    info!("Data base is copied to {backup}");
+    Ok(())
+}
+
+fn createTablesV1(connection: &Connection) -> Result<(), GhermezError> {
+    // Create category_db_table and add 'All Downloads' and 'Single Downloads' to it
+    connection.execute(
+        "
+            CREATE TABLE IF NOT EXISTS category_db_table(
+                category TEXT PRIMARY KEY,
+                start_time_enable TEXT,
+                start_time TEXT,
+                end_time_enable TEXT,
+                end_time TEXT,
+                reverse TEXT,
+                limit_enable TEXT,
+                limit_value TEXT,
+                after_download TEXT,
+                -- items of category in old versions, see category_item
+                gid_list TEXT
+            )",
+        (),
+    )?;
+
+    // download table contains download table download items information
+    connection.execute(
+        "
+            CREATE TABLE IF NOT EXISTS download_db_table(
+                file_name TEXT,
+                status TEXT,
+                size TEXT,
+                downloaded_size TEXT,
+                percent TEXT,
+                connections TEXT,
+                rate TEXT,
+                estimate_time_left TEXT,
+                gid TEXT PRIMARY KEY,
+                link TEXT,
+                first_try_date TEXT,
+                last_try_date TEXT,
+                category TEXT,
+                FOREIGN KEY(category) REFERENCES category_db_table(category)
+                ON UPDATE CASCADE
+                ON DELETE CASCADE
+            )",
+        (),
+    )?;
+
+    // addlink_db_table contains addlink window download information
+    connection.execute(
+        "
+        CREATE TABLE IF NOT EXISTS addlink_db_table(
+            ID INTEGER PRIMARY KEY,
+            gid TEXT,
+            out TEXT,
+            start_time TEXT,
+            end_time TEXT,
+            link TEXT,
+            ip TEXT,
+            port TEXT,
+            proxy_user TEXT,
+            proxy_passwd TEXT,
+            download_user TEXT,
+            download_passwd TEXT,
+            connections TEXT,
+            limit_value TEXT,
+            download_path TEXT,
+            referer TEXT,
+            load_cookies TEXT,
+            user_agent TEXT,
+            header TEXT,
+            after_download TEXT,
+            FOREIGN KEY(gid) REFERENCES download_db_table(gid)
+            ON UPDATE CASCADE
+            ON DELETE CASCADE
+        )
+        ",
+        (),
+    )?;
+
+    // video_finder_db_table contains addlink window download information
+    connection.execute(
+        "
+        CREATE TABLE IF NOT EXISTS video_finder_db_table(
+            ID INTEGER PRIMARY KEY,
+            video_gid TEXT,
+            audio_gid TEXT,
+            video_completed TEXT,
+            audio_completed TEXT,
+            muxing_status TEXT,
+            checking TEXT,
+            download_path TEXT,
+            FOREIGN KEY(video_gid) REFERENCES download_db_table(gid)
+            ON DELETE CASCADE,
+            FOREIGN KEY(audio_gid) REFERENCES download_db_table(gid)
+            ON DELETE CASCADE
+        )
+        ",
+        (),
+    )?;
+    Ok(())
+}
+
+// this function replaces:
+// GB >> GiB
+// MB >> MiB
+// KB >> KiB
+// Read this link for more information:
+// https://en.wikipedia.org/wiki/Orders_of_magnitude_(data)
+fn correctUnits(connection: &Connection) -> Result<(), GhermezError> {
+    for [old_unit, new_unit] in [["KB", "KiB"], ["MB", "MiB"], ["GB", "GiB"]] {
+        connection.execute(
+            "
+            UPDATE download_db_table SET
+            size = replace(size, ?1, ?2),
+            rate = replace(rate, ?1, ?2),
+            downloaded_size = replace(downloaded_size, ?1, ?2)
+            ",
+            [old_unit, new_unit],
+        )?;
+    }
+    Ok(())
+}
+
+// retry_db_table contains number of automatic retries and last error of downloads
+fn createRetryTable(connection: &Connection) -> Result<(), GhermezError> {
+    connection.execute(
+        "
+        CREATE TABLE IF NOT EXISTS retry_db_table(
+            gid TEXT PRIMARY KEY,
+            attempts INTEGER NOT NULL DEFAULT 0,
+            last_error TEXT,
+            FOREIGN KEY(gid) REFERENCES download_db_table(gid)
+            ON UPDATE CASCADE
+            ON DELETE CASCADE
+        )
+        ",
+        (),
+    )?;
+    Ok(())
+}
+
+// schedule_db_table contains settings of downloads that are waiting for their start_time,
+// so scheduler can start them after persepolis is restarted. settings are saved as JSON.
+fn createScheduleTable(connection: &Connection) -> Result<(), GhermezError> {
+    connection.execute(
+        "
+        CREATE TABLE IF NOT EXISTS schedule_db_table(
+            gid TEXT PRIMARY KEY,
+            settings TEXT NOT NULL,
+            FOREIGN KEY(gid) REFERENCES download_db_table(gid)
+            ON UPDATE CASCADE
+            ON DELETE CASCADE
+        )
+        ",
+        (),
+    )?;
+    Ok(())
+}
 
-use crate::useful_tools::determineConfigFolder;
+// category_item contains items of categories in the order of download table.
+// every download is an item of its category and 'All Downloads'.
+fn createCategoryItemTable(connection: &Connection) -> Result<(), GhermezError> {
+    connection.execute(
+        "
+        CREATE TABLE IF NOT EXISTS category_item(
+            category TEXT NOT NULL,
+            gid TEXT NOT NULL,
+            position INTEGER NOT NULL,
+            PRIMARY KEY(category, gid),
+            FOREIGN KEY(category) REFERENCES category_db_table(category)
+            ON UPDATE CASCADE
+            ON DELETE CASCADE,
+            FOREIGN KEY(gid) REFERENCES download_db_table(gid)
+            ON UPDATE CASCADE
+            ON DELETE CASCADE
+        )
+        ",
+        (),
+    )?;
+    connection.execute(
+        "CREATE INDEX IF NOT EXISTS category_item_position ON category_item(category, position)",
+        (),
+    )?;
+    migrateGidLists(connection)
+}
+
+// sizes and rates of download_db_table were saved like "1.5 GiB" and "100 KiB/s", percents
+// like "45%" and dates like "2017/09/09 , 13:12:26". table is rebuilt with INTEGER columns
+// for them and old values are converted, so downloads can be sorted and filtered by size and date.
+fn numericDownloadColumns(connection: &Connection) -> Result<(), GhermezError> {
+    connection.execute(
+        "
+        CREATE TABLE download_db_table_new(
+            file_name TEXT,
+            status TEXT,
+            -- byte
+            size INTEGER,
+            downloaded_size INTEGER,
+            -- from 0 to 100
+            percent INTEGER,
+            connections TEXT,
+            -- byte per second
+            rate INTEGER,
+            estimate_time_left TEXT,
+            gid TEXT PRIMARY KEY,
+            link TEXT,
+            -- unix time
+            first_try_date INTEGER,
+            last_try_date INTEGER,
+            category TEXT,
+            FOREIGN KEY(category) REFERENCES category_db_table(category)
+            ON UPDATE CASCADE
+            ON DELETE CASCADE
+        )",
+        (),
+    )?;
+
+    let mut stmt = connection.prepare("SELECT * FROM download_db_table")?;
+    let rows = stmt
+        .query_map([], |row| {
+            DOWNLOAD_COLUMNS
+                .iter()
+                .enumerate()
+                .map(|(i, column)| {
+                    let text = match row.get::<_, Value>(i)? {
+                        Value::Integer(x) => x.to_string(),
+                        Value::Real(x) => x.to_string(),
+                        Value::Text(x) => x,
+                        _ => return Ok(Value::Null),
+                    };
+                    let number = match *column {
+                        "size" | "downloaded_size" | "rate" => sizeInBytes(&text),
+                        "percent" => text
+                            .trim_end_matches('%')
+                            .trim()
+                            .parse::<f64>()
+                            .ok()
+                            .map(|percent| percent as i64),
+                        "first_try_date" | "last_try_date" => dateToTimestamp(&text),
+                        _ => return Ok(Value::Text(text)),
+                    };
+                    Ok(number.map_or(Value::Null, Value::Integer))
+                })
+                .collect::<rusqlite::Result<Vec<Value>>>()
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+    for row in rows {
+        connection.execute(
+            "INSERT INTO download_db_table_new VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)",
+            params_from_iter(row),
+        )?;
+    }
+
+    // foreign keys of other tables refer to the new table after renaming
+    connection.execute("DROP TABLE download_db_table", ())?;
+    connection.execute(
+        "ALTER TABLE download_db_table_new RENAME TO download_db_table",
+        (),
+    )?;
+    Ok(())
+}
+
+// download_fts is full text index of file name and link of downloads, see searchDownloads.
+// rowid of download_fts is rowid of download_db_table and triggers keep it up to date,
+// so a migration that rebuilds download_db_table must rebuild download_fts too.
+fn createDownloadSearchTable(connection: &Connection) -> Result<(), GhermezError> {
+    connection.execute_batch(
+        "
+        CREATE VIRTUAL TABLE IF NOT EXISTS download_fts USING fts5(file_name, link);
+        DELETE FROM download_fts;
+        INSERT INTO download_fts(rowid, file_name, link)
+        SELECT rowid, file_name, link FROM download_db_table;
+
+        CREATE TRIGGER IF NOT EXISTS download_fts_insert AFTER INSERT ON download_db_table
+        BEGIN
+            INSERT INTO download_fts(rowid, file_name, link)
+            VALUES (new.rowid, new.file_name, new.link);
+        END;
+        CREATE TRIGGER IF NOT EXISTS download_fts_delete AFTER DELETE ON download_db_table
+        BEGIN
+            DELETE FROM download_fts WHERE rowid = old.rowid;
+        END;
+        CREATE TRIGGER IF NOT EXISTS download_fts_update
+        AFTER UPDATE OF file_name, link ON download_db_table
+        BEGIN
+            UPDATE download_fts SET file_name = new.file_name, link = new.link
+            WHERE rowid = old.rowid;
+        END;
+        ",
+    )?;
+    Ok(())
+}
+
+// plugins_db_table contains links that sends by browser plugins.
+fn createPluginsTable(connection: &Connection) -> Result<(), GhermezError> {
+    connection.execute(
+        "
+        CREATE TABLE IF NOT EXISTS plugins_db_table(
+            ID INTEGER PRIMARY KEY,
+            link TEXT,
+            referer TEXT,
+            load_cookies TEXT,
+            user_agent TEXT,
+            header TEXT,
+            out TEXT,
+            status TEXT
+            )
+        ",
+        (),
+    )?;
+    Ok(())
+}
 
 // This class manages TempDB
 // TempDB contains gid of active downloads in every session.
@@ -21,51 +634,48 @@ pub struct TempDB {
 #[pymethods]
 impl TempDB {
     #[new]
-    fn new() -> Self {
+    fn new() -> Result<Self, GhermezError> {
         // temp_db saves in RAM
-        Self {
-            connection: Arc::new(Mutex::new(Connection::open_in_memory().unwrap())),
-        }
+        Ok(Self {
+            connection: Arc::new(Mutex::new(Connection::open_in_memory()?)),
+        })
     }
 
     // temp_db_table contains gid of active downloads.
 
-    fn createTables(&self) {
+    fn createTables(&self) -> Result<(), GhermezError> {
         // lock data base
         let mut connection = self.connection.lock().unwrap();
-        let transaction = connection.transaction().unwrap();
-        transaction
-            .execute(
-                "
+        let transaction = connection.transaction()?;
+        transaction.execute(
+            "
             CREATE TABLE IF NOT EXISTS single_db_table (
                 ID INTEGER,
                 gid TEXT PRIMARY KEY,
                 status TEXT,
                 shutdown TEXT
             )",
-                (),
-            )
-            .unwrap();
-        transaction
-            .execute(
-                "
+            (),
+        )?;
+        transaction.execute(
+            "
             CREATE TABLE IF NOT EXISTS queue_db_table (
                 ID INTEGER,
                 category TEXT PRIMARY KEY,
                 shutdown TEXT
             )",
-                (),
-            )
-            .unwrap();
-        transaction.commit().unwrap();
+            (),
+        )?;
+        transaction.commit()?;
+        Ok(())
     }
 
     // insert new item in single_db_table
-    fn insertInSingleTable(&self, gid: &str) {
+    fn insertInSingleTable(&self, gid: &str) -> Result<(), GhermezError> {
         // lock data base
         let connection = self.connection.lock().unwrap();
         connection
-            .execute(
+            .prepare_cached(
                 "
             INSERT INTO single_db_table VALUES (
                 NULL,
@@ -73,146 +683,141 @@ impl TempDB {
                 'active',
                 NULL
             )",
-                [gid],
-            )
-            .unwrap();
+            )?
+            .execute([gid])?;
+        Ok(())
     }
 
     // insert new item in queue_db_table
-    fn insertInQueueTable(&self, category: &str) {
+    fn insertInQueueTable(&self, category: &str) -> Result<(), GhermezError> {
         // lock data base
         let connection = self.connection.lock().unwrap();
         connection
-            .execute(
+            .prepare_cached(
                 "
             INSERT INTO queue_db_table VALUES (
                 NULL,
                 ?1,
                 NULL
             )",
-                [category],
-            )
-            .unwrap();
+            )?
+            .execute([category])?;
+        Ok(())
     }
 
     // this method updates single_db_table
-    fn updateSingleTable(&self, dict: HashMap<&str, &str>) {
+    fn updateSingleTable(&self, dict: HashMap<&str, &str>) -> Result<(), GhermezError> {
         // lock data base
         let connection = self.connection.lock().unwrap();
 
         // update data base if value for the keys is not None
         connection
-            .execute(
+            .prepare_cached(
                 "
                 UPDATE single_db_table SET
                 shutdown = coalesce(?1, shutdown),
                 status = coalesce(?2, status)
                 WHERE gid = ?3
                 ",
-                [dict.get(&"shutdown"), dict.get(&"status"), dict.get(&"gid")],
-            )
-            .unwrap();
+            )?
+            .execute([dict.get(&"shutdown"), dict.get(&"status"), dict.get(&"gid")])?;
+        Ok(())
     }
 
     // this method updates queue_db_table
-    fn updateQueueTable(&self, dict: HashMap<&str, &str>) {
+    fn updateQueueTable(&self, dict: HashMap<&str, &str>) -> Result<(), GhermezError> {
         // lock data base
         let connection = self.connection.lock().unwrap();
 
         // update data base if value for the keys is not None
         connection
-            .execute(
+            .prepare_cached(
                 "
                 UPDATE queue_db_table SET
                 shutdown = coalesce(?1, shutdown)
                 WHERE category = ?2
                 ",
-                [dict.get(&"shutdown"), dict.get(&"category")],
-            )
-            .unwrap();
+            )?
+            .execute([dict.get(&"shutdown"), dict.get(&"category")])?;
+        Ok(())
     }
 
     // this method returns gid of active downloads
-    fn returnActiveGids(&self) -> Vec<String> {
+    fn returnActiveGids(&self) -> Result<Vec<String>, GhermezError> {
         // lock data base
         let connection = self.connection.lock().unwrap();
-        let mut stmt = connection
-            .prepare(
-                "
+        let mut stmt = connection.prepare_cached(
+            "
         SELECT gid FROM single_db_table WHERE status = 'active'
         ",
-            )
-            .unwrap();
+        )?;
 
         let mut gid_list = vec![];
 
-        let mut rows = stmt.query([]).unwrap();
-        while let Some(row) = rows.next().unwrap() {
-            gid_list.push(row.get(0).unwrap());
+        let mut rows = stmt.query([])?;
+        while let Some(row) = rows.next()? {
+            gid_list.push(row.get(0)?);
         }
-        gid_list
+        Ok(gid_list)
     }
 
     // this method returns shutdown value for specific gid
-    fn returnGid(&self, gid: &str) -> Option<HashMap<String, String>> {
+    fn returnGid(&self, gid: &str) -> Result<Option<HashMap<String, String>>, GhermezError> {
         // lock data base
         let connection = self.connection.lock().unwrap();
-        let mut stmt = connection
-            .prepare(
-                "
+        let mut stmt = connection.prepare_cached(
+            "
                 SELECT shutdown, status FROM single_db_table WHERE gid = ?1
                 ",
-            )
-            .unwrap();
+        )?;
 
-        let mut rows = stmt.query([gid]).unwrap();
-        if let Some(row) = rows.next().unwrap() {
-            return Some(HashMap::from([
+        let mut rows = stmt.query([gid])?;
+        if let Some(row) = rows.next()? {
+            return Ok(Some(HashMap::from([
                 (
                     "shutdown".to_string(),
                     row.get(0).unwrap_or("NULL".to_string()),
                 ),
-                ("status".to_string(), row.get(1).unwrap()),
-            ]));
+                ("status".to_string(), row.get(1)?),
+            ])));
         }
-        None
+        Ok(None)
     }
 
     // This method returns values of columns for specific category
-    fn returnCategory(&self, category: &str) -> Option<HashMap<String, String>> {
+    fn returnCategory(
+        &self,
+        category: &str,
+    ) -> Result<Option<HashMap<String, String>>, GhermezError> {
         // lock data base
         let connection = self.connection.lock().unwrap();
-        let mut stmt = connection
-            .prepare(
-                "
+        let mut stmt = connection.prepare_cached(
+            "
                 SELECT shutdown FROM queue_db_table WHERE category = ?1
                 ",
-            )
-            .unwrap();
+        )?;
 
-        let mut rows = stmt.query([category]).unwrap();
-        if let Some(row) = rows.next().unwrap() {
-            return Some(HashMap::from([(
-                "shutdown".to_string(),
-                row.get(0).unwrap(),
-            )]));
+        let mut rows = stmt.query([category])?;
+        if let Some(row) = rows.next()? {
+            return Ok(Some(HashMap::from([("shutdown".to_string(), row.get(0)?)])));
         }
-        None
+        Ok(None)
     }
 
-    fn resetDataBase(&self) {
+    fn resetDataBase(&self) -> Result<(), GhermezError> {
         // lock data base
         let mut connection = self.connection.lock().unwrap();
-        let transaction = connection.transaction().unwrap();
+        let transaction = connection.transaction()?;
 
         // delete all items
         transaction
-            .execute("DELETE FROM single_db_table", ())
-            .unwrap();
+            .prepare_cached("DELETE FROM single_db_table")?
+            .execute(())?;
         transaction
-            .execute("DELETE FROM queue_db_table", ())
-            .unwrap();
-        transaction.commit().unwrap();
+            .prepare_cached("DELETE FROM queue_db_table")?
+            .execute(())?;
+        transaction.commit()?;
+        Ok(())
     }
 }
 
@@ -226,130 +831,151 @@ pub struct PluginsDB {
 #[pymethods]
 impl PluginsDB {
     #[new]
-    fn new() -> Self {
-        Self {
-            connection: Arc::new(Mutex::new(
-                Connection::open(determineConfigFolder().join("plugins.db")).unwrap(),
-            )),
-        }
+    fn new() -> Result<Self, GhermezError> {
+        Ok(Self {
+            connection: Arc::new(Mutex::new(Connection::open(
+                determineConfigFolder().join("plugins.db"),
+            )?)),
+        })
     }
 
-    // plugins_db_table contains links that sends by browser plugins.
-
-    fn createTables(&self) {
+    fn createTables(&self) -> Result<(), GhermezError> {
         // lock data base
-        let connection = self.connection.lock().unwrap();
-        connection
-            .execute(
-                "
-            CREATE TABLE IF NOT EXISTS plugins_db_table(
-                ID INTEGER PRIMARY KEY,
-                link TEXT,
-                referer TEXT,
-                load_cookies TEXT,
-                user_agent TEXT,
-                header TEXT,
-                out TEXT,
-                status TEXT
-                )
-            ",
-                (),
-            )
-            .unwrap();
+        let mut connection = self.connection.lock().unwrap();
+        migrate(&mut connection, &PLUGINS_MIGRATIONS)
     }
 
     // insert new items in plugins_db_table
-    fn insertInPluginsTable(&self, list: Vec<HashMap<&str, &str>>) {
+    fn insertInPluginsTable(&self, list: Vec<HashMap<&str, &str>>) -> Result<(), GhermezError> {
         // lock data base
         let mut connection = self.connection.lock().unwrap();
-        let mut transaction = connection.transaction().unwrap();
+        let mut transaction = connection.transaction()?;
 
         let transaction_size = 5;
         for (i, dict) in list.into_iter().enumerate() {
             if i % transaction_size == 0 {
-                transaction.commit().unwrap();
-                transaction = connection.transaction().unwrap();
+                transaction.commit()?;
+                transaction = connection.transaction()?;
             }
+            let header = dict.get("header").map(|header| headerJson(header));
             transaction
-                .execute(
+                .prepare_cached(
                     "
                     INSERT INTO plugins_db_table VALUES(
                         NULL, ?1, ?2, ?3, ?4, ?5, ?6, 'new'
                     )
                 ",
-                    [
-                        dict.get("link"),
-                        dict.get("referer"),
-                        dict.get("load_cookies"),
-                        dict.get("user_agent"),
-                        dict.get("header"),
-                        dict.get("out"),
-                    ],
-                )
-                .unwrap();
+                )?
+                .execute([
+                    dict.get("link"),
+                    dict.get("referer"),
+                    dict.get("load_cookies"),
+                    dict.get("user_agent"),
+                    header.as_deref().as_ref(),
+                    dict.get("out"),
+                ])?;
         }
-        transaction.commit().unwrap();
+        transaction.commit()?;
+        Ok(())
     }
 
-    fn returnNewLinks(&self) -> Vec<HashMap<String, String>> {
+    fn returnNewLinks(&self) -> Result<Vec<HashMap<String, String>>, GhermezError> {
         // lock data base
         let connection = self.connection.lock().unwrap();
-        let mut stmt = connection
-            .prepare(
-                "
+        let mut stmt = connection.prepare_cached(
+            "
                 SELECT link, referer, load_cookies, user_agent, header, out
                 FROM plugins_db_table WHERE status = 'new'
             ",
-            )
-            .unwrap();
+        )?;
 
         // chang all rows status to 'old'
         connection
-            .execute(
+            .prepare_cached(
                 "
             UPDATE plugins_db_table SET
             status = 'old'
             WHERE status = 'new'
             ",
-                (),
-            )
-            .unwrap();
+            )?
+            .execute(())?;
 
         let mut new_list = vec![];
 
         // put the information in tuples in dictionary format and add it to new_list
-        let mut rows = stmt.query([]).unwrap();
-        while let Some(row) = rows.next().unwrap() {
+        let mut rows = stmt.query([])?;
+        while let Some(row) = rows.next()? {
             new_list.push(HashMap::from([
-                ("link".to_string(), row.get(0).unwrap()),
-                ("referer".to_string(), row.get(1).unwrap()),
-                ("load_cookies".to_string(), row.get(2).unwrap()),
-                ("user_agent".to_string(), row.get(3).unwrap()),
-                ("header".to_string(), row.get(4).unwrap()),
-                ("out".to_string(), row.get(5).unwrap()),
+                ("link".to_string(), row.get(0)?),
+                ("referer".to_string(), row.get(1)?),
+                ("load_cookies".to_string(), row.get(2)?),
+                ("user_agent".to_string(), row.get(3)?),
+                ("header".to_string(), row.get(4)?),
+                ("out".to_string(), row.get(5)?),
             ]));
         }
 
         // return results in list format!
         // every member of this list is a dictionary.
         // every dictionary contains download information
-        new_list
+        Ok(new_list)
+    }
+
+    // return name and value pairs of header of the last request that browser plugins
+    // sent for link
+    fn searchHeaderInPluginsTable(
+        &self,
+        link: &str,
+    ) -> Result<Vec<(String, String)>, GhermezError> {
+        // lock data base
+        let connection = self.connection.lock().unwrap();
+
+        let header: Option<Option<String>> = connection
+            .prepare_cached(
+                "SELECT header FROM plugins_db_table WHERE link = ?1 ORDER BY ID DESC LIMIT 1",
+            )?
+            .query_row([link], |row| row.get(0))
+            .optional()?;
+        Ok(header
+            .flatten()
+            .map_or(vec![], |header| headerPairs(&header)))
     }
 
     // delete old links from data base
-    fn deleteOldLinks(&self) {
+    fn deleteOldLinks(&self) -> Result<(), GhermezError> {
         // lock data base
         let connection = self.connection.lock().unwrap();
 
         connection
-            .execute("DELETE FROM plugins_db_table WHERE status = 'old'", ())
-            .unwrap();
+            .prepare_cached("DELETE FROM plugins_db_table WHERE status = 'old'")?
+            .execute(())?;
+        Ok(())
     }
 }
 
+// start_time and end_time of a download in addlink_db_table
+pub(crate) struct ScheduledDownload {
+    pub gid: String,
+    pub status: String,
+    pub start_time: Option<String>,
+    pub end_time: Option<String>,
+    // unix time that download is added, first_try_date in download_db_table
+    pub added: Option<i64>,
+}
+
+// start time and end time of a category.
+// they are None if they are not enabled.
+pub(crate) struct CategoryWindow {
+    pub category: String,
+    pub start_time: Option<String>,
+    pub end_time: Option<String>,
+}
+
 // ghermez main data base contains downloads information
 // This class is managing ghermez.db
+// clones share the same connection.
 #[pyclass]
+#[derive(Clone)]
 pub struct DataBase {
     connection: Arc<Mutex<Connection>>,
 }
@@ -357,140 +983,21 @@ pub struct DataBase {
 #[pymethods]
 impl DataBase {
     #[new]
-    fn new() -> Self {
-        let connection = Arc::new(Mutex::new(
-            Connection::open(determineConfigFolder().join("ghermez.db")).unwrap(),
-        ));
-
-        let cnn = connection.lock().unwrap();
-
-        // To debuging
-        // cnn.trace(Some(|s| {
-        //     println!("{s}");
-        // }));
-
-        // turn FOREIGN KEY Support on!
-        cnn.execute("PRAGMA foreign_keys = ON", ()).unwrap();
-        drop(cnn);
-
-        Self { connection }
+    fn new() -> Result<Self, GhermezError> {
+        Self::open(determineConfigFolder().join("ghermez.db"))
     }
 
     // queues_list contains name of categories and category settings
-    fn createTables(&self) {
+    pub(crate) fn createTables(&self) -> Result<(), GhermezError> {
         // lock data base
         let mut connection = self.connection.lock().unwrap();
-        let transaction = connection.transaction().unwrap();
-
-        // Create category_db_table and add 'All Downloads' and 'Single Downloads' to it
-        transaction
-            .execute(
-                "
-                CREATE TABLE IF NOT EXISTS category_db_table(
-                    category TEXT PRIMARY KEY,
-                    start_time_enable TEXT,
-                    start_time TEXT,
-                    end_time_enable TEXT,
-                    end_time TEXT,
-                    reverse TEXT,
-                    limit_enable TEXT,
-                    limit_value TEXT,
-                    after_download TEXT,
-                    gid_list TEXT
-                )",
-                (),
-            )
-            .unwrap();
-
-        // download table contains download table download items information
-        transaction
-            .execute(
-                "
-                CREATE TABLE IF NOT EXISTS download_db_table(
-                    file_name TEXT,
-                    status TEXT,
-                    size TEXT,
-                    downloaded_size TEXT,
-                    percent TEXT,
-                    connections TEXT,
-                    rate TEXT,
-                    estimate_time_left TEXT,
-                    gid TEXT PRIMARY KEY,
-                    link TEXT,
-                    first_try_date TEXT,
-                    last_try_date TEXT,
-                    category TEXT,
-                    FOREIGN KEY(category) REFERENCES category_db_table(category)
-                    ON UPDATE CASCADE
-                    ON DELETE CASCADE
-                )",
-                (),
-            )
-            .unwrap();
-
-        // addlink_db_table contains addlink window download information
-        transaction
-            .execute(
-                "
-            CREATE TABLE IF NOT EXISTS addlink_db_table(
-                ID INTEGER PRIMARY KEY,
-                gid TEXT,
-                out TEXT,
-                start_time TEXT,
-                end_time TEXT,
-                link TEXT,
-                ip TEXT,
-                port TEXT,
-                proxy_user TEXT,
-                proxy_passwd TEXT,
-                download_user TEXT,
-                download_passwd TEXT,
-                connections TEXT,
-                limit_value TEXT,
-                download_path TEXT,
-                referer TEXT,
-                load_cookies TEXT,
-                user_agent TEXT,
-                header TEXT,
-                after_download TEXT,
-                FOREIGN KEY(gid) REFERENCES download_db_table(gid)
-                ON UPDATE CASCADE
-                ON DELETE CASCADE
-            )
-            ",
-                (),
-            )
-            .unwrap();
-
-        // video_finder_db_table contains addlink window download information
-        transaction
-            .execute(
-                "
-            CREATE TABLE IF NOT EXISTS video_finder_db_table(
-                ID INTEGER PRIMARY KEY,
-                video_gid TEXT,
-                audio_gid TEXT,
-                video_completed TEXT,
-                audio_completed TEXT,
-                muxing_status TEXT,
-                checking TEXT,
-                download_path TEXT,
-                FOREIGN KEY(video_gid) REFERENCES download_db_table(gid)
-                ON DELETE CASCADE,
-                FOREIGN KEY(audio_gid) REFERENCES download_db_table(gid)
-                ON DELETE CASCADE
-            )
-            ",
-                (),
-            )
-            .unwrap();
-        transaction.commit().unwrap();
+        migrate(&mut connection, &MIGRATIONS)?;
 
         // job is done! open the lock
         drop(connection);
 
         // add 'All Downloads' and 'Single Downloads' to the category_db_table if they wasn't added.
-        let answer = self.searchCategoryInCategoryTable("All Downloads");
+        let answer = self.searchCategoryInCategoryTable("All Downloads")?;
         if answer.is_none() {
             let all_downloads_dict = HashMap::from([
                 ("category", "All Downloads"),
@@ -502,7 +1009,6 @@ impl DataBase {
                 ("limit_enable", "no"),
                 ("limit_value", "OK"),
                 ("after_download", "no"),
-                ("gid_list", "[]"),
             ]);
             let single_downloads_dict = HashMap::from([
                 ("category", "Single Downloads"),
@@ -514,14 +1020,13 @@ impl DataBase {
                 ("limit_enable", "no"),
                 ("limit_value", "OK"),
                 ("after_download", "no"),
-                ("gid_list", "[]"),
             ]);
-            self.insertInCategoryTable(all_downloads_dict);
-            self.insertInCategoryTable(single_downloads_dict);
+            self.insertInCategoryTable(all_downloads_dict)?;
+            self.insertInCategoryTable(single_downloads_dict)?;
         }
 
         // add default queue with the name 'Scheduled Downloads'
-        let answer = self.searchCategoryInCategoryTable("Scheduled Downloads");
+        let answer = self.searchCategoryInCategoryTable("Scheduled Downloads")?;
         if answer.is_none() {
             let scheduled_downloads_dict = HashMap::from([
                 ("category", "Scheduled Downloads"),
@@ -533,140 +1038,99 @@ impl DataBase {
                 ("limit_enable", "no"),
                 ("limit_value", "OK"),
                 ("after_download", "no"),
-                ("gid_list", "[]"),
             ]);
-            self.insertInCategoryTable(scheduled_downloads_dict);
+            self.insertInCategoryTable(scheduled_downloads_dict)?;
         }
+        Ok(())
     }
 
     // insert new category in category_db_table
-    fn insertInCategoryTable(&self, dict: HashMap<&str, &str>) {
+    pub(crate) fn insertInCategoryTable(
+        &self,
+        dict: HashMap<&str, &str>,
+    ) -> Result<(), GhermezError> {
         // lock data base
         let connection = self.connection.lock().unwrap();
+        // items of category are saved in category_item and gid_list column is NULL
         connection
-            .execute(
+            .prepare_cached(
                 "
             INSERT INTO category_db_table VALUES (
-                ?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10
+                ?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, NULL
             )
             ",
-                [
-                    dict.get("category"),
-                    dict.get("start_time_enable"),
-                    dict.get("start_time"),
-                    dict.get("end_time_enable"),
-                    dict.get("end_time"),
-                    dict.get("reverse"),
-                    dict.get("limit_enable"),
-                    dict.get("limit_value"),
-                    dict.get("after_download"),
-                    dict.get("gid_list"),
-                ],
-            )
-            .unwrap();
+            )?
+            .execute([
+                dict.get("category"),
+                dict.get("start_time_enable"),
+                dict.get("start_time"),
+                dict.get("end_time_enable"),
+                dict.get("end_time"),
+                dict.get("reverse"),
+                dict.get("limit_enable"),
+                dict.get("limit_value"),
+                dict.get("after_download"),
+            ])?;
+        Ok(())
     }
 
     // insert in to download_db_table in ghermez.db
-    fn insertInDownloadTable(&self, list: Vec<HashMap<&str, &str>>) {
+    pub(crate) fn insertInDownloadTable(
+        &self,
+        list: Vec<HashMap<&str, &str>>,
+    ) -> Result<(), GhermezError> {
         // lock data base
         let mut connection = self.connection.lock().unwrap();
-        let mut transaction = connection.transaction().unwrap();
+        let mut transaction = connection.transaction()?;
 
         let transaction_size = 5;
-        for (i, dict) in list.clone().into_iter().enumerate() {
+        for (i, dict) in list.into_iter().enumerate() {
             if i % transaction_size == 0 {
-                transaction.commit().unwrap();
-                transaction = connection.transaction().unwrap();
+                transaction.commit()?;
+                transaction = connection.transaction()?;
             }
             transaction
-                .execute(
+                .prepare_cached(
                     "
                 INSERT INTO download_db_table VALUES (
                     ?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13
                 )
                 ",
-                    [
-                        dict.get("file_name"),
-                        dict.get("status"),
-                        dict.get("size"),
-                        dict.get("downloaded_size"),
-                        dict.get("percent"),
-                        dict.get("connections"),
-                        dict.get("rate"),
-                        dict.get("estimate_time_left"),
-                        dict.get("gid"),
-                        dict.get("link"),
-                        dict.get("first_try_date"),
-                        dict.get("last_try_date"),
-                        dict.get("category"),
-                    ],
-                )
-                .unwrap();
-        }
-        transaction.commit().unwrap();
+                )?
+                .execute(params_from_iter(downloadValues(&dict)))?;
 
-        // job is done! open the lock
-        drop(connection);
-
-        if !list.is_empty() {
-            let dict = list.last().unwrap();
-
-            // item must be inserted to gid_list of 'All Downloads' and gid_list of category
-            // find download category and gid
-            let category = dict.get("category").unwrap();
-
-            // get category_dict from data base
-            let mut category_dict = self.searchCategoryInCategoryTable(category).unwrap();
-
-            // get all_downloads_dict from data base
-            let mut all_downloads_dict =
-                self.searchCategoryInCategoryTable("All Downloads").unwrap();
-
-            // get gid_list
-            let re = Regex::new(r"[\d\w]+").unwrap();
-            let mut category_gid_list: Vec<_> = re
-                .find_iter(category_dict.get("gid_list").unwrap())
-                .map(|m| m.as_str())
-                .collect();
-            let mut all_downloads_gid_list: Vec<_> = re
-                .find_iter(all_downloads_dict.get("gid_list").unwrap())
-                .map(|m| m.as_str())
-                .collect();
-
-            for dict in list {
-                let gid = dict.get("gid").unwrap();
-
-                // add gid of item to gid_list
-                category_gid_list.push(gid);
-                all_downloads_gid_list.push(gid);
+            // item must be added to the end of category and 'All Downloads'
+            let gid = dict.get("gid").copied().unwrap_or_default();
+            if let Some(category) = dict.get("category") {
+                appendCategoryItem(&transaction, category, gid)?;
             }
-
-            *category_dict.get_mut("gid_list").unwrap() = format!("{category_gid_list:?}");
-            *all_downloads_dict.get_mut("gid_list").unwrap() =
-                format!("{all_downloads_gid_list:?}");
-
-            // update category_db_table
-            self.updateCategoryTable(vec![all_downloads_dict]);
-            self.updateCategoryTable(vec![category_dict]);
+            appendCategoryItem(&transaction, "All Downloads", gid)?;
         }
+        transaction.commit()?;
+        Ok(())
     }
 
     // insert in addlink table in ghermez.db
-    fn insertInAddLinkTable(&self, list: Vec<HashMap<&str, &str>>) {
+    pub(crate) fn insertInAddLinkTable(
+        &self,
+        list: Vec<HashMap<&str, &str>>,
+    ) -> Result<(), GhermezError> {
         // lock data base
         let mut connection = self.connection.lock().unwrap();
-        let mut transaction = connection.transaction().unwrap();
+        let mut transaction = connection.transaction()?;
 
         let transaction_size = 5;
         for (i, dict) in list.clone().into_iter().enumerate() {
             if i % transaction_size == 0 {
-                transaction.commit().unwrap();
-                transaction = connection.transaction().unwrap();
+                transaction.commit()?;
+                transaction = connection.transaction()?;
             }
 
+            let header = dict.get("header").map(|header| headerJson(header));
+
             // first column and after download column is NULL
             transaction
-                .execute(
+                .prepare_cached(
                     "
                     INSERT INTO addlink_db_table VALUES(NULL,
                         ?1, ?2, ?3, ?4, ?5, ?6, ?7,
@@ -675,205 +1139,182 @@ impl DataBase {
                         NULL
                     )
                 ",
-                    [
-                        dict.get("gid"),
-                        dict.get("out"),
-                        dict.get("start_time"),
-                        dict.get("end_time"),
-                        dict.get("link"),
-                        dict.get("ip"),
-                        dict.get("port"),
-                        dict.get("proxy_user"),
-                        dict.get("proxy_passwd"),
-                        dict.get("download_user"),
-                        dict.get("download_passwd"),
-                        dict.get("connections"),
-                        dict.get("limit_value"),
-                        dict.get("download_path"),
-                        dict.get("referer"),
-                        dict.get("load_cookies"),
-                        dict.get("user_agent"),
-                        dict.get("header"),
-                    ],
-                )
-                .unwrap();
+                )?
+                .execute([
+                    dict.get("gid"),
+                    dict.get("out"),
+                    dict.get("start_time"),
+                    dict.get("end_time"),
+                    dict.get("link"),
+                    dict.get("ip"),
+                    dict.get("port"),
+                    dict.get("proxy_user"),
+                    dict.get("proxy_passwd"),
+                    dict.get("download_user"),
+                    dict.get("download_passwd"),
+                    dict.get("connections"),
+                    dict.get("limit_value"),
+                    dict.get("download_path"),
+                    dict.get("referer"),
+                    dict.get("load_cookies"),
+                    dict.get("user_agent"),
+                    header.as_deref().as_ref(),
+                ])?;
         }
-        transaction.commit().unwrap();
+        transaction.commit()?;
+        Ok(())
     }
 
-    fn insertInVideoFinderTable(&self, list: Vec<HashMap<&str, &str>>) {
+    fn insertInVideoFinderTable(&self, list: Vec<HashMap<&str, &str>>) -> Result<(), GhermezError> {
         // lock data base
         let mut connection = self.connection.lock().unwrap();
-        let mut transaction = connection.transaction().unwrap();
+        let mut transaction = connection.transaction()?;
 
         let transaction_size = 5;
         for (i, dict) in list.clone().into_iter().enumerate() {
             if i % transaction_size == 0 {
-                transaction.commit().unwrap();
-                transaction = connection.transaction().unwrap();
+                transaction.commit()?;
+                transaction = connection.transaction()?;
             }
 
             // first column is NULL
             transaction
-                .execute(
+                .prepare_cached(
                     "
                         INSERT INTO video_finder_db_table VALUES(
                             NULL, ?1, ?2, ?3, ?4, ?5, ?6, ?7
                         )
                     ",
-                    [
-                        dict.get("video_gid"),
-                        dict.get("audio_gid"),
-                        dict.get("video_completed"),
-                        dict.get("audio_completed"),
-                        dict.get("muxing_status"),
-                        dict.get("checking"),
-                        dict.get("download_path"),
-                    ],
-                )
-                .unwrap();
+                )?
+                .execute([
+                    dict.get("video_gid"),
+                    dict.get("audio_gid"),
+                    dict.get("video_completed"),
+                    dict.get("audio_completed"),
+                    dict.get("muxing_status"),
+                    dict.get("checking"),
+                    dict.get("download_path"),
+                ])?;
         }
-        transaction.commit().unwrap();
+        transaction.commit()?;
+        Ok(())
     }
 
-    fn searchGidInVideoFinderTable(&self, gid: &str) -> Option<HashMap<String, String>> {
+    fn searchGidInVideoFinderTable(
+        &self,
+        gid: &str,
+    ) -> Result<Option<HashMap<String, String>>, GhermezError> {
         // lock data base
         let connection = self.connection.lock().unwrap();
 
-        let mut stmt = connection
-            .prepare(
-                "
+        let mut stmt = connection.prepare_cached(
+            "
                 SELECT * FROM video_finder_db_table WHERE audio_gid = ?1 OR video_gid = ?2
                 ",
-            )
-            .unwrap();
-
-        let mut rows = stmt.query([gid, gid]).unwrap();
-        if let Some(row) = rows.next().unwrap() {
-            return Some(HashMap::from([
-                ("video_gid".to_string(), row.get(1).unwrap()),
-                ("audio_gid".to_string(), row.get(2).unwrap()),
-                ("video_completed".to_string(), row.get(3).unwrap()),
-                ("audio_completed".to_string(), row.get(4).unwrap()),
-                ("muxing_status".to_string(), row.get(5).unwrap()),
-                ("checking".to_string(), row.get(6).unwrap()),
-                ("download_path".to_string(), row.get(7).unwrap()),
-            ]));
+        )?;
+
+        let mut rows = stmt.query([gid, gid])?;
+        if let Some(row) = rows.next()? {
+            return Ok(Some(HashMap::from([
+                ("video_gid".to_string(), row.get(1)?),
+                ("audio_gid".to_string(), row.get(2)?),
+                ("video_completed".to_string(), row.get(3)?),
+                ("audio_completed".to_string(), row.get(4)?),
+                ("muxing_status".to_string(), row.get(5)?),
+                ("checking".to_string(), row.get(6)?),
+                ("download_path".to_string(), row.get(7)?),
+            ])));
         }
-        None
+        Ok(None)
     }
 
-    fn searchGidInDownloadTable(&self, gid: &str) -> Option<HashMap<String, String>> {
+    pub(crate) fn searchGidInDownloadTable(
+        &self,
+        gid: &str,
+    ) -> Result<Option<HashMap<String, String>>, GhermezError> {
         // lock data base
         let connection = self.connection.lock().unwrap();
 
-        let mut stmt = connection
-            .prepare(
-                "
+        let mut stmt = connection.prepare_cached(
+            "
                 SELECT * FROM download_db_table WHERE gid = ?1
                 ",
-            )
-            .unwrap();
-
-        let mut rows = stmt.query([gid]).unwrap();
-        if let Some(row) = rows.next().unwrap() {
-            return Some(HashMap::from([
-                ("file_name".to_string(), row.get(0).unwrap()),
-                ("status".to_string(), row.get(1).unwrap()),
-                ("size".to_string(), row.get(2).unwrap()),
-                ("downloaded_size".to_string(), row.get(3).unwrap()),
-                ("percent".to_string(), row.get(4).unwrap()),
-                ("connections".to_string(), row.get(5).unwrap()),
-                ("rate".to_string(), row.get(6).unwrap()),
-                ("estimate_time_left".to_string(), row.get(7).unwrap()),
-                ("gid".to_string(), row.get(8).unwrap()),
-                ("link".to_string(), row.get(9).unwrap()),
-                ("first_try_date".to_string(), row.get(10).unwrap()),
-                ("last_try_date".to_string(), row.get(11).unwrap()),
-                ("category".to_string(), row.get(12).unwrap()),
-            ]));
+        )?;
+
+        let mut rows = stmt.query([gid])?;
+        if let Some(row) = rows.next()? {
+            return Ok(Some(
+                downloadDict(row)?
+                    .into_iter()
+                    .map(|(key, value)| (key.to_string(), value))
+                    .collect(),
+            ));
         }
-        None
+        Ok(None)
     }
 
     // return all items in download_db_table
-    // '*' for category, cause that method returns all items.
+    // None for category, cause that method returns all items.
     fn returnItemsInDownloadTable(
         &self,
         category: Option<&str>,
-    ) -> HashMap<String, HashMap<&str, String>> {
+    ) -> Result<HashMap<String, HashMap<&str, String>>, GhermezError> {
         // lock data base
         let connection = self.connection.lock().unwrap();
 
-        let query = if category.is_some() {
-            format!(
-                "SELECT * FROM download_db_table WHERE category = '{}'",
-                category.unwrap()
-            )
-        } else {
-            "SELECT * FROM download_db_table".to_string()
-        };
-
-        let mut stmt = connection.prepare(&query).unwrap();
-        let rows = stmt
-            .query_map([], |row| {
-                // change format of tuple to dictionary
-                Ok(HashMap::from([
-                    ("file_name", row.get::<usize, String>(0).unwrap()),
-                    ("status", row.get(1).unwrap()),
-                    ("size", row.get(2).unwrap()),
-                    ("downloaded_size", row.get(3).unwrap()),
-                    ("percent", row.get(4).unwrap()),
-                    ("connections", row.get(5).unwrap()),
-                    ("rate", row.get(6).unwrap()),
-                    ("estimate_time_left", row.get(7).unwrap()),
-                    ("gid", row.get(8).unwrap()),
-                    ("link", row.get(9).unwrap()),
-                    ("first_try_date", row.get(10).unwrap()),
-                    ("last_try_date", row.get(11).unwrap()),
-                    ("category", row.get(12).unwrap()),
-                ]))
-            })
-            .unwrap();
+        let mut stmt = connection
+            .prepare_cached("SELECT * FROM download_db_table WHERE ?1 IS NULL OR category = ?1")?;
+        // change format of tuple to dictionary
+        let rows = stmt.query_map([category], downloadDict)?;
 
         let mut downloads_dict = HashMap::new();
         for download in rows {
             // add dict to the downloads_dict
             // gid is key and dict is value
-            let download = download.unwrap();
+            let download = download?;
             downloads_dict.insert(download.get("gid").unwrap().to_string(), download);
         }
-        downloads_dict
+        Ok(downloads_dict)
     }
 
-    // this method checks existence of a link in addlink_db_table
-    fn searchLinkInAddLinkTable(&self, link: &str) -> bool {
+    // search downloads of history, see DownloadQuery for filters and sort order.
+    // it returns a page of rows with sizes in byte and dates in unix time.
+    pub(crate) fn searchDownloads(
+        &self,
+        query: DownloadQuery,
+    ) -> Result<Vec<DownloadRow>, GhermezError> {
         // lock data base
         let connection = self.connection.lock().unwrap();
-
-        let result = connection.execute("SELECT * FROM addlink_db_table WHERE link = (?1)", [link]);
-
-        if result.is_ok_and(|r| r > 0) {
-            return true;
-        }
-        false
+        queryDownloads(&connection, &query)
     }
 
-    fn searchGidInAddLinkTable(&self, gid: &str) -> Option<HashMap<String, String>> {
+    // this method checks existence of a link in addlink_db_table
+    pub(crate) fn searchLinkInAddLinkTable(&self, link: &str) -> Result<bool, GhermezError> {
         // lock data base
         let connection = self.connection.lock().unwrap();
 
-        let mut stmt = connection
-            .prepare(
-                "
+        let exists = connection
+            .prepare_cached("SELECT EXISTS(SELECT 1 FROM addlink_db_table WHERE link = ?1)")?
+            .query_row([link], |row| row.get(0))?;
+        Ok(exists)
+    }
+
+    pub(crate) fn searchGidInAddLinkTable(
+        &self,
+        gid: &str,
+    ) -> Result<Option<HashMap<String, String>>, GhermezError> {
+        // lock data base
+        let connection = self.connection.lock().unwrap();
+
+        let mut stmt = connection.prepare_cached(
+            "
                 SELECT * FROM addlink_db_table WHERE gid = ?1
                 ",
-            )
-            .unwrap();
+        )?;
 
-        let mut rows = stmt.query([gid]).unwrap();
-        if let Some(row) = rows.next().unwrap() {
-            return Some(HashMap::from([
+        let mut rows = stmt.query([gid])?;
+        if let Some(row) = rows.next()? {
+            return Ok(Some(HashMap::from([
                 ("gid".to_string(), row.get(1).unwrap_or("NULL".to_string())),
                 ("out".to_string(), row.get(2).unwrap_or("NULL".to_string())),
                 (
@@ -935,77 +1376,122 @@ impl DataBase {
                     "after_download".to_string(),
                     row.get(19).unwrap_or("NULL".to_string()),
                 ),
-            ]));
+            ])));
         }
-        None
+        Ok(None)
+    }
+
+    // return name and value pairs of header of gid in addlink_db_table
+    pub(crate) fn searchHeaderInAddLinkTable(
+        &self,
+        gid: &str,
+    ) -> Result<Vec<(String, String)>, GhermezError> {
+        // lock data base
+        let connection = self.connection.lock().unwrap();
+
+        let header: Option<Option<String>> = connection
+            .prepare_cached("SELECT header FROM addlink_db_table WHERE gid = ?1")?
+            .query_row([gid], |row| row.get(0))
+            .optional()?;
+        Ok(header
+            .flatten()
+            .map_or(vec![], |header| headerPairs(&header)))
     }
 
     // return items in addlink_db_table
-    // '*' for category, cause that method returns all items.
+    // None for category, cause that method returns all items.
     fn returnItemsInAddLinkTable(
         &self,
         category: Option<&str>,
-    ) -> HashMap<String, HashMap<String, String>> {
+    ) -> Result<HashMap<String, HashMap<String, Option<String>>>, GhermezError> {
         // lock data base
         let connection = self.connection.lock().unwrap();
 
-        let query = if category.is_some() {
-            format!(
-                "SELECT * FROM addlink_db_table WHERE category = '{}'",
-                category.unwrap()
-            )
-        } else {
-            "SELECT * FROM addlink_db_table".to_string()
-        };
-
-        let mut stmt = connection.prepare(&query).unwrap();
-        let rows = stmt
-            .query_map([], |row| {
-                // change format of tuple to dictionary
-                Ok(HashMap::from([
-                    ("gid".to_string(), row.get::<usize, String>(1).unwrap()),
-                    ("out".to_string(), row.get(2).unwrap()),
-                    ("start_time".to_string(), row.get(3).unwrap()),
-                    ("end_time".to_string(), row.get(4).unwrap()),
-                    ("link".to_string(), row.get(5).unwrap()),
-                    ("ip".to_string(), row.get(6).unwrap()),
-                    ("port".to_string(), row.get(7).unwrap()),
-                    ("proxy_user".to_string(), row.get(8).unwrap()),
-                    ("proxy_passwd".to_string(), row.get(9).unwrap()),
-                    ("download_user".to_string(), row.get(10).unwrap()),
-                    ("download_passwd".to_string(), row.get(11).unwrap()),
-                    ("connections".to_string(), row.get(12).unwrap()),
-                    ("limit_value".to_string(), row.get(13).unwrap()),
-                    ("download_path".to_string(), row.get(14).unwrap()),
-                    ("referer".to_string(), row.get(15).unwrap()),
-                    ("load_cookies".to_string(), row.get(16).unwrap()),
-                    ("user_agent".to_string(), row.get(17).unwrap()),
-                    ("header".to_string(), row.get(18).unwrap()),
-                    ("after_download".to_string(), row.get(19).unwrap()),
-                ]))
-            })
-            .unwrap();
+        // category of items is in download_db_table
+        let mut stmt = connection.prepare_cached(
+            "
+            SELECT * FROM addlink_db_table WHERE ?1 IS NULL
+            OR gid IN (SELECT gid FROM download_db_table WHERE category = ?1)
+            ",
+        )?;
+        let rows = stmt.query_map([category], |row| {
+            // change format of tuple to dictionary
+            Ok(HashMap::from([
+                ("gid".to_string(), row.get::<usize, Option<String>>(1)?),
+                ("out".to_string(), row.get(2)?),
+                ("start_time".to_string(), row.get(3)?),
+                ("end_time".to_string(), row.get(4)?),
+                ("link".to_string(), row.get(5)?),
+                ("ip".to_string(), row.get(6)?),
+                ("port".to_string(), row.get(7)?),
+                ("proxy_user".to_string(), row.get(8)?),
+                ("proxy_passwd".to_string(), row.get(9)?),
+                ("download_user".to_string(), row.get(10)?),
+                ("download_passwd".to_string(), row.get(11)?),
+                ("connections".to_string(), row.get(12)?),
+                ("limit_value".to_string(), row.get(13)?),
+                ("download_path".to_string(), row.get(14)?),
+                ("referer".to_string(), row.get(15)?),
+                ("load_cookies".to_string(), row.get(16)?),
+                ("user_agent".to_string(), row.get(17)?),
+                ("header".to_string(), row.get(18)?),
+                ("after_download".to_string(), row.get(19)?),
+            ]))
+        })?;
 
         let mut addlink_dict = HashMap::new();
         for download in rows {
             // add dict to the addlink_dict
             // gid as key and dict as value
-            let download = download.unwrap();
-            addlink_dict.insert(download.get("gid").unwrap().to_string(), download);
+            let download = download?;
+            let gid = download["gid"].clone().unwrap_or_default();
+            addlink_dict.insert(gid, download);
         }
-        addlink_dict
+        Ok(addlink_dict)
+    }
+
+    // return number of automatic retries and last error of gid.
+    // keys are gid, attempts and last_error.
+    pub(crate) fn searchGidInRetryTable(
+        &self,
+        gid: &str,
+    ) -> Result<Option<HashMap<String, String>>, GhermezError> {
+        // lock data base
+        let connection = self.connection.lock().unwrap();
+
+        let mut stmt = connection.prepare_cached(
+            "
+                SELECT gid, attempts, last_error FROM retry_db_table WHERE gid = ?1
+                ",
+        )?;
+
+        let mut rows = stmt.query([gid])?;
+        if let Some(row) = rows.next()? {
+            return Ok(Some(HashMap::from([
+                ("gid".to_string(), row.get(0)?),
+                ("attempts".to_string(), row.get::<_, i64>(1)?.to_string()),
+                (
+                    "last_error".to_string(),
+                    row.get(2).unwrap_or("NULL".to_string()),
+                ),
+            ])));
+        }
+        Ok(None)
     }
 
     // this method updates download_db_table
-    fn updateDownloadTable(&self, list: Vec<HashMap<&str, &str>>) {
+    pub(crate) fn updateDownloadTable(
+        &self,
+        list: Vec<HashMap<&str, &str>>,
+    ) -> Result<(), GhermezError> {
         // lock data base
         let mut connection = self.connection.lock().unwrap();
-        let transaction = connection.transaction().unwrap();
+        let transaction = connection.transaction()?;
 
         for dict in list {
             // update data base if value for the keys is not None
             transaction
-                .execute(
+                .prepare_cached(
                     "
                 UPDATE download_db_table SET
                 file_name = coalesce(?1, file_name),
@@ -1016,43 +1502,42 @@ impl DataBase {
                 connections = coalesce(?6, connections),
                 rate = coalesce(?7, rate),
                 estimate_time_left = coalesce(?8, estimate_time_left),
-                link = coalesce(?9, link),
-                first_try_date = coalesce(?10, first_try_date),
-                last_try_date = coalesce(?11, last_try_date),
-                category = coalesce(?12, category)
-                WHERE gid = ?13
+                link = coalesce(?10, link),
+                first_try_date = coalesce(?11, first_try_date),
+                last_try_date = coalesce(?12, last_try_date),
+                category = coalesce(?13, category)
+                WHERE gid = ?9
             ",
-                    [
-                        dict.get("file_name"),
-                        dict.get("status"),
-                        dict.get("size"),
-                        dict.get("downloaded_size"),
-                        dict.get("percent"),
-                        dict.get("connections"),
-                        dict.get("rate"),
-                        dict.get("estimate_time_left"),
-                        dict.get("link"),
-                        dict.get("first_try_date"),
-                        dict.get("last_try_date"),
-                        dict.get("category"),
-                        dict.get("gid"),
-                    ],
-                )
-                .unwrap();
+                )?
+                .execute(params_from_iter(downloadValues(&dict)))?;
+
+            // download is moved to another category
+            if let (Some(category), Some(gid)) = (dict.get("category"), dict.get("gid")) {
+                transaction
+                    .prepare_cached(
+                        "
+                    DELETE FROM category_item WHERE gid = ?1
+                    AND category NOT IN (?2, 'All Downloads')
+                    ",
+                    )?
+                    .execute([gid, category])?;
+                appendCategoryItem(&transaction, category, gid)?;
+            }
         }
-        transaction.commit().unwrap();
+        transaction.commit()?;
+        Ok(())
     }
 
     // this method updates category_db_table
-    fn updateCategoryTable(&self, list: Vec<HashMap<&str, String>>) {
+    fn updateCategoryTable(&self, list: Vec<HashMap<&str, String>>) -> Result<(), GhermezError> {
         // lock data base
         let mut connection = self.connection.lock().unwrap();
-        let transaction = connection.transaction().unwrap();
+        let transaction = connection.transaction()?;
 
         for dict in list {
             // update data base if value for the keys is not None
             transaction
-                .execute(
+                .prepare_cached(
                     "
                     UPDATE category_db_table SET
                     start_time_enable = coalesce(?1, start_time_enable),
@@ -1062,37 +1547,37 @@ impl DataBase {
                     reverse = coalesce(?5, reverse),
                     limit_enable = coalesce(?6, limit_enable),
                     limit_value = coalesce(?7, limit_value),
-                    after_download = coalesce(?8, after_download),
-                    gid_list = coalesce(?9, gid_list)
-                    WHERE category = ?10
+                    after_download = coalesce(?8, after_download)
+                    WHERE category = ?9
                     ",
-                    [
-                        dict.get("start_time_enable"),
-                        dict.get("start_time"),
-                        dict.get("end_time_enable"),
-                        dict.get("end_time"),
-                        dict.get("reverse"),
-                        dict.get("limit_enable"),
-                        dict.get("limit_value"),
-                        dict.get("after_download"),
-                        dict.get("gid_list"),
-                        dict.get("category"),
-                    ],
-                )
-                .unwrap();
+                )?
+                .execute([
+                    dict.get("start_time_enable"),
+                    dict.get("start_time"),
+                    dict.get("end_time_enable"),
+                    dict.get("end_time"),
+                    dict.get("reverse"),
+                    dict.get("limit_enable"),
+                    dict.get("limit_value"),
+                    dict.get("after_download"),
+                    dict.get("category"),
+                ])?;
         }
-        transaction.commit().unwrap();
+        transaction.commit()?;
+        Ok(())
     }
 
-    fn updateAddLinkTable(&self, list: Vec<HashMap<&str, &str>>) {
+    fn updateAddLinkTable(&self, list: Vec<HashMap<&str, &str>>) -> Result<(), GhermezError> {
         // lock data base
         let mut connection = self.connection.lock().unwrap();
-        let transaction = connection.transaction().unwrap();
+        let transaction = connection.transaction()?;
 
         for dict in list {
+            let header = dict.get("header").map(|header| headerJson(header));
+
             // update data base if value for the keys is not None
             transaction
-                .execute(
+                .prepare_cached(
                     "
                     UPDATE addlink_db_table SET
                     out = coalesce(?1, out),
@@ -1115,43 +1600,43 @@ impl DataBase {
                     after_download = coalesce(?18 , after_download)
                     WHERE gid = ?19
                     ",
-                    [
-                        dict.get("out"),
-                        dict.get("start_time"),
-                        dict.get("end_time"),
-                        dict.get("link"),
-                        dict.get("ip"),
-                        dict.get("port"),
-                        dict.get("proxy_user"),
-                        dict.get("proxy_passwd"),
-                        dict.get("download_user"),
-                        dict.get("download_passwd"),
-                        dict.get("connections"),
-                        dict.get("limit_value"),
-                        dict.get("download_path"),
-                        dict.get("referer"),
-                        dict.get("load_cookies"),
-                        dict.get("user_agent"),
-                        dict.get("header"),
-                        dict.get("after_download"),
-                        dict.get("gid"),
-                    ],
-                )
-                .unwrap();
+                )?
+                .execute([
+                    dict.get("out"),
+                    dict.get("start_time"),
+                    dict.get("end_time"),
+                    dict.get("link"),
+                    dict.get("ip"),
+                    dict.get("port"),
+                    dict.get("proxy_user"),
+                    dict.get("proxy_passwd"),
+                    dict.get("download_user"),
+                    dict.get("download_passwd"),
+                    dict.get("connections"),
+                    dict.get("limit_value"),
+                    dict.get("download_path"),
+                    dict.get("referer"),
+                    dict.get("load_cookies"),
+                    dict.get("user_agent"),
+                    header.as_deref().as_ref(),
+                    dict.get("after_download"),
+                    dict.get("gid"),
+                ])?;
         }
-        transaction.commit().unwrap();
+        transaction.commit()?;
+        Ok(())
     }
 
-    fn updateVideoFinderTable(&self, list: Vec<HashMap<&str, &str>>) {
+    fn updateVideoFinderTable(&self, list: Vec<HashMap<&str, &str>>) -> Result<(), GhermezError> {
         // lock data base
         let mut connection = self.connection.lock().unwrap();
-        let transaction = connection.transaction().unwrap();
+        let transaction = connection.transaction()?;
 
         for dict in list {
-            if dict.get("video_gid").is_some() {
+            if dict.contains_key("video_gid") {
                 // update data base if value for the keys is not None
                 transaction
-                    .execute(
+                    .prepare_cached(
                         "
                         UPDATE video_finder_db_table SET
                         video_completed = coalesce(?1, video_completed),
@@ -1161,20 +1646,19 @@ impl DataBase {
                         download_path = coalesce(?5, download_path)
                         WHERE video_gid = ?6
                         ",
-                        [
-                            dict.get("video_completed"),
-                            dict.get("audio_completed"),
-                            dict.get("muxing_status"),
-                            dict.get("checking"),
-                            dict.get("download_path"),
-                            dict.get("video_gid"),
-                        ],
-                    )
-                    .unwrap();
-            } else if dict.get("audio_gid").is_some() {
+                    )?
+                    .execute([
+                        dict.get("video_completed"),
+                        dict.get("audio_completed"),
+                        dict.get("muxing_status"),
+                        dict.get("checking"),
+                        dict.get("download_path"),
+                        dict.get("video_gid"),
+                    ])?;
+            } else if dict.contains_key("audio_gid") {
                 // update data base if value for the keys is not None
                 transaction
-                    .execute(
+                    .prepare_cached(
                         "
                         UPDATE video_finder_db_table SET
                         video_completed = coalesce(?1, video_completed),
@@ -1184,461 +1668,1259 @@ impl DataBase {
                         download_path = coalesce(?5, download_path)
                         WHERE audio_gid = ?6
                         ",
-                        [
-                            dict.get("video_completed"),
-                            dict.get("audio_completed"),
-                            dict.get("muxing_status"),
-                            dict.get("checking"),
-                            dict.get("download_path"),
-                            dict.get("audio_gid"),
-                        ],
-                    )
-                    .unwrap();
+                    )?
+                    .execute([
+                        dict.get("video_completed"),
+                        dict.get("audio_completed"),
+                        dict.get("muxing_status"),
+                        dict.get("checking"),
+                        dict.get("download_path"),
+                        dict.get("audio_gid"),
+                    ])?;
             }
         }
-        transaction.commit().unwrap();
+        transaction.commit()?;
+        Ok(())
     }
 
-    fn setDefaultGidInAddlinkTable(
+    pub(crate) fn setDefaultGidInAddlinkTable(
         &self,
         gid: &str,
         start_time: bool,
         end_time: bool,
         after_download: bool,
-    ) {
+    ) -> Result<(), GhermezError> {
         // lock data base
         let connection = self.connection.lock().unwrap();
 
         if start_time {
             connection
-                .execute(
+                .prepare_cached(
                     "
                     UPDATE addlink_db_table SET
                     start_time = NULL
                     WHERE gid = ?1
                 ",
-                    [gid],
-                )
-                .unwrap();
+                )?
+                .execute([gid])?;
         }
         if end_time {
             connection
-                .execute(
+                .prepare_cached(
                     "
                     UPDATE addlink_db_table SET
                     end_time = NULL
                     WHERE gid = ?1
                 ",
-                    [gid],
-                )
-                .unwrap();
+                )?
+                .execute([gid])?;
         }
         if after_download {
             connection
-                .execute(
+                .prepare_cached(
                     "
                     UPDATE addlink_db_table SET
                     after_download = NULL
                     WHERE gid = ?1
                 ",
-                    [gid],
-                )
-                .unwrap();
+                )?
+                .execute([gid])?;
         }
+        Ok(())
     }
 
-    fn searchCategoryInCategoryTable(&self, category: &str) -> Option<HashMap<&str, String>> {
+    pub(crate) fn searchCategoryInCategoryTable(
+        &self,
+        category: &str,
+    ) -> Result<Option<HashMap<&str, String>>, GhermezError> {
         // lock data base
         let connection = self.connection.lock().unwrap();
 
-        let mut stmt = connection
-            .prepare(
-                "
+        let mut stmt = connection.prepare_cached(
+            "
                 SELECT * FROM category_db_table WHERE category = ?1
                 ",
-            )
-            .unwrap();
+        )?;
+
+        let mut rows = stmt.query([category])?;
+        if let Some(row) = rows.next()? {
+            return Ok(Some(HashMap::from([
+                ("category", row.get(0)?),
+                ("start_time_enable", row.get(1)?),
+                ("start_time", row.get(2)?),
+                ("end_time_enable", row.get(3)?),
+                ("end_time", row.get(4)?),
+                ("reverse", row.get(5)?),
+                ("limit_enable", row.get(6)?),
+                ("limit_value", row.get(7)?),
+                ("after_download", row.get(8)?),
+            ])));
+        }
+        Ok(None)
+    }
 
-        let mut rows = stmt.query([category]).unwrap();
-        if let Some(row) = rows.next().unwrap() {
-            return Some(HashMap::from([
-                ("category", row.get(0).unwrap()),
-                ("start_time_enable", row.get(1).unwrap()),
-                ("start_time", row.get(2).unwrap()),
-                ("end_time_enable", row.get(3).unwrap()),
-                ("end_time", row.get(4).unwrap()),
-                ("reverse", row.get(5).unwrap()),
-                ("limit_enable", row.get(6).unwrap()),
-                ("limit_value", row.get(7).unwrap()),
-                ("after_download", row.get(8).unwrap()),
-                ("gid_list", row.get(9).unwrap()),
-            ]));
+    // return gid of items in category, from bottom to top of download table
+    pub(crate) fn gidListOfCategory(&self, category: &str) -> Result<Vec<String>, GhermezError> {
+        self.existingCategory(category)?;
+
+        // lock data base
+        let connection = self.connection.lock().unwrap();
+
+        let mut stmt = connection.prepare_cached(
+            "
+                SELECT gid FROM category_item WHERE category = ?1 ORDER BY position
+                ",
+        )?;
+        let gid_list = stmt
+            .query_map([category], |row| row.get(0))?
+            .collect::<Result<_, _>>()?;
+        Ok(gid_list)
+    }
+
+    // change order of items in category. gid_list must contain every item of category once.
+    fn updateGidListOfCategory(
+        &self,
+        category: &str,
+        gid_list: Vec<String>,
+    ) -> Result<(), GhermezError> {
+        let mut old_gid_list = self.gidListOfCategory(category)?;
+        let mut new_gid_list = gid_list.clone();
+        old_gid_list.sort();
+        new_gid_list.sort();
+        if old_gid_list != new_gid_list {
+            return Err(GhermezError::Database(format!(
+                "gid_list doesn't match items of category {category:?}"
+            )));
         }
-        None
+
+        // lock data base
+        let mut connection = self.connection.lock().unwrap();
+        let transaction = connection.transaction()?;
+        for (position, gid) in gid_list.iter().enumerate() {
+            transaction
+                .prepare_cached(
+                    "UPDATE category_item SET position = ?1 WHERE category = ?2 AND gid = ?3",
+                )?
+                .execute((position as i64, category, gid))?;
+        }
+        transaction.commit()?;
+        Ok(())
     }
 
     // return categories name
-    fn categoriesList(&self) -> Vec<String> {
+    fn categoriesList(&self) -> Result<Vec<String>, GhermezError> {
         // lock data base
         let connection = self.connection.lock().unwrap();
 
-        let mut stmt = connection
-            .prepare("SELECT category FROM category_db_table ORDER BY ROWID")
-            .unwrap();
+        let mut stmt =
+            connection.prepare_cached("SELECT category FROM category_db_table ORDER BY ROWID")?;
 
         let mut queues_list = vec![];
 
-        let mut rows = stmt.query([]).unwrap();
-        while let Some(row) = rows.next().unwrap() {
-            queues_list.push(row.get(0).unwrap());
+        let mut rows = stmt.query([])?;
+        while let Some(row) = rows.next()? {
+            queues_list.push(row.get(0)?);
         }
-        queues_list
+        Ok(queues_list)
     }
 
-    fn setDBTablesToDefaultValue(&self) {
+    fn setDBTablesToDefaultValue(&self) -> Result<(), GhermezError> {
         // lock data base
         let mut connection = self.connection.lock().unwrap();
-        let transaction = connection.transaction().unwrap();
+        let transaction = connection.transaction()?;
 
         // change start_time_enable , end_time_enable , reverse ,
         // limit_enable , after_download value to default value !
         transaction
-            .execute(
+            .prepare_cached(
                 "
                 UPDATE category_db_table SET start_time_enable = 'no', end_time_enable = 'no',
                 reverse = 'no', limit_enable = 'no', after_download = 'no'
             ",
-                (),
-            )
-            .unwrap();
+            )?
+            .execute(())?;
 
         // change status of download to 'stopped' if status isn't 'complete' or 'error'
         transaction
-            .execute("
-                UPDATE download_db_table SET status = 'stopped' WHERE status NOT IN ('complete', 'error')
-            ", ())
-            .unwrap();
+            .prepare_cached(
+                "
+                UPDATE download_db_table SET status = 'stopped'
+                WHERE status NOT IN ('complete', 'error')
+            ",
+            )?
+            .execute(())?;
 
         // change start_time and end_time and
         // after_download value to None in addlink_db_table!
         transaction
-            .execute(
+            .prepare_cached(
                 "
                 UPDATE addlink_db_table SET start_time = NULL,
                 end_time = NULL, after_download = NULL
             ",
-                (),
-            )
-            .unwrap();
+            )?
+            .execute(())?;
 
         // change checking value to no in video_finder_db_table
         transaction
-            .execute(
+            .prepare_cached(
                 "
                 UPDATE video_finder_db_table SET checking = 'no'
             ",
-                (),
-            )
-            .unwrap();
+            )?
+            .execute(())?;
 
-        transaction.commit().unwrap();
+        transaction.commit()?;
+        Ok(())
     }
 
-    fn findActiveDownloads(&self, category: Option<&str>) -> Vec<String> {
+    fn findActiveDownloads(&self, category: Option<&str>) -> Result<Vec<String>, GhermezError> {
         // lock data base
         let connection = self.connection.lock().unwrap();
 
         // find download items is download_db_table with status = "downloading" or "waiting" or paused or scheduled
-        let query = if category.is_some() {
-            format!(
-                "
-            SELECT gid FROM download_db_table WHERE (category = '{}')
+        // all categories are searched if category is None
+        let mut stmt = connection.prepare_cached(
+            "
+            SELECT gid FROM download_db_table WHERE (?1 IS NULL OR category = ?1)
             AND (status = 'downloading' OR status = 'waiting'
             OR status = 'scheduled' OR status = 'paused')
             ",
-                category.unwrap()
-            )
-        } else {
-            "SELECT gid FROM download_db_table WHERE
-            (status = 'downloading' OR status = 'waiting'
-            OR status = 'scheduled' OR status = 'paused')"
-                .to_string()
-        };
-        let mut stmt = connection.prepare(&query).unwrap();
+        )?;
 
         let mut gid_list = vec![];
 
-        let mut rows = stmt.query([]).unwrap();
-        while let Some(row) = rows.next().unwrap() {
-            gid_list.push(row.get(0).unwrap());
+        let mut rows = stmt.query([category])?;
+        while let Some(row) = rows.next()? {
+            gid_list.push(row.get(0)?);
         }
 
-        gid_list
+        Ok(gid_list)
     }
 
     // this method returns items with 'downloading' or 'waiting' status
-    fn returnDownloadingItems(&self) -> Vec<String> {
+    fn returnDownloadingItems(&self) -> Result<Vec<String>, GhermezError> {
         // lock data base
         let connection = self.connection.lock().unwrap();
 
         // find download items is download_db_table with status = "downloading" or "waiting" or paused or scheduled
-        let mut stmt = connection
-            .prepare(
-                "
+        let mut stmt = connection.prepare_cached(
+            "
                 SELECT gid FROM download_db_table WHERE
                 (status = 'downloading' OR status = 'waiting')
             ",
-            )
-            .unwrap();
+        )?;
 
         let mut gid_list = vec![];
 
-        let mut rows = stmt.query([]).unwrap();
-        while let Some(row) = rows.next().unwrap() {
-            gid_list.push(row.get(0).unwrap());
+        let mut rows = stmt.query([])?;
+        while let Some(row) = rows.next()? {
+            gid_list.push(row.get(0)?);
         }
 
-        gid_list
+        Ok(gid_list)
     }
 
     // this method returns items with 'paused' status.
-    fn returnPausedItems(&self) -> Vec<String> {
+    fn returnPausedItems(&self) -> Result<Vec<String>, GhermezError> {
         // lock data base
         let connection = self.connection.lock().unwrap();
 
         // find download items is download_db_table with status = "downloading" or "waiting" or paused or scheduled
-        let mut stmt = connection
-            .prepare(
-                "
+        let mut stmt = connection.prepare_cached(
+            "
                 SELECT gid FROM download_db_table WHERE (status = 'paused')
             ",
-            )
-            .unwrap();
+        )?;
 
         let mut gid_list = vec![];
 
-        let mut rows = stmt.query([]).unwrap();
-        while let Some(row) = rows.next().unwrap() {
-            gid_list.push(row.get(0).unwrap());
+        let mut rows = stmt.query([])?;
+        while let Some(row) = rows.next()? {
+            gid_list.push(row.get(0)?);
         }
 
-        gid_list
+        Ok(gid_list)
     }
 
     // return all video_gids and audio_gids in video_finder_db_table
-    fn returnVideoFinderGids(&self) -> (Vec<String>, Vec<String>, Vec<String>) {
+    fn returnVideoFinderGids(&self) -> Result<VideoFinderGids, GhermezError> {
         // lock data base
         let connection = self.connection.lock().unwrap();
 
-        let mut stmt = connection
-            .prepare(
-                "
+        let mut stmt = connection.prepare_cached(
+            "
                 SELECT video_gid, audio_gid FROM video_finder_db_table
             ",
-            )
-            .unwrap();
+        )?;
 
         let mut gid_list: Vec<String> = vec![];
         let mut video_gid_list: Vec<String> = vec![];
         let mut audio_gid_list: Vec<String> = vec![];
 
-        let mut rows = stmt.query([]).unwrap();
-        while let Some(row) = rows.next().unwrap() {
-            gid_list.push(row.get(0).unwrap());
-            video_gid_list.push(row.get(0).unwrap());
+        let mut rows = stmt.query([])?;
+        while let Some(row) = rows.next()? {
+            gid_list.push(row.get(0)?);
+            video_gid_list.push(row.get(0)?);
 
-            gid_list.push(row.get(1).unwrap());
-            audio_gid_list.push(row.get(1).unwrap());
-        }
-        (gid_list, video_gid_list, audio_gid_list)
-    }
-
-    // This method deletes a category from category_db_table
-    fn deleteCategory(&self, category: &str) {
-        // delete gids of this category from gid_list of 'All Downloads'
-        let category_dict = self.searchCategoryInCategoryTable(category).unwrap();
-        let mut all_downloads_dict = self.searchCategoryInCategoryTable("All Downloads").unwrap();
-
-        // get gid_list
-        let re = Regex::new(r"[\d\w]+").unwrap();
-        let category_gid_list: Vec<_> = re
-            .find_iter(category_dict.get("gid_list").unwrap())
-            .map(|m| m.as_str())
-            .collect();
-        let all_downloads_gid_list: Vec<_> = re
-            .find_iter(all_downloads_dict.get("gid_list").unwrap())
-            .map(|m| m.as_str())
-            .collect();
-        let mut new_all_downloads_gid_list = all_downloads_gid_list.clone();
-
-        // delete item from all_downloads_gid_list
-        for gid in category_gid_list {
-            new_all_downloads_gid_list.remove(
-                new_all_downloads_gid_list
-                    .iter()
-                    .position(|x| *x == gid)
-                    .unwrap(),
-            );
+            gid_list.push(row.get(1)?);
+            audio_gid_list.push(row.get(1)?);
         }
+        Ok((gid_list, video_gid_list, audio_gid_list))
+    }
 
-        // update category_db_table
-        *all_downloads_dict.get_mut("gid_list").unwrap() =
-            format!("{new_all_downloads_gid_list:?}");
-        self.updateCategoryTable(vec![all_downloads_dict]);
+    // This method deletes a category from category_db_table.
+    // downloads of category and their items in 'All Downloads' are deleted by foreign keys.
+    fn deleteCategory(&self, category: &str) -> Result<(), GhermezError> {
+        if category == "All Downloads" {
+            return Err(GhermezError::Database(
+                "'All Downloads' can't be deleted".to_string(),
+            ));
+        }
+        self.existingCategory(category)?;
 
         // lock data base
         let connection = self.connection.lock().unwrap();
 
         // delete category from data_base
         connection
-            .execute(
+            .prepare_cached(
                 "
                 DELETE FROM category_db_table WHERE category = ?1
             ",
-                [category],
-            )
-            .unwrap();
+            )?
+            .execute([category])?;
+        Ok(())
     }
 
     // this method deletes all items in data_base
-    fn resetDataBase(&self) {
-        // update gid_list in categories with empty gid_list
-        let all_downloads_dict = HashMap::from([
-            ("category", "All Downloads".to_string()),
-            ("gid_list", "[]".to_string()),
-        ]);
-        let single_downloads_dict = HashMap::from([
-            ("category", "Single Downloads".to_string()),
-            ("gid_list", "[]".to_string()),
-        ]);
-        let scheduled_downloads_dict = HashMap::from([
-            ("category", "Scheduled Downloads".to_string()),
-            ("gid_list", "[]".to_string()),
-        ]);
-
-        self.updateCategoryTable(vec![
-            all_downloads_dict,
-            single_downloads_dict,
-            scheduled_downloads_dict,
-        ]);
-
+    pub(crate) fn resetDataBase(&self) -> Result<(), GhermezError> {
+        // items of categories are deleted with downloads by foreign keys
         // lock data base
         let mut connection = self.connection.lock().unwrap();
-        let transaction = connection.transaction().unwrap();
+        let transaction = connection.transaction()?;
 
         // delete all items in category_db_table, except 'All Downloads' and 'Single Downloads'
-        transaction.execute("
-        DELETE FROM category_db_table WHERE category NOT IN ('All Downloads', 'Single Downloads', 'Scheduled Downloads')
-        ", ())
-        .unwrap();
         transaction
-            .execute("DELETE FROM download_db_table", ())
-            .unwrap();
+            .prepare_cached(
+                "
+                DELETE FROM category_db_table
+                WHERE category NOT IN ('All Downloads', 'Single Downloads', 'Scheduled Downloads')
+            ",
+            )?
+            .execute(())?;
         transaction
-            .execute("DELETE FROM addlink_db_table", ())
-            .unwrap();
-        transaction.commit().unwrap();
+            .prepare_cached("DELETE FROM download_db_table")?
+            .execute(())?;
+        transaction
+            .prepare_cached("DELETE FROM addlink_db_table")?
+            .execute(())?;
+        transaction.commit()?;
+        Ok(())
     }
 
-    // This method deletes a download item from download_db_table
-    fn deleteItemInDownloadTable(&self, gid: &str, category: &str) {
+    // This method deletes a download item from download_db_table.
+    // items of gid in category and 'All Downloads' are deleted by foreign keys,
+    // so category is not needed anymore.
+    pub(crate) fn deleteItemInDownloadTable(
+        &self,
+        gid: &str,
+        _category: &str,
+    ) -> Result<(), GhermezError> {
         // lock data base
         let connection = self.connection.lock().unwrap();
 
         connection
-            .execute(
+            .prepare_cached(
                 "
                 DELETE FROM download_db_table WHERE gid = ?1
             ",
-                [gid],
-            )
-            .unwrap();
+            )?
+            .execute([gid])?;
+        Ok(())
+    }
 
-        // job is done! open the lock
-        drop(connection);
+    // replace KB, MB and GB with KiB, MiB and GiB, see correctUnits.
+    // createTables does it for old data bases too.
+    fn correctDataBase(&self) -> Result<(), GhermezError> {
+        // lock data base
+        let mut connection = self.connection.lock().unwrap();
+        let transaction = connection.transaction()?;
+        correctUnits(&transaction)?;
+        transaction.commit()?;
+        Ok(())
+    }
+}
 
-        // delete item from gid_list in category and All Downloads
-        for category_name in [category, "All Downloads"] {
-            let category_dict = self.searchCategoryInCategoryTable(category_name).unwrap();
-
-            // get gid_list
-            let re = Regex::new(r"\d+").unwrap();
-            let gid_list: Vec<_> = re
-                .find_iter(category_dict.get("gid_list").unwrap())
-                .map(|m| m.as_str())
-                .collect();
-            let mut new_gid_list = gid_list.clone();
-
-            // delete item
-            for gid in &gid_list {
-                new_gid_list.remove(new_gid_list.iter().position(|x| x == gid).unwrap());
-
-                // if gid is in video_finder_db_table, both of video_gid and audio_gid must be deleted from gid_list
-                let video_finder_dictionary = self.searchGidInVideoFinderTable(gid);
-
-                if let Some(video_finder_dictionary) = video_finder_dictionary {
-                    let video_gid = video_finder_dictionary.get("video_gid").unwrap();
-                    let audio_gid = video_finder_dictionary.get("audio_gid").unwrap();
-
-                    if gid == video_gid {
-                        new_gid_list
-                            .remove(new_gid_list.iter().position(|x| x == audio_gid).unwrap());
-                    } else {
-                        new_gid_list
-                            .remove(new_gid_list.iter().position(|x| x == video_gid).unwrap());
-                    }
-                }
+impl DataBase {
+    // open data base in path. ":memory:" opens a data base in RAM.
+    pub(crate) fn open(path: impl AsRef<Path>) -> Result<Self, GhermezError> {
+        let connection = Arc::new(Mutex::new(Connection::open(path)?));
 
-                // update category_db_table
-                let mut new_category_dict = category_dict.clone();
-                *new_category_dict.get_mut("gid_list").unwrap() = format!("{new_gid_list:?}");
-                self.updateCategoryTable(vec![new_category_dict]);
-            }
-        }
+        let cnn = connection.lock().unwrap();
+
+        // To debuging
+        // cnn.trace(Some(|s| {
+        //     println!("{s}");
+        // }));
+
+        // turn FOREIGN KEY Support on!
+        cnn.execute("PRAGMA foreign_keys = ON", ())?;
+
+        // statements are prepared once and cached, there are more of them than default capacity
+        cnn.set_prepared_statement_cache_capacity(64);
+
+        // host_of(link) is used to search downloads by host
+        cnn.create_scalar_function(
+            "host_of",
+            1,
+            FunctionFlags::SQLITE_UTF8 | FunctionFlags::SQLITE_DETERMINISTIC,
+            |context| {
+                Ok(context
+                    .get::<Option<String>>(0)?
+                    .as_deref()
+                    .and_then(hostOf))
+            },
+        )?;
+        drop(cnn);
+
+        Ok(Self { connection })
     }
 
-    // this method replaces:
-    // GB >> GiB
-    // MB >> MiB
-    // KB >> KiB
-    // Read this link for more information:
-    // https://en.wikipedia.org/wiki/Orders_of_magnitude_(data)
-    fn correctDataBase(&self) {
+    // return category_dict of category or an error if category doesn't exist
+    fn existingCategory(&self, category: &str) -> Result<HashMap<&str, String>, GhermezError> {
+        self.searchCategoryInCategoryTable(category)?
+            .ok_or_else(|| GhermezError::Database(format!("category {category:?} doesn't exist")))
+    }
+
+    // return downloads that have start_time or end_time in addlink_db_table
+    pub(crate) fn scheduledDownloads(&self) -> Result<Vec<ScheduledDownload>, GhermezError> {
         // lock data base
-        let mut connection = self.connection.lock().unwrap();
-        let transaction = connection.transaction().unwrap();
+        let connection = self.connection.lock().unwrap();
 
-        for units in [["KB", "KiB"], ["MB", "MiB"], ["GB", "GiB"]] {
-            let dict = HashMap::from([("old_unit", units[0]), ("new_unit", units[1])]);
+        let mut stmt = connection.prepare_cached(
+            "
+                SELECT addlink_db_table.gid, download_db_table.status,
+                addlink_db_table.start_time, addlink_db_table.end_time,
+                download_db_table.first_try_date
+                FROM addlink_db_table JOIN download_db_table
+                ON addlink_db_table.gid = download_db_table.gid
+                WHERE addlink_db_table.start_time IS NOT NULL
+                OR addlink_db_table.end_time IS NOT NULL
+                ",
+        )?;
+        let downloads = stmt
+            .query_map([], |row| {
+                Ok(ScheduledDownload {
+                    gid: row.get(0)?,
+                    status: row.get::<_, Option<String>>(1)?.unwrap_or_default(),
+                    start_time: row.get(2)?,
+                    end_time: row.get(3)?,
+                    added: row.get(4)?,
+                })
+            })?
+            .collect::<Result<_, _>>()?;
+        Ok(downloads)
+    }
 
-            transaction
-                .execute(
-                    "
-                    UPDATE download_db_table 
-                    SET size = replace(size, ?1, ?2)
+    // return time windows of categories that have start time or end time
+    pub(crate) fn categoryWindows(&self) -> Result<Vec<CategoryWindow>, GhermezError> {
+        // lock data base
+        let connection = self.connection.lock().unwrap();
+
+        let mut stmt = connection.prepare_cached(
+            "
+                SELECT category,
+                CASE WHEN start_time_enable = 'yes' THEN start_time END,
+                CASE WHEN end_time_enable = 'yes' THEN end_time END
+                FROM category_db_table
+                WHERE start_time_enable = 'yes' OR end_time_enable = 'yes'
                 ",
-                    [dict.get("old_unit").unwrap(), dict.get("new_unit").unwrap()],
-                )
-                .unwrap();
-            transaction
-                .execute(
-                    "
-                    UPDATE download_db_table
-                    SET rate = replace(rate, ?1, ?2)
+        )?;
+        let windows = stmt
+            .query_map([], |row| {
+                Ok(CategoryWindow {
+                    category: row.get(0)?,
+                    start_time: row.get(1)?,
+                    end_time: row.get(2)?,
+                })
+            })?
+            .collect::<Result<_, _>>()?;
+        Ok(windows)
+    }
+
+    // return gid of items in category with 'downloading' or 'waiting' status
+    pub(crate) fn downloadingItemsOfCategory(
+        &self,
+        category: &str,
+    ) -> Result<Vec<String>, GhermezError> {
+        // lock data base
+        let connection = self.connection.lock().unwrap();
+
+        let mut stmt = connection.prepare_cached(
+            "
+                SELECT gid FROM download_db_table WHERE category = ?1
+                AND (status = 'downloading' OR status = 'waiting')
                 ",
-                    [dict.get("old_unit").unwrap(), dict.get("new_unit").unwrap()],
-                )
+        )?;
+        let gid_list = stmt
+            .query_map([category], |row| row.get(0))?
+            .collect::<Result<_, _>>()?;
+        Ok(gid_list)
+    }
+
+    // add one to retry attempts of gid and save its last error.
+    // it returns number of attempts.
+    pub(crate) fn recordRetry(&self, gid: &str, last_error: &str) -> Result<i64, GhermezError> {
+        // lock data base
+        let connection = self.connection.lock().unwrap();
+
+        let attempts = connection
+            .prepare_cached(
+                "
+                INSERT INTO retry_db_table (gid, attempts, last_error) VALUES (?1, 1, ?2)
+                ON CONFLICT(gid) DO UPDATE SET
+                attempts = attempts + 1,
+                last_error = excluded.last_error
+                RETURNING attempts
+                ",
+            )?
+            .query_row([gid, last_error], |row| row.get(0))?;
+        Ok(attempts)
+    }
+
+    // forget retry attempts of gid, for example after it's completed.
+    pub(crate) fn resetRetry(&self, gid: &str) -> Result<(), GhermezError> {
+        // lock data base
+        let connection = self.connection.lock().unwrap();
+
+        connection
+            .prepare_cached("DELETE FROM retry_db_table WHERE gid = ?1")?
+            .execute([gid])?;
+        Ok(())
+    }
+
+    // save settings of a scheduled gid until its start_time arrives.
+    pub(crate) fn saveScheduleSettings(
+        &self,
+        gid: &str,
+        settings: &HashMap<String, String>,
+    ) -> Result<(), GhermezError> {
+        // lock data base
+        let connection = self.connection.lock().unwrap();
+
+        let settings =
+            serde_json::to_string(settings).map_err(|e| GhermezError::Database(e.to_string()))?;
+        connection
+            .prepare_cached(
+                "
+                INSERT INTO schedule_db_table (gid, settings) VALUES (?1, ?2)
+                ON CONFLICT(gid) DO UPDATE SET settings = excluded.settings
+                ",
+            )?
+            .execute([gid, settings.as_str()])?;
+        Ok(())
+    }
+
+    // return and forget saved settings of a scheduled gid.
+    // it returns None if settings of gid are not saved.
+    pub(crate) fn takeScheduleSettings(
+        &self,
+        gid: &str,
+    ) -> Result<Option<HashMap<String, String>>, GhermezError> {
+        // lock data base
+        let connection = self.connection.lock().unwrap();
+
+        let settings: Option<String> = connection
+            .prepare_cached("DELETE FROM schedule_db_table WHERE gid = ?1 RETURNING settings")?
+            .query_row([gid], |row| row.get(0))
+            .optional()?;
+        settings
+            .map(|settings| serde_json::from_str(&settings))
+            .transpose()
+            .map_err(|e| GhermezError::Database(e.to_string()))
+    }
+
+    // return status of downloads in category. gid is key and status is value.
+    pub(crate) fn statusesOfCategory(
+        &self,
+        category: &str,
+    ) -> Result<HashMap<String, String>, GhermezError> {
+        // lock data base
+        let connection = self.connection.lock().unwrap();
+
+        let mut stmt = connection.prepare_cached(
+            "
+                SELECT gid, status FROM download_db_table WHERE category = ?1
+                ",
+        )?;
+        let statuses = stmt
+            .query_map([category], |row| {
+                Ok((
+                    row.get(0)?,
+                    row.get::<_, Option<String>>(1)?.unwrap_or_default(),
+                ))
+            })?
+            .collect::<Result<_, _>>()?;
+        Ok(statuses)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+
+    // make a data base file from a fixture of an older release
+    fn fixtureDataBase(name: &str, sql: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("ghermez_{name}_{}.db", std::process::id()));
+        let _ = fs::remove_file(&path);
+        let _ = fs::remove_file(format!("{}.v0.bak", path.display()));
+        Connection::open(&path).unwrap().execute_batch(sql).unwrap();
+        path
+    }
+
+    fn userVersion(connection: &Connection) -> i32 {
+        connection
+            .query_row("PRAGMA user_version", [], |row| row.get(0))
+            .unwrap()
+    }
+
+    #[test]
+    fn new_data_base_has_last_version() {
+        let database = DataBase::open(":memory:").unwrap();
+        database.createTables().unwrap();
+        database.createTables().unwrap();
+        let connection = database.connection.lock().unwrap();
+        assert_eq!(userVersion(&connection), MIGRATIONS.last().unwrap().version);
+    }
+
+    #[test]
+    fn persepolis_data_base_is_migrated() {
+        let path = fixtureDataBase(
+            "persepolis",
+            include_str!("../tests/fixtures/persepolis_3_2.sql"),
+        );
+        let database = DataBase::open(&path).unwrap();
+        database.createTables().unwrap();
+
+        let download_dict = database
+            .searchGidInDownloadTable("6b2c3d4e5f607182")
+            .unwrap()
+            .unwrap();
+        assert_eq!(download_dict["size"], "8 MiB");
+        assert_eq!(download_dict["downloaded_size"], "512 KiB");
+        assert_eq!(download_dict["percent"], "6%");
+        assert_eq!(download_dict["first_try_date"], "2020/01/02 , 10:00:00");
+        let (size, percent, last_try_date): (i64, i64, i64) = database
+            .connection
+            .lock()
+            .unwrap()
+            .query_row(
+                "SELECT size, percent, last_try_date FROM download_db_table WHERE gid = '6b2c3d4e5f607182'",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+            )
+            .unwrap();
+        assert_eq!(size, 8 * 1024 * 1024);
+        assert_eq!(percent, 6);
+        assert_eq!(
+            timestampToDate(last_try_date).unwrap(),
+            "2020/01/02 , 10:01:00"
+        );
+        assert_eq!(
+            database
+                .searchHeaderInAddLinkTable("5a1b2c3d4e5f6071")
+                .unwrap(),
+            vec![
+                ("X-Token".to_string(), "a=b".to_string()),
+                ("Accept".to_string(), "*/*".to_string()),
+            ]
+        );
+        assert_eq!(
+            database.gidListOfCategory("All Downloads").unwrap(),
+            strings(&["5a1b2c3d4e5f6071", "6b2c3d4e5f607182"])
+        );
+        assert!(database
+            .searchGidInRetryTable("5a1b2c3d4e5f6071")
+            .unwrap()
+            .is_none());
+
+        // old data base is copied before migration
+        let backup = format!("{}.v0.bak", path.display());
+        let connection = Connection::open(&backup).unwrap();
+        assert_eq!(userVersion(&connection), 0);
+        let size: String = connection
+            .query_row(
+                "SELECT size FROM download_db_table WHERE gid = '6b2c3d4e5f607182'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(size, "8 MB");
+        drop(connection);
+
+        // migrated data base is not copied again
+        fs::remove_file(&backup).unwrap();
+        database.createTables().unwrap();
+        assert!(!Path::new(&backup).exists());
+        assert_eq!(
+            userVersion(&database.connection.lock().unwrap()),
+            MIGRATIONS.last().unwrap().version
+        );
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn ghermez_data_base_is_migrated() {
+        let path = fixtureDataBase("ghermez", include_str!("../tests/fixtures/ghermez_0_1.sql"));
+        let database = DataBase::open(&path).unwrap();
+        database.createTables().unwrap();
+
+        // migrations that are done before are not changing anything
+        assert_eq!(
+            database.gidListOfCategory("All Downloads").unwrap(),
+            strings(&["6b2c3d4e5f607182", "5a1b2c3d4e5f6071"])
+        );
+        assert_eq!(
+            database
+                .searchHeaderInAddLinkTable("5a1b2c3d4e5f6071")
+                .unwrap()[0],
+            ("X-Token".to_string(), "a=b".to_string())
+        );
+        let retry_dict = database
+            .searchGidInRetryTable("6b2c3d4e5f607182")
+            .unwrap()
+            .unwrap();
+        assert_eq!(retry_dict["attempts"], "2");
+        assert_eq!(
+            database
+                .searchGidInDownloadTable("5a1b2c3d4e5f6071")
+                .unwrap()
+                .unwrap()["size"],
+            "1.5 GiB"
+        );
+        fs::remove_file(&path).unwrap();
+        fs::remove_file(format!("{}.v0.bak", path.display())).unwrap();
+    }
+
+    #[test]
+    fn failed_migration_is_rolled_back() {
+        let mut connection = Connection::open_in_memory().unwrap();
+        let migrations = [
+            Migration {
+                version: 1,
+                description: "create table",
+                apply: |connection| {
+                    connection.execute("CREATE TABLE a(x TEXT)", ())?;
+                    Ok(())
+                },
+            },
+            Migration {
+                version: 2,
+                description: "broken",
+                apply: |connection| {
+                    connection.execute("CREATE TABLE b(x TEXT)", ())?;
+                    connection.execute("INSERT INTO nothing VALUES (1)", ())?;
+                    Ok(())
+                },
+            },
+        ];
+        assert!(migrate(&mut connection, &migrations).is_err());
+        assert_eq!(userVersion(&connection), 1);
+        let tables: i64 = connection
+            .query_row(
+                "SELECT count(*) FROM sqlite_master WHERE name = 'b'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(tables, 0);
+
+        // foreign keys are on again
+        let foreign_keys: bool = connection
+            .query_row("PRAGMA foreign_keys", [], |row| row.get(0))
+            .unwrap();
+        assert!(foreign_keys);
+    }
+
+    fn strings(list: &[&str]) -> Vec<String> {
+        list.iter().map(|x| x.to_string()).collect()
+    }
+
+    fn insertDownloads(database: &DataBase, list: &[(&str, &str)]) {
+        database
+            .insertInDownloadTable(
+                list.iter()
+                    .map(|(gid, category)| HashMap::from([("gid", *gid), ("category", *category)]))
+                    .collect(),
+            )
+            .unwrap();
+    }
+
+    #[test]
+    fn category_items_follow_downloads() {
+        let database = DataBase::open(":memory:").unwrap();
+        database.createTables().unwrap();
+        insertDownloads(
+            &database,
+            &[
+                ("a", "Single Downloads"),
+                ("b", "Scheduled Downloads"),
+                ("c", "Single Downloads"),
+            ],
+        );
+        assert_eq!(
+            database.gidListOfCategory("All Downloads").unwrap(),
+            strings(&["a", "b", "c"])
+        );
+        assert_eq!(
+            database.gidListOfCategory("Single Downloads").unwrap(),
+            strings(&["a", "c"])
+        );
+
+        // move a to the end of 'Scheduled Downloads'
+        database
+            .updateDownloadTable(vec![HashMap::from([
+                ("gid", "a"),
+                ("category", "Scheduled Downloads"),
+            ])])
+            .unwrap();
+        assert_eq!(
+            database.gidListOfCategory("Single Downloads").unwrap(),
+            strings(&["c"])
+        );
+        assert_eq!(
+            database.gidListOfCategory("Scheduled Downloads").unwrap(),
+            strings(&["b", "a"])
+        );
+
+        // reorder
+        database
+            .updateGidListOfCategory("All Downloads", strings(&["c", "a", "b"]))
+            .unwrap();
+        assert_eq!(
+            database.gidListOfCategory("All Downloads").unwrap(),
+            strings(&["c", "a", "b"])
+        );
+        assert!(database
+            .updateGidListOfCategory("All Downloads", strings(&["c", "a"]))
+            .is_err());
+        assert!(database
+            .updateGidListOfCategory("All Downloads", strings(&["c", "a", "x"]))
+            .is_err());
+
+        // deleting download or category deletes its items
+        database
+            .deleteItemInDownloadTable("c", "Single Downloads")
+            .unwrap();
+        assert_eq!(
+            database.gidListOfCategory("All Downloads").unwrap(),
+            strings(&["a", "b"])
+        );
+        database.deleteCategory("Scheduled Downloads").unwrap();
+        assert!(database
+            .gidListOfCategory("All Downloads")
+            .unwrap()
+            .is_empty());
+        assert!(database.gidListOfCategory("Scheduled Downloads").is_err());
+        assert!(database.deleteCategory("All Downloads").is_err());
+    }
+
+    #[test]
+    fn quotes_and_unicode_are_saved_as_they_are() {
+        let database = DataBase::open(":memory:").unwrap();
+        database.createTables().unwrap();
+        let categories = [
+            "Bob's \"best\" queue",
+            "دانلودهای من 🎵",
+            "x'); DROP TABLE download_db_table; --",
+        ];
+        for (i, category) in categories.iter().enumerate() {
+            database
+                .insertInCategoryTable(HashMap::from([
+                    ("category", *category),
+                    ("start_time_enable", "no"),
+                    ("start_time", "0:0"),
+                    ("end_time_enable", "no"),
+                    ("end_time", "0:0"),
+                    ("reverse", "no"),
+                    ("limit_enable", "no"),
+                    ("limit_value", "0K"),
+                    ("after_download", "no"),
+                ]))
                 .unwrap();
-            transaction
+            let gid = format!("gid'{i}");
+            let link = format!("http://example.com/{category}'.zip");
+            database
+                .insertInDownloadTable(vec![HashMap::from([
+                    ("file_name", *category),
+                    ("status", "downloading"),
+                    ("size", "1048576"),
+                    ("downloaded_size", "0"),
+                    ("percent", "0"),
+                    ("connections", "0"),
+                    ("rate", "0"),
+                    ("estimate_time_left", "0"),
+                    ("gid", gid.as_str()),
+                    ("link", link.as_str()),
+                    ("first_try_date", "1577872800"),
+                    ("last_try_date", "1577872800"),
+                    ("category", *category),
+                ])])
+                .unwrap();
+            database
+                .insertInAddLinkTable(vec![HashMap::from([
+                    ("gid", gid.as_str()),
+                    ("out", *category),
+                    ("start_time", "1:00"),
+                    ("link", link.as_str()),
+                    ("header", "X-Name=it's"),
+                ])])
+                .unwrap();
+            database
+                .insertInVideoFinderTable(vec![HashMap::from([
+                    ("video_gid", gid.as_str()),
+                    ("audio_gid", gid.as_str()),
+                    ("video_completed", "no"),
+                    ("audio_completed", "no"),
+                    ("muxing_status", "no"),
+                    ("checking", "yes"),
+                    ("download_path", *category),
+                ])])
+                .unwrap();
+        }
+        assert!(database
+            .categoriesList()
+            .unwrap()
+            .ends_with(&categories.map(String::from)));
+
+        for (i, category) in categories.iter().enumerate() {
+            let gid = format!("gid'{i}");
+            let link = format!("http://example.com/{category}'.zip");
+            let category_dict = database
+                .searchCategoryInCategoryTable(category)
+                .unwrap()
+                .unwrap();
+            assert_eq!(category_dict["category"], *category);
+            assert_eq!(
+                database.gidListOfCategory(category).unwrap(),
+                vec![gid.clone()]
+            );
+            database
+                .updateGidListOfCategory(category, vec![gid.clone()])
+                .unwrap();
+
+            let download_dict = database.searchGidInDownloadTable(&gid).unwrap().unwrap();
+            assert_eq!(download_dict["file_name"], *category);
+            assert_eq!(download_dict["link"], link);
+            let downloads_dict = database.returnItemsInDownloadTable(Some(category)).unwrap();
+            assert_eq!(downloads_dict.keys().collect::<Vec<_>>(), [&gid]);
+            assert_eq!(
+                database.findActiveDownloads(Some(category)).unwrap(),
+                vec![gid.clone()]
+            );
+            assert_eq!(
+                database.downloadingItemsOfCategory(category).unwrap(),
+                vec![gid.clone()]
+            );
+            assert_eq!(
+                database.statusesOfCategory(category).unwrap()[&gid],
+                "downloading"
+            );
+
+            assert!(database.searchLinkInAddLinkTable(&link).unwrap());
+            let addlink_dict = database.searchGidInAddLinkTable(&gid).unwrap().unwrap();
+            assert_eq!(addlink_dict["out"], *category);
+            assert_eq!(
+                database.searchHeaderInAddLinkTable(&gid).unwrap(),
+                [("X-Name".to_string(), "it's".to_string())]
+            );
+            let addlink_items = database.returnItemsInAddLinkTable(Some(category)).unwrap();
+            assert_eq!(addlink_items[&gid]["link"].as_deref(), Some(link.as_str()));
+            assert!(database
+                .searchGidInVideoFinderTable(&gid)
+                .unwrap()
+                .is_some());
+            assert_eq!(database.recordRetry(&gid, "it's broken").unwrap(), 1);
+            assert_eq!(
+                database.searchGidInRetryTable(&gid).unwrap().unwrap()["last_error"],
+                "it's broken"
+            );
+            database.resetRetry(&gid).unwrap();
+
+            database
+                .updateCategoryTable(vec![HashMap::from([
+                    ("category", category.to_string()),
+                    ("start_time_enable", "yes".to_string()),
+                    ("start_time", "2:00".to_string()),
+                ])])
+                .unwrap();
+            database
+                .updateAddLinkTable(vec![HashMap::from([
+                    ("gid", gid.as_str()),
+                    ("out", "it's.zip"),
+                ])])
+                .unwrap();
+            database
+                .updateVideoFinderTable(vec![HashMap::from([
+                    ("video_gid", gid.as_str()),
+                    ("muxing_status", "it's done"),
+                ])])
+                .unwrap();
+            database
+                .updateDownloadTable(vec![HashMap::from([
+                    ("gid", gid.as_str()),
+                    ("status", "paused"),
+                ])])
+                .unwrap();
+        }
+        let gids = strings(&["gid'0", "gid'1", "gid'2"]);
+        let sorted = |mut list: Vec<String>| {
+            list.sort();
+            list
+        };
+        assert_eq!(sorted(database.findActiveDownloads(None).unwrap()), gids);
+        assert_eq!(sorted(database.returnPausedItems().unwrap()), gids);
+        assert!(database.returnDownloadingItems().unwrap().is_empty());
+        assert_eq!(sorted(database.returnVideoFinderGids().unwrap().1), gids);
+        assert_eq!(database.scheduledDownloads().unwrap().len(), 3);
+        assert_eq!(
+            database
+                .categoryWindows()
+                .unwrap()
+                .iter()
+                .map(|window| window.category.as_str())
+                .collect::<Vec<_>>(),
+            categories
+        );
+        assert_eq!(
+            database.returnItemsInAddLinkTable(None).unwrap()["gid'1"]["out"].as_deref(),
+            Some("it's.zip")
+        );
+
+        database
+            .setDefaultGidInAddlinkTable("gid'0", true, true, true)
+            .unwrap();
+        database.setDBTablesToDefaultValue().unwrap();
+        database.correctDataBase().unwrap();
+        assert!(database.scheduledDownloads().unwrap().is_empty());
+
+        // the whole category is deleted, not the tables
+        database
+            .deleteItemInDownloadTable("gid'0", categories[0])
+            .unwrap();
+        database.deleteCategory(categories[2]).unwrap();
+        assert_eq!(
+            database
+                .returnItemsInDownloadTable(None)
+                .unwrap()
+                .keys()
+                .collect::<Vec<_>>(),
+            ["gid'1"]
+        );
+        database.resetDataBase().unwrap();
+        assert!(database
+            .returnItemsInDownloadTable(None)
+            .unwrap()
+            .is_empty());
+        assert_eq!(database.categoriesList().unwrap().len(), 3);
+    }
+
+    #[test]
+    fn old_gid_lists_are_migrated() {
+        let database = DataBase::open(":memory:").unwrap();
+        database.createTables().unwrap();
+        insertDownloads(
+            &database,
+            &[
+                ("a1", "Single Downloads"),
+                ("b2", "Single Downloads"),
+                ("c3", "Scheduled Downloads"),
+            ],
+        );
+
+        // gid_list of old versions. c3 is missed, x9 doesn't exist
+        // and a1 is in wrong category.
+        {
+            let connection = database.connection.lock().unwrap();
+            connection.execute("DELETE FROM category_item", ()).unwrap();
+            connection.pragma_update(None, "user_version", 4).unwrap();
+            for (category, gid_list) in [
+                ("All Downloads", "['b2', 'x9', 'a1']"),
+                ("Single Downloads", "['b2']"),
+                ("Scheduled Downloads", "['a1']"),
+            ] {
+                connection
+                    .execute(
+                        "UPDATE category_db_table SET gid_list = ?1 WHERE category = ?2",
+                        [gid_list, category],
+                    )
+                    .unwrap();
+            }
+        }
+
+        database.createTables().unwrap();
+        database.createTables().unwrap();
+        assert_eq!(
+            database.gidListOfCategory("All Downloads").unwrap(),
+            strings(&["b2", "a1", "c3"])
+        );
+        assert_eq!(
+            database.gidListOfCategory("Single Downloads").unwrap(),
+            strings(&["b2", "a1"])
+        );
+        assert_eq!(
+            database.gidListOfCategory("Scheduled Downloads").unwrap(),
+            strings(&["c3"])
+        );
+    }
+
+    #[test]
+    fn sizes_and_dates_are_saved_as_numbers() {
+        let database = DataBase::open(":memory:").unwrap();
+        database.createTables().unwrap();
+        let date = |date| dateToTimestamp(date).unwrap().to_string();
+        let download = |gid, size, date| {
+            HashMap::from([
+                ("file_name", "a.zip"),
+                ("status", "stopped"),
+                ("size", size),
+                ("downloaded_size", "***"),
+                ("percent", "***"),
+                ("connections", "0"),
+                ("rate", "0"),
+                ("estimate_time_left", "0"),
+                ("gid", gid),
+                ("link", "http://example.com/a.zip"),
+                ("first_try_date", date),
+                ("last_try_date", date),
+                ("category", "Single Downloads"),
+            ])
+        };
+        let (date_a1, date_b2, date_c3) = (
+            date("2020/01/01 , 10:00:00"),
+            date("2021/01/01 , 10:00:00"),
+            date("2019/01/01 , 10:00:00"),
+        );
+        database
+            .insertInDownloadTable(vec![
+                download("a1", "1610612736", date_a1.as_str()),
+                download("b2", "***", date_b2.as_str()),
+                download("c3", "716800", date_c3.as_str()),
+            ])
+            .unwrap();
+        database
+            .updateDownloadTable(vec![HashMap::from([
+                ("gid", "c3"),
+                ("downloaded_size", "2048"),
+                ("percent", "0"),
+                ("rate", "1572864"),
+            ])])
+            .unwrap();
+        // human readable values are not parsed, so old values are kept
+        database
+            .updateDownloadTable(vec![HashMap::from([
+                ("gid", "c3"),
+                ("size", "1 KiB"),
+                ("rate", "1 KiB/s"),
+            ])])
+            .unwrap();
+
+        let download_dict = database.searchGidInDownloadTable("c3").unwrap().unwrap();
+        assert_eq!(download_dict["size"], "700 KiB");
+        assert_eq!(download_dict["downloaded_size"], "2 KiB");
+        assert_eq!(download_dict["percent"], "0%");
+        assert_eq!(download_dict["rate"], "1.5 MiB/s");
+        assert_eq!(download_dict["first_try_date"], "2019/01/01 , 10:00:00");
+        let downloads_dict = database.returnItemsInDownloadTable(None).unwrap();
+        assert_eq!(downloads_dict["b2"]["size"], "***");
+        assert_eq!(downloads_dict["a1"]["rate"], "0");
+        assert_eq!(downloads_dict["a1"]["downloaded_size"], "***");
+        assert_eq!(downloads_dict["a1"]["percent"], "***");
+
+        // downloads can be sorted by numbers
+        let connection = database.connection.lock().unwrap();
+        let sorted = |column: &str| {
+            connection
+                .prepare(&format!(
+                    "SELECT gid FROM download_db_table WHERE {column} IS NOT NULL ORDER BY {column}"
+                ))
+                .unwrap()
+                .query_map([], |row| row.get(0))
+                .unwrap()
+                .collect::<Result<Vec<String>, _>>()
+                .unwrap()
+        };
+        assert_eq!(sorted("size"), strings(&["c3", "a1"]));
+        assert_eq!(sorted("first_try_date"), strings(&["c3", "a1", "b2"]));
+    }
+
+    #[test]
+    fn headers_are_saved_as_json() {
+        let database = DataBase::open(":memory:").unwrap();
+        database.createTables().unwrap();
+        database
+            .insertInDownloadTable(vec![HashMap::from([
+                ("gid", "a"),
+                ("category", "Single Downloads"),
+            ])])
+            .unwrap();
+        database
+            .insertInAddLinkTable(vec![HashMap::from([
+                ("gid", "a"),
+                ("header", "X-Token=a=b; Accept=*/*"),
+            ])])
+            .unwrap();
+
+        let pairs = vec![
+            ("X-Token".to_string(), "a=b".to_string()),
+            ("Accept".to_string(), "*/*".to_string()),
+        ];
+        assert_eq!(database.searchHeaderInAddLinkTable("a").unwrap(), pairs);
+
+        database
+            .updateAddLinkTable(vec![HashMap::from([
+                ("gid", "a"),
+                ("header", r#"[["Cookie","x=1; y=2"]]"#),
+            ])])
+            .unwrap();
+        assert_eq!(
+            database.searchHeaderInAddLinkTable("a").unwrap(),
+            vec![("Cookie".to_string(), "x=1; y=2".to_string())]
+        );
+        assert!(database.searchHeaderInAddLinkTable("b").unwrap().is_empty());
+    }
+
+    #[test]
+    fn old_headers_are_migrated() {
+        let database = DataBase::open(":memory:").unwrap();
+        database.createTables().unwrap();
+        database
+            .insertInDownloadTable(vec![HashMap::from([
+                ("gid", "a"),
+                ("category", "Single Downloads"),
+            ])])
+            .unwrap();
+        {
+            let connection = database.connection.lock().unwrap();
+            connection
                 .execute(
-                    "
-                UPDATE download_db_table 
-                SET downloaded_size = replace(downloaded_size, ?1, ?2)
-                ",
-                    [dict.get("old_unit").unwrap(), dict.get("new_unit").unwrap()],
+                    "INSERT INTO addlink_db_table (gid, header) VALUES ('a', 'X-Token=a=b; Accept=*/*')",
+                    (),
                 )
                 .unwrap();
+            connection.pragma_update(None, "user_version", 3).unwrap();
         }
-        transaction.commit().unwrap();
+
+        // createTables runs every time that ghermez starts
+        database.createTables().unwrap();
+        database.createTables().unwrap();
+        let add_link_dict = database.searchGidInAddLinkTable("a").unwrap().unwrap();
+        assert_eq!(
+            add_link_dict["header"],
+            r#"[["X-Token","a=b"],["Accept","*/*"]]"#
+        );
     }
 }
@@ -0,0 +1,195 @@
+#![allow(non_snake_case)]
+// pyo3 0.19 create_exception! checks a cfg that newer compilers don't know
+#![allow(unexpected_cfgs)]
+
+use std::{fmt, io};
+
+use pyo3::{create_exception, exceptions::PyException, prelude::*};
+
+use aria2_ws::Error as Aria2WsError;
+
+// python side of GhermezError.
+// all of them are subclasses of ghermez.GhermezError, so python side can catch
+// every error of ghermez with one except clause.
+pub mod exceptions {
+    use super::*;
+
+    create_exception!(
+        ghermez,
+        GhermezError,
+        PyException,
+        "Base class of ghermez errors."
+    );
+    create_exception!(
+        ghermez,
+        Aria2Unavailable,
+        GhermezError,
+        "aria2 is not running or doesn't answer."
+    );
+    create_exception!(
+        ghermez,
+        Aria2RpcError,
+        GhermezError,
+        "aria2 answered with an error. `code` is aria2 error code."
+    );
+    create_exception!(
+        ghermez,
+        Aria2AnswerError,
+        GhermezError,
+        "answer of aria2 couldn't be read."
+    );
+    create_exception!(ghermez, DatabaseError, GhermezError, "Data base error.");
+    create_exception!(ghermez, IoError, GhermezError, "File system error.");
+    create_exception!(
+        ghermez,
+        HttpError,
+        GhermezError,
+        "Server couldn't be reached or request failed."
+    );
+}
+
+#[derive(Debug)]
+pub enum GhermezError {
+    // websocket is closed, aria2 is not running or it didn't answer in time
+    Aria2Unavailable(String),
+    // aria2 answered with an error.
+    // code is aria2 exit status, see "EXIT STATUS" in aria2 documentation.
+    Aria2Rpc { code: i32, message: String },
+    // aria2 answered, but answer isn't what ghermez expects
+    Aria2Answer(String),
+    Database(String),
+    Io(io::Error),
+    // spider couldn't connect to server or request failed
+    Http(String),
+}
+
+impl fmt::Display for GhermezError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Aria2Unavailable(message) => write!(f, "aria2 is unavailable: {message}"),
+            Self::Aria2Rpc { code, message } => write!(f, "aria2 error {code}: {message}"),
+            Self::Aria2Answer(message) => write!(f, "invalid answer of aria2: {message}"),
+            Self::Database(message) => write!(f, "data base error: {message}"),
+            Self::Io(e) => write!(f, "{e}"),
+            Self::Http(message) => write!(f, "http error: {message}"),
+        }
+    }
+}
+
+impl std::error::Error for GhermezError {}
+
+impl From<Aria2WsError> for GhermezError {
+    fn from(e: Aria2WsError) -> Self {
+        match e {
+            Aria2WsError::Aria2 { source } => Self::Aria2Rpc {
+                code: source.code,
+                message: source.message,
+            },
+            Aria2WsError::Parse { .. } | Aria2WsError::Json { .. } => {
+                Self::Aria2Answer(e.to_string())
+            }
+            _ => Self::Aria2Unavailable(e.to_string()),
+        }
+    }
+}
+
+impl From<serde_json::Error> for GhermezError {
+    fn from(e: serde_json::Error) -> Self {
+        Self::Aria2Answer(e.to_string())
+    }
+}
+
+impl From<rusqlite::Error> for GhermezError {
+    fn from(e: rusqlite::Error) -> Self {
+        Self::Database(e.to_string())
+    }
+}
+
+impl From<io::Error> for GhermezError {
+    fn from(e: io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+impl From<reqwest::Error> for GhermezError {
+    fn from(e: reqwest::Error) -> Self {
+        Self::Http(e.to_string())
+    }
+}
+
+impl From<GhermezError> for PyErr {
+    fn from(e: GhermezError) -> Self {
+        let message = e.to_string();
+        match e {
+            GhermezError::Aria2Unavailable(_) => exceptions::Aria2Unavailable::new_err(message),
+            GhermezError::Aria2Rpc { code, .. } => Python::with_gil(|py| {
+                let err = exceptions::Aria2RpcError::new_err(message);
+                // make code available as an attribute of the exception
+                if let Err(e) = err.value(py).setattr("code", code) {
+                    return e;
+                }
+                err
+            }),
+            GhermezError::Aria2Answer(_) => exceptions::Aria2AnswerError::new_err(message),
+            GhermezError::Database(_) => exceptions::DatabaseError::new_err(message),
+            GhermezError::Io(_) => exceptions::IoError::new_err(message),
+            GhermezError::Http(_) => exceptions::HttpError::new_err(message),
+        }
+    }
+}
+
+// add exception classes to ghermez module
+pub fn addExceptions(py: Python<'_>, m: &PyModule) -> PyResult<()> {
+    m.add("GhermezError", py.get_type::<exceptions::GhermezError>())?;
+    m.add(
+        "Aria2Unavailable",
+        py.get_type::<exceptions::Aria2Unavailable>(),
+    )?;
+    m.add("Aria2RpcError", py.get_type::<exceptions::Aria2RpcError>())?;
+    m.add(
+        "Aria2AnswerError",
+        py.get_type::<exceptions::Aria2AnswerError>(),
+    )?;
+    m.add("DatabaseError", py.get_type::<exceptions::DatabaseError>())?;
+    m.add("IoError", py.get_type::<exceptions::IoError>())?;
+    m.add("HttpError", py.get_type::<exceptions::HttpError>())?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn errors_are_python_exceptions() {
+        pyo3::prepare_freethreaded_python();
+        Python::with_gil(|py| {
+            let err: PyErr = GhermezError::Aria2Rpc {
+                code: 3,
+                message: "Resource was not found".to_string(),
+            }
+            .into();
+            assert!(err.is_instance_of::<exceptions::Aria2RpcError>(py));
+            assert!(err.is_instance_of::<exceptions::GhermezError>(py));
+            let code: i32 = err.value(py).getattr("code").unwrap().extract().unwrap();
+            assert_eq!(code, 3);
+
+            let err: PyErr = GhermezError::from(Aria2WsError::WebsocketClosed {
+                message: "aria2 didn't answer in time".to_string(),
+            })
+            .into();
+            assert!(err.is_instance_of::<exceptions::Aria2Unavailable>(py));
+
+            let json_error = serde_json::from_str::<i32>("answer").unwrap_err();
+            let err: PyErr = GhermezError::from(json_error).into();
+            assert!(err.is_instance_of::<exceptions::Aria2AnswerError>(py));
+            assert!(!err.is_instance_of::<exceptions::Aria2RpcError>(py));
+
+            let err: PyErr = GhermezError::from(rusqlite::Error::QueryReturnedNoRows).into();
+            assert!(err.is_instance_of::<exceptions::DatabaseError>(py));
+
+            let err: PyErr = GhermezError::from(io::Error::from(io::ErrorKind::NotFound)).into();
+            assert!(err.is_instance_of::<exceptions::IoError>(py));
+        });
+    }
+}
@@ -0,0 +1,294 @@
+#![allow(non_snake_case)]
+
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::Mutex,
+};
+
+use log::{error, info};
+use once_cell::sync::Lazy;
+use pyo3::{prelude::*, types::IntoPyDict};
+use serde_json::{Map, Value};
+use tokio::{
+    sync::broadcast::{error::RecvError, Receiver},
+    time::timeout,
+};
+
+use aria2_ws::{Client, Error as Aria2WsError, Event, Notification};
+
+use crate::{
+    aria2c::{currentClient, RPC_TIMEOUT},
+    retry::watchEvent,
+};
+
+// events that are not taken by pollEvents yet.
+// oldest events are dropped if python side doesn't poll for a long time.
+static EVENTS: Lazy<Mutex<VecDeque<DownloadEvent>>> = Lazy::new(|| Mutex::new(VecDeque::new()));
+const MAX_EVENTS: usize = 10000;
+
+// last event of every gid.
+// it's used to find out what is missed while websocket was disconnected.
+// gids are forgotten when their result is removed from aria2, and finished gids are
+// dropped if it has MAX_EVENTS gids.
+static LAST_EVENTS: Lazy<Mutex<HashMap<String, &'static str>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+// number of downloads that are asked from aria2 for making synthetic events.
+const RESYNC_LIMIT: i32 = 1000;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DownloadEvent {
+    // gid is None for connected, disconnected and category events
+    pub gid: Option<String>,
+    // category of window_open and window_close events
+    pub category: Option<String>,
+    // start, pause, stop, complete, error, bt_complete, connected or disconnected.
+    // scheduler sends scheduled_start, scheduled_end, window_open and window_close.
+    // retry manager sends retry when a failed download is sent to aria2 again.
+    pub event: &'static str,
+    // synthetic events are not sent by aria2.
+    // they are made after reconnecting for events that are missed.
+    pub synthetic: bool,
+}
+
+impl IntoPy<PyObject> for DownloadEvent {
+    fn into_py(self, py: Python<'_>) -> PyObject {
+        [
+            ("gid", self.gid.into_py(py)),
+            ("category", self.category.into_py(py)),
+            ("event", self.event.into_py(py)),
+            ("synthetic", self.synthetic.into_py(py)),
+        ]
+        .into_py_dict(py)
+        .into()
+    }
+}
+
+fn eventName(event: &Event) -> &'static str {
+    match event {
+        Event::Start => "start",
+        Event::Pause => "pause",
+        Event::Stop => "stop",
+        Event::Complete => "complete",
+        Event::Error => "error",
+        Event::BtComplete => "bt_complete",
+    }
+}
+
+// which event leads to this status of aria2.
+// waiting downloads have no event.
+fn statusEvent(status: &str) -> Option<&'static str> {
+    match status {
+        "active" => Some("start"),
+        "paused" => Some("pause"),
+        "removed" => Some("stop"),
+        "complete" => Some("complete"),
+        "error" => Some("error"),
+        _ => None,
+    }
+}
+
+// events that mean aria2 is finished with download
+fn isFinished(event: &str) -> bool {
+    ["complete", "bt_complete", "error", "stop"].contains(&event)
+}
+
+fn rememberEvent(last_events: &mut HashMap<String, &'static str>, gid: &str, event: &'static str) {
+    if last_events.len() >= MAX_EVENTS && !last_events.contains_key(gid) {
+        last_events.retain(|_, last_event| !isFinished(last_event));
+    }
+    last_events.insert(gid.to_string(), event);
+}
+
+// gid is removed from aria2, so its events are not needed anymore.
+pub(crate) fn forgetGid(gid: &str) {
+    LAST_EVENTS.lock().unwrap().remove(gid);
+}
+
+pub(crate) fn pushEvent(event: DownloadEvent) {
+    watchEvent(&event);
+    if let Some(gid) = &event.gid {
+        rememberEvent(&mut LAST_EVENTS.lock().unwrap(), gid, event.event);
+    }
+
+    let mut events = EVENTS.lock().unwrap();
+    if events.len() >= MAX_EVENTS {
+        events.pop_front();
+    }
+    events.push_back(event);
+}
+
+// compare status of downloads with last events and make events for differences.
+// bt_complete is same as complete for this comparison.
+fn missedEvents(
+    last_events: &HashMap<String, &'static str>,
+    statuses: Vec<(String, &'static str)>,
+) -> Vec<DownloadEvent> {
+    statuses
+        .into_iter()
+        .filter(|(gid, event)| {
+            let last_event = last_events.get(gid).map(|last_event| match *last_event {
+                "bt_complete" => "complete",
+                last_event => last_event,
+            });
+            last_event != Some(*event)
+        })
+        .map(|(gid, event)| DownloadEvent {
+            gid: Some(gid),
+            category: None,
+            event,
+            synthetic: true,
+        })
+        .collect()
+}
+
+// ask aria2 for status of all downloads.
+async fn downloadStatuses(client: &Client) -> Result<Vec<(String, &'static str)>, Aria2WsError> {
+    let keys = || Some(vec!["gid".to_string(), "status".to_string()]);
+
+    let mut downloads: Vec<Map<String, Value>> = client.custom_tell_active(keys()).await?;
+    downloads.extend(client.custom_tell_waiting(0, RESYNC_LIMIT, keys()).await?);
+    downloads.extend(client.custom_tell_stopped(0, RESYNC_LIMIT, keys()).await?);
+
+    Ok(downloads
+        .iter()
+        .filter_map(|download| {
+            let gid = download.get("gid")?.as_str()?;
+            let event = statusEvent(download.get("status")?.as_str()?)?;
+            Some((gid.to_string(), event))
+        })
+        .collect())
+}
+
+// make synthetic events for the events that are missed.
+async fn resync() {
+    let Some(client) = currentClient().await else {
+        return;
+    };
+    match timeout(RPC_TIMEOUT, downloadStatuses(&client)).await {
+        Ok(Ok(statuses)) => {
+            let events = {
+                let mut last_events = LAST_EVENTS.lock().unwrap();
+                // aria2 doesn't know the other gids anymore
+                last_events.retain(|gid, _| statuses.iter().any(|(known, _)| known == gid));
+                missedEvents(&last_events, statuses)
+            };
+            if !events.is_empty() {
+                info!("{} missed download events are recovered", events.len());
+            }
+            events.into_iter().for_each(pushEvent);
+        }
+        Ok(Err(e)) => error!("Couldn't recover missed download events: {e}"),
+        Err(_) => error!("Couldn't recover missed download events: aria2 didn't answer in time"),
+    }
+}
+
+// move notifications of a client to the event queue.
+// it ends when the client is dropped.
+pub(crate) async fn pumpEvents(mut notifications: Receiver<Notification>) {
+    // first connected notification is sent before subscribing
+    resync().await;
+
+    loop {
+        match notifications.recv().await {
+            Ok(Notification::Aria2 { gid, event }) => pushEvent(DownloadEvent {
+                gid: Some(gid),
+                category: None,
+                event: eventName(&event),
+                synthetic: false,
+            }),
+            Ok(Notification::WebSocketConnected) => {
+                pushEvent(DownloadEvent {
+                    gid: None,
+                    category: None,
+                    event: "connected",
+                    synthetic: false,
+                });
+                resync().await;
+            }
+            Ok(Notification::WebsocketClosed) => pushEvent(DownloadEvent {
+                gid: None,
+                category: None,
+                event: "disconnected",
+                synthetic: false,
+            }),
+            // some notifications are lost, so ask aria2 what happened
+            Err(RecvError::Lagged(_)) => resync().await,
+            Err(RecvError::Closed) => break,
+        }
+    }
+}
+
+// this function returns download events that are received since last call.
+// every event is a dictionary like
+// {'gid': '...', 'category': None, 'event': 'complete', 'synthetic': False}
+#[pyfunction]
+pub fn pollEvents() -> Vec<DownloadEvent> {
+    EVENTS.lock().unwrap().drain(..).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn missed_events_are_synthetic() {
+        let last_events = HashMap::from([
+            ("a".to_string(), "start"),
+            ("b".to_string(), "start"),
+            ("c".to_string(), "bt_complete"),
+        ]);
+        let statuses = vec![
+            ("a".to_string(), "start"),
+            ("b".to_string(), "complete"),
+            ("c".to_string(), "complete"),
+            ("d".to_string(), "error"),
+        ];
+
+        let events = missedEvents(&last_events, statuses);
+        assert_eq!(
+            events,
+            vec![
+                DownloadEvent {
+                    gid: Some("b".to_string()),
+                    category: None,
+                    event: "complete",
+                    synthetic: true,
+                },
+                DownloadEvent {
+                    gid: Some("d".to_string()),
+                    category: None,
+                    event: "error",
+                    synthetic: true,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn finished_gids_are_dropped_when_last_events_is_full() {
+        let mut last_events = HashMap::new();
+        for number in 0..MAX_EVENTS {
+            let event = if number % 2 == 0 { "complete" } else { "start" };
+            rememberEvent(&mut last_events, &number.to_string(), event);
+        }
+        assert_eq!(last_events.len(), MAX_EVENTS);
+
+        // known gids don't make room
+        rememberEvent(&mut last_events, "0", "start");
+        assert_eq!(last_events.len(), MAX_EVENTS);
+
+        rememberEvent(&mut last_events, "new", "start");
+        assert_eq!(last_events.len(), MAX_EVENTS / 2 + 2);
+        assert_eq!(last_events.get("0"), Some(&"start"));
+        assert!(!last_events.contains_key("2"));
+    }
+
+    #[test]
+    fn status_to_event() {
+        assert_eq!(statusEvent("active"), Some("start"));
+        assert_eq!(statusEvent("removed"), Some("stop"));
+        assert_eq!(statusEvent("waiting"), None);
+        assert_eq!(eventName(&Event::BtComplete), "bt_complete");
+    }
+}
@@ -4,22 +4,23 @@ use std::io::prelude::*;
 use chrono::Local;
 use pyo3::prelude::*;
 
-use crate::useful_tools::determineConfigFolder;
+use crate::{error::GhermezError, useful_tools::determineConfigFolder};
 
 #[pyfunction]
-pub fn init_create_folders() {
+pub fn init_create_folders() -> Result<(), GhermezError> {
     // download manager config folder
     let config_folder = determineConfigFolder();
     // ghermez tmp folder path
     let ghermez_tmp = config_folder.join("ghermez_tmp");
     // create folders
     for folder in [config_folder, ghermez_tmp] {
-        fs::create_dir_all(folder).unwrap();
+        fs::create_dir_all(folder)?;
     }
+    Ok(())
 }
 
 #[pyfunction]
-pub fn init_log_file() {
+pub fn init_log_file() -> Result<(), GhermezError> {
     // refresh logs!
     let config_folder = determineConfigFolder();
     let log_file = config_folder.join("ghermezdm.log");
@@ -28,10 +29,7 @@ pub fn init_log_file() {
     let current_time = Local::now().format("%Y/%m/%d , %H:%M:%S").to_string();
 
     // find number of lines in log_file
-    let len = fs::read_to_string(log_file.clone())
-        .unwrap()
-        .lines()
-        .count();
+    let len = fs::read_to_string(log_file.clone())?.lines().count();
 
     // if number of lines in log_file is more than 300, then keep last 200 lines in log_file.
     if len < 300 {
@@ -39,20 +37,19 @@ pub fn init_log_file() {
             "\n\n===================================================\nGhermez Download Manager, {}\n\n",
             current_time
         );
-        let mut file = OpenOptions::new().append(true).open(log_file).unwrap();
-        file.write_all(content.as_bytes()).unwrap();
+        let mut file = OpenOptions::new().append(true).open(log_file)?;
+        file.write_all(content.as_bytes())?;
     } else {
         // keep last 200 lines
         let line_num = len - 200;
-        let f_lines: Vec<String> = fs::read_to_string(log_file.clone())
-            .unwrap()
+        let f_lines: Vec<String> = fs::read_to_string(log_file.clone())?
             .lines()
             .map(String::from)
             .collect();
-        let mut file = OpenOptions::new().write(true).open(log_file).unwrap();
-        file.write_all(f_lines[line_num..].join("\n").as_bytes())
-            .unwrap();
+        let mut file = OpenOptions::new().write(true).open(log_file)?;
+        file.write_all(f_lines[line_num..].join("\n").as_bytes())?;
         let content = format!("Ghermez Download Manager, {}", current_time);
-        file.write_all(content.as_bytes()).unwrap();
+        file.write_all(content.as_bytes())?;
     }
+    Ok(())
 }
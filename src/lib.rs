@@ -1,21 +1,44 @@
 use pyo3::prelude::*;
 
 mod aria2c;
+mod cookies;
 mod database;
+mod error;
+mod events;
 mod initialization;
+mod links;
 mod logger;
+mod options;
 mod os_command;
+mod pool;
+mod queue;
+mod retry;
+mod scheduler;
+mod search;
+mod spider;
 mod startup;
 mod useful_tools;
 
 use aria2c::{
-    activeDownloads, aria2Version, downloadPause, downloadUnpause, findDownloadPath, limitSpeed,
-    nowDate, shutDown, startAria, tellActive,
+    activeDownloads, addDownload, addMetalink, addTorrent, aria2Version, connect, disconnect,
+    downloadPause, downloadStop, downloadUnpause, findDownloadPath, globalStat, limitSpeed,
+    nowDate, pauseAll, resumeAll, rpcSecret, setGlobalSpeedLimit, shutDown, startAria,
+    startDownload, tellActive, tellStatus, tellStopped, tellWaiting,
 };
+use cookies::writeCookiesFile;
 use database::{DataBase, PluginsDB, TempDB};
+use error::addExceptions;
+use events::pollEvents;
 use initialization::{init_create_folders, init_log_file};
+use links::extractLinks;
 use logger::{initLogger, sendToLog};
+use options::headerText;
 use os_command::{makeDirs, moveFile, remove, removeDir, touch, xdgOpen};
+use pool::{configurePool, submitDownload};
+use queue::QueueRunner;
+use retry::configureRetry;
+use scheduler::{scheduleDownload, startScheduler};
+use spider::{spiderLink, spiderMany, SpiderStream};
 use startup::{addstartup, checkstartup, removestartup};
 use useful_tools::{
     convertToByte, determineConfigFolder, humanReadableSize, osAndDesktopEnvironment,
@@ -27,21 +50,51 @@ mod response;
 use useful_tools::freeSpace;
 
 #[pymodule]
-fn ghermez(_py: Python, m: &PyModule) -> PyResult<()> {
+fn ghermez(py: Python, m: &PyModule) -> PyResult<()> {
+    addExceptions(py, m)?;
+
     m.add_function(wrap_pyfunction!(startAria, m)?)?;
+    m.add_function(wrap_pyfunction!(rpcSecret, m)?)?;
     m.add_function(wrap_pyfunction!(aria2Version, m)?)?;
+    m.add_function(wrap_pyfunction!(connect, m)?)?;
+    m.add_function(wrap_pyfunction!(disconnect, m)?)?;
+    m.add_function(wrap_pyfunction!(addDownload, m)?)?;
+    m.add_function(wrap_pyfunction!(startDownload, m)?)?;
+    m.add_function(wrap_pyfunction!(addTorrent, m)?)?;
+    m.add_function(wrap_pyfunction!(addMetalink, m)?)?;
     m.add_function(wrap_pyfunction!(tellActive, m)?)?;
+    m.add_function(wrap_pyfunction!(tellStatus, m)?)?;
+    m.add_function(wrap_pyfunction!(tellWaiting, m)?)?;
+    m.add_function(wrap_pyfunction!(tellStopped, m)?)?;
     m.add_function(wrap_pyfunction!(findDownloadPath, m)?)?;
     m.add_function(wrap_pyfunction!(shutDown, m)?)?;
+    m.add_function(wrap_pyfunction!(downloadStop, m)?)?;
     m.add_function(wrap_pyfunction!(downloadPause, m)?)?;
     m.add_function(wrap_pyfunction!(downloadUnpause, m)?)?;
     m.add_function(wrap_pyfunction!(limitSpeed, m)?)?;
+    m.add_function(wrap_pyfunction!(pauseAll, m)?)?;
+    m.add_function(wrap_pyfunction!(resumeAll, m)?)?;
+    m.add_function(wrap_pyfunction!(setGlobalSpeedLimit, m)?)?;
     m.add_function(wrap_pyfunction!(activeDownloads, m)?)?;
+    m.add_function(wrap_pyfunction!(globalStat, m)?)?;
+    m.add_function(wrap_pyfunction!(pollEvents, m)?)?;
+    m.add_function(wrap_pyfunction!(startScheduler, m)?)?;
+    m.add_function(wrap_pyfunction!(scheduleDownload, m)?)?;
     m.add_function(wrap_pyfunction!(nowDate, m)?)?;
 
     m.add_class::<DataBase>()?;
     m.add_class::<TempDB>()?;
     m.add_class::<PluginsDB>()?;
+    m.add_function(wrap_pyfunction!(configurePool, m)?)?;
+    m.add_function(wrap_pyfunction!(submitDownload, m)?)?;
+    m.add_function(wrap_pyfunction!(configureRetry, m)?)?;
+    m.add_function(wrap_pyfunction!(extractLinks, m)?)?;
+    m.add_function(wrap_pyfunction!(writeCookiesFile, m)?)?;
+    m.add_function(wrap_pyfunction!(headerText, m)?)?;
+    m.add_function(wrap_pyfunction!(spiderLink, m)?)?;
+    m.add_function(wrap_pyfunction!(spiderMany, m)?)?;
+    m.add_class::<SpiderStream>()?;
+    m.add_class::<QueueRunner>()?;
 
     m.add_function(wrap_pyfunction!(determineConfigFolder, m)?)?;
     m.add_function(wrap_pyfunction!(humanReadableSize, m)?)?;
@@ -0,0 +1,356 @@
+#![allow(non_snake_case)]
+
+use std::collections::{HashMap, HashSet};
+
+use once_cell::sync::Lazy;
+use percent_encoding::percent_decode_str;
+use pyo3::{prelude::*, types::IntoPyDict};
+use regex::Regex;
+
+// a bracket range pattern can't make more links than this.
+const MAX_EXPANDED_LINKS: usize = 10000;
+
+// http, https, ftp, sftp and magnet links in text.
+// links end at white space, quotes and angle brackets, so links in HTML attributes are found too.
+static LINK_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r#"(?i)(?:(?:https?|s?ftp)://|magnet:\?)[^\s<>"'`]+"#).unwrap());
+
+// <a> tags of HTML and Netscape bookmarks files
+static ANCHOR_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?is)<a\s[^>]*>").unwrap());
+
+static ATTRIBUTE_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r#"(?is)\b(href|download)\s*=\s*(?:"([^"]*)"|'([^']*)'|([^\s>]+))"#).unwrap()
+});
+
+// numeric range like [001-120] or letter range like [a-z]
+static RANGE_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"\[(?:(\d+)-(\d+)|([a-zA-Z])-([a-zA-Z]))\]").unwrap());
+
+// query parameters that only track where user came from
+const TRACKING_PARAMS: [&str; 8] = [
+    "fbclid", "gclid", "dclid", "msclkid", "mc_cid", "mc_eid", "igshid", "yclid",
+];
+
+// a link that is found by extractLinks
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LinkEntry {
+    pub link: String,
+    // suggested file name, from download attribute of HTML or dn of magnet links
+    pub out: Option<String>,
+}
+
+impl IntoPy<PyObject> for LinkEntry {
+    fn into_py(self, py: Python<'_>) -> PyObject {
+        [
+            ("link", self.link.into_py(py)),
+            ("out", self.out.into_py(py)),
+        ]
+        .into_py_dict(py)
+        .into()
+    }
+}
+
+// links in HTML are escaped like "a?b=1&amp;c=2"
+fn unescapeHtml(text: &str) -> String {
+    text.replace("&amp;", "&")
+        .replace("&#38;", "&")
+        .replace("&#x26;", "&")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+}
+
+// remove punctuation that ends a sentence, and closing brackets that are not opened in link.
+fn trimLink(link: &str) -> &str {
+    let mut link = link;
+    loop {
+        let trimmed = link.trim_end_matches(['.', ',', ';', ':', '!', '?']);
+        let trimmed = match trimmed.chars().last() {
+            Some(close @ (')' | ']' | '}')) => {
+                let open = match close {
+                    ')' => '(',
+                    ']' => '[',
+                    _ => '{',
+                };
+                let opened = trimmed.matches(open).count();
+                let closed = trimmed.matches(close).count();
+                if closed > opened {
+                    &trimmed[..trimmed.len() - 1]
+                } else {
+                    trimmed
+                }
+            }
+            _ => trimmed,
+        };
+        if trimmed == link {
+            return link;
+        }
+        link = trimmed;
+    }
+}
+
+// remove tracking parameters like utm_source from parameter list of a query or fragment.
+fn keepParams(params: &str) -> String {
+    params
+        .split('&')
+        .filter(|param| {
+            let name = param.split('=').next().unwrap_or_default().to_lowercase();
+            !param.is_empty()
+                && !name.starts_with("utm_")
+                && !TRACKING_PARAMS.contains(&name.as_str())
+        })
+        .collect::<Vec<_>>()
+        .join("&")
+}
+
+// remove tracking parameters like utm_source from query and fragment of link.
+// other fragments like #t=30 are kept. magnet links are not changed.
+fn stripTracking(link: &str) -> String {
+    if link.to_lowercase().starts_with("magnet:") {
+        return link.to_string();
+    }
+    let (link, fragment) = match link.split_once('#') {
+        Some((link, fragment)) => (link, Some(fragment)),
+        None => (link, None),
+    };
+    let mut stripped = match link.split_once('?') {
+        Some((address, query)) => {
+            let query = keepParams(query);
+            if query.is_empty() {
+                address.to_string()
+            } else {
+                format!("{address}?{query}")
+            }
+        }
+        None => link.to_string(),
+    };
+    if let Some(fragment) = fragment.map(keepParams) {
+        if !fragment.is_empty() {
+            stripped.push('#');
+            stripped.push_str(&fragment);
+        }
+    }
+    stripped
+}
+
+// values of a bracket range. numbers keep zero padding of range start.
+fn rangeValues(captures: &regex::Captures) -> Option<Vec<String>> {
+    if let (Some(start), Some(end)) = (captures.get(1), captures.get(2)) {
+        let width = start.as_str().len();
+        let (start, end): (u64, u64) = (start.as_str().parse().ok()?, end.as_str().parse().ok()?);
+        if start > end || end - start >= MAX_EXPANDED_LINKS as u64 {
+            return None;
+        }
+        return Some((start..=end).map(|i| format!("{i:0width$}")).collect());
+    }
+    let start = captures.get(3)?.as_str().chars().next()?;
+    let end = captures.get(4)?.as_str().chars().next()?;
+    if start > end || start.is_lowercase() != end.is_lowercase() {
+        return None;
+    }
+    Some((start..=end).map(String::from).collect())
+}
+
+// expand bracket ranges, for example file[1-3].jpg is file1.jpg, file2.jpg and file3.jpg.
+// links with more than one range are expanded for every combination.
+// link is not changed if it has no valid range or it makes too many links.
+fn expandRanges(link: &str) -> Vec<String> {
+    let mut links = vec![String::new()];
+    let mut last = 0;
+    for captures in RANGE_RE.captures_iter(link) {
+        let whole = captures.get(0).unwrap();
+        let prefix = &link[last..whole.start()];
+        last = whole.end();
+
+        let Some(values) = rangeValues(&captures) else {
+            links.iter_mut().for_each(|x| {
+                x.push_str(prefix);
+                x.push_str(whole.as_str())
+            });
+            continue;
+        };
+        if links.len() * values.len() > MAX_EXPANDED_LINKS {
+            return vec![link.to_string()];
+        }
+        links = links
+            .iter()
+            .flat_map(|x| {
+                values
+                    .iter()
+                    .map(move |value| format!("{x}{prefix}{value}"))
+            })
+            .collect();
+    }
+    links.iter_mut().for_each(|x| x.push_str(&link[last..]));
+    links
+}
+
+// display name of magnet link
+fn magnetName(link: &str) -> Option<String> {
+    let (_, query) = link.split_once('?')?;
+    query
+        .split('&')
+        .find_map(|param| param.strip_prefix("dn="))
+        .map(|name| {
+            percent_decode_str(&name.replace('+', " "))
+                .decode_utf8_lossy()
+                .to_string()
+        })
+        .filter(|name| !name.is_empty())
+}
+
+// file names of HTML links that have download attribute
+fn downloadNames(text: &str) -> HashMap<String, String> {
+    let mut names = HashMap::new();
+    for anchor in ANCHOR_RE.find_iter(text) {
+        let mut href = None;
+        let mut download = None;
+        for captures in ATTRIBUTE_RE.captures_iter(anchor.as_str()) {
+            let value = (2..=4)
+                .find_map(|i| captures.get(i))
+                .map(|value| unescapeHtml(value.as_str().trim()));
+            match captures[1].to_lowercase().as_str() {
+                "href" => href = value,
+                _ => download = value,
+            }
+        }
+        if let (Some(href), Some(download)) = (href, download) {
+            if !download.is_empty() {
+                names.insert(stripTracking(trimLink(&href)), download);
+            }
+        }
+    }
+    names
+}
+
+// find links in text. text can be a list of links, any text, HTML or Netscape bookmarks file.
+// bracket ranges are expanded, tracking parameters are removed
+// and every link is returned once, in the order that it's found.
+pub(crate) fn findLinks(text: &str) -> Vec<LinkEntry> {
+    let names = downloadNames(text);
+    let mut seen = HashSet::new();
+    let mut entries = vec![];
+    for found in LINK_RE.find_iter(text) {
+        let link = unescapeHtml(trimLink(found.as_str()));
+        let link = stripTracking(&link);
+        let out = names.get(&link).cloned().or_else(|| magnetName(&link));
+        for link in expandRanges(&link) {
+            if seen.insert(link.clone()) {
+                entries.push(LinkEntry {
+                    link,
+                    out: out.clone(),
+                });
+            }
+        }
+    }
+    entries
+}
+
+// extractLinks returns a list of dictionaries with link and out keys.
+// out is suggested file name or None.
+// see findLinks for more information.
+#[pyfunction]
+pub fn extractLinks(text: &str) -> Vec<LinkEntry> {
+    findLinks(text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn links(text: &str) -> Vec<String> {
+        findLinks(text)
+            .into_iter()
+            .map(|entry| entry.link)
+            .collect()
+    }
+
+    #[test]
+    fn links_in_text() {
+        let text = "get http://a.com/file.zip, then (https://b.com/x_(1).iso).\n\
+                    ftp://c.com/y.tar and sftp://d.com/z! http://a.com/file.zip";
+        assert_eq!(
+            links(text),
+            vec![
+                "http://a.com/file.zip",
+                "https://b.com/x_(1).iso",
+                "ftp://c.com/y.tar",
+                "sftp://d.com/z",
+            ]
+        );
+        assert!(links("mailto:a@b.com and www.example.com").is_empty());
+    }
+
+    #[test]
+    fn links_in_html_and_bookmarks() {
+        let text = r#"<!DOCTYPE NETSCAPE-Bookmark-file-1>
+            <DT><A HREF="https://a.com/get?id=1&amp;utm_source=x#top" ADD_DATE="1">A</A>
+            <a href='http://b.com/f.bin' download="nice name.bin">b</a>
+            <a href="magnet:?xt=urn:btih:abc&amp;dn=Some+Movie%20.mkv">magnet</a>"#;
+        assert_eq!(
+            findLinks(text),
+            vec![
+                LinkEntry {
+                    link: "https://a.com/get?id=1#top".to_string(),
+                    out: None,
+                },
+                LinkEntry {
+                    link: "http://b.com/f.bin".to_string(),
+                    out: Some("nice name.bin".to_string()),
+                },
+                LinkEntry {
+                    link: "magnet:?xt=urn:btih:abc&dn=Some+Movie%20.mkv".to_string(),
+                    out: Some("Some Movie .mkv".to_string()),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn tracking_is_removed() {
+        assert_eq!(
+            stripTracking("http://a.com/f?utm_medium=m&x=1&fbclid=2#utm_source=s"),
+            "http://a.com/f?x=1"
+        );
+        assert_eq!(stripTracking("http://a.com/f?gclid=1"), "http://a.com/f");
+        assert_eq!(
+            stripTracking("http://a.com/v.mp4?utm_campaign=c#t=30"),
+            "http://a.com/v.mp4#t=30"
+        );
+        assert_eq!(
+            stripTracking("http://a.com/v.mp4#t=30&utm_source=s"),
+            "http://a.com/v.mp4#t=30"
+        );
+    }
+
+    #[test]
+    fn ranges_are_expanded() {
+        assert_eq!(
+            links("http://a.com/file[008-011].jpg"),
+            vec![
+                "http://a.com/file008.jpg",
+                "http://a.com/file009.jpg",
+                "http://a.com/file010.jpg",
+                "http://a.com/file011.jpg",
+            ]
+        );
+        assert_eq!(
+            expandRanges("http://a.com/[a-b]/[1-2]"),
+            vec![
+                "http://a.com/a/1",
+                "http://a.com/a/2",
+                "http://a.com/b/1",
+                "http://a.com/b/2",
+            ]
+        );
+        // invalid and huge ranges are not expanded
+        assert_eq!(
+            expandRanges("http://a.com/[5-1]"),
+            vec!["http://a.com/[5-1]"]
+        );
+        assert_eq!(
+            expandRanges("http://a.com/[1-999999]"),
+            vec!["http://a.com/[1-999999]"]
+        );
+        assert_eq!(expandRanges("http://[::1]/a"), vec!["http://[::1]/a"]);
+    }
+}
@@ -11,7 +11,7 @@ use std::{
     sync::Mutex,
 };
 
-use crate::os_command;
+use crate::error::GhermezError;
 use crate::useful_tools;
 
 lazy_static! {
@@ -90,20 +90,20 @@ pub fn _log_to<T: Write + Send + 'static>(sink: T, max_log_level: LevelFilter) {
 }
 
 #[pyfunction]
-pub fn initLogger() {
+pub fn initLogger() -> Result<(), GhermezError> {
     let config_folder = useful_tools::determineConfigFolder();
     if !config_folder.exists() {
-        fs::create_dir_all(config_folder.clone()).unwrap();
+        fs::create_dir_all(config_folder.clone())?;
     }
 
     let log_file = config_folder.join("ghermezdm.log");
-    if !log_file.is_file() {
-        os_command::touch(log_file.to_str().unwrap());
-    }
-
-    let file = OpenOptions::new().append(true).open(log_file).unwrap();
+    let file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(log_file)?;
     LOGGER.renew(file);
     let _ = log::set_logger(&*LOGGER).map(|()| log::set_max_level(LevelFilter::Info));
+    Ok(())
 }
 
 #[pyfunction]
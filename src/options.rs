@@ -0,0 +1,481 @@
+#![allow(non_snake_case)]
+
+use std::collections::HashMap;
+
+use once_cell::sync::Lazy;
+use pyo3::{exceptions::PyValueError, prelude::*, types::PyDict};
+use serde_json::Value;
+
+use aria2_ws::TaskOptions;
+
+use crate::{
+    cookies::{cookieOption, CookieOption},
+    useful_tools::{returnDefaultSettings, round},
+};
+
+// aria2 options that python side sends with download requests.
+// keys are aria2 option names, for example {'dir': '/tmp', 'split': 16}.
+// see aria2 documentation for more information:
+// https://aria2.github.io/manual/en/html/aria2c.html#input-file
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DownloadOptions(pub TaskOptions);
+
+// ghermez settings that are used in aria2 options
+const DOWNLOAD_SETTINGS: [&str; 5] = [
+    "max-tries",
+    "retry-wait",
+    "timeout",
+    "dont-check-certificate",
+    "download_path_temp",
+];
+
+// default values of DOWNLOAD_SETTINGS, same as python side gets from returnDefaultSettings.
+// front ends without their own settings start downloads with these values.
+static DEFAULT_SETTINGS: Lazy<HashMap<String, String>> = Lazy::new(|| {
+    returnDefaultSettings(vec![])
+        .into_iter()
+        .filter(|(key, _)| DOWNLOAD_SETTINGS.contains(key))
+        .map(|(key, value)| (key.to_string(), value))
+        .collect()
+});
+
+// these options have no field in TaskOptions and are sent as extra options.
+const EXTRA_OPTIONS: [&str; 10] = [
+    "all-proxy-user",
+    "all-proxy-passwd",
+    "http-user",
+    "http-passwd",
+    "referer",
+    "user-agent",
+    "min-split-size",
+    "retry-wait",
+    "check-certificate",
+    "load-cookies",
+];
+
+impl<'source> FromPyObject<'source> for DownloadOptions {
+    fn extract(ob: &'source PyAny) -> PyResult<Self> {
+        let dict: &PyDict = ob.downcast()?;
+        let mut options = TaskOptions::default();
+
+        for (key, value) in dict.iter() {
+            let key: &str = key.extract()?;
+
+            if key == "header" {
+                let header: Option<Vec<String>> = value.extract()?;
+                options.header = header.filter(|h| !h.is_empty());
+                continue;
+            }
+
+            // python side uses None and '' for options that are not set
+            let Some(text) = extractString(value)? else {
+                continue;
+            };
+
+            match key {
+                "all-proxy" => options.all_proxy = Some(text),
+                "dir" => options.dir = Some(text),
+                "out" => options.out = Some(text),
+                "gid" => options.gid = Some(text),
+                "max-download-limit" => options.max_download_limit = Some(text),
+                "lowest-speed-limit" => options.lowest_speed_limit = Some(text),
+                "split" => options.split = Some(parseNumber(key, &text)?),
+                "max-connection-per-server" => {
+                    options.max_connection_per_server = Some(parseNumber(key, &text)?)
+                }
+                "max-tries" => options.max_tries = Some(parseNumber(key, &text)?),
+                "timeout" => options.timeout = Some(parseNumber(key, &text)?),
+                "continue" => options.r#continue = Some(parseBool(key, &text)?),
+                "check-certificate" => {
+                    let check = parseBool(key, &text)?;
+                    options
+                        .extra_options
+                        .insert(key.to_string(), Value::String(check.to_string()));
+                }
+                _ if EXTRA_OPTIONS.contains(&key) => {
+                    options
+                        .extra_options
+                        .insert(key.to_string(), Value::String(text));
+                }
+                _ => {
+                    return Err(PyValueError::new_err(format!(
+                        "unknown aria2 option: {key}"
+                    )))
+                }
+            }
+        }
+
+        Ok(Self(options))
+    }
+}
+
+// convert python value to string.
+// None, '' and 'None' are returned as None.
+fn extractString(value: &PyAny) -> PyResult<Option<String>> {
+    if value.is_none() {
+        return Ok(None);
+    }
+    let text = value.str()?.to_string();
+    if text.is_empty() || text == "None" {
+        return Ok(None);
+    }
+    Ok(Some(text))
+}
+
+fn parseNumber(key: &str, text: &str) -> PyResult<i32> {
+    text.trim()
+        .parse()
+        .map_err(|_| PyValueError::new_err(format!("{key} must be a number, not {text:?}")))
+}
+
+fn parseBool(key: &str, text: &str) -> PyResult<bool> {
+    match text.to_lowercase().as_str() {
+        "true" | "yes" => Ok(true),
+        "false" | "no" => Ok(false),
+        _ => Err(PyValueError::new_err(format!(
+            "{key} must be true or false, not {text:?}"
+        ))),
+    }
+}
+
+impl DownloadOptions {
+    // make aria2 options from a row of addlink_db_table and ghermez settings.
+    // settings can contain max-tries, retry-wait, timeout, dont-check-certificate
+    // and download_path_temp (folder that aria2 downloads file into it).
+    // settings that are missing, empty or 'None' are taken from DEFAULT_SETTINGS.
+    pub fn fromAddLink(
+        add_link_dict: &HashMap<String, String>,
+        settings: &HashMap<String, String>,
+    ) -> Self {
+        // data base returns 'NULL' for empty columns and python side stores 'None'
+        let get = |key: &str| {
+            add_link_dict
+                .get(key)
+                .filter(|value| !["NULL", "None", ""].contains(&value.as_str()))
+                .cloned()
+        };
+        let setting = |key: &str| {
+            settings
+                .get(key)
+                .filter(|value| !["None", ""].contains(&value.as_str()))
+                .or_else(|| DEFAULT_SETTINGS.get(key))
+        };
+
+        let mut options = TaskOptions {
+            gid: get("gid"),
+            out: get("out"),
+            dir: setting("download_path_temp").cloned(),
+            split: Some(16),
+            r#continue: Some(true),
+            max_tries: setting("max-tries").and_then(|x| x.parse().ok()),
+            timeout: setting("timeout").and_then(|x| x.parse().ok()),
+            max_connection_per_server: get("connections").and_then(|x| x.parse().ok()),
+            max_download_limit: get("limit_value").map(|x| convertLimit(&x)),
+            ..Default::default()
+        };
+
+        // make header option
+        let mut header_list = vec![];
+        let cookies = get("load_cookies")
+            .and_then(|load_cookies| cookieOption(&load_cookies, &get("link").unwrap_or_default()));
+        if let Some(CookieOption::Header(cookies)) = &cookies {
+            header_list.push(format!("Cookie: {cookies}"));
+        }
+        if let Some(header) = get("header") {
+            header_list.extend(headerOption(&headerPairs(&header)));
+        }
+        if !header_list.is_empty() {
+            options.header = Some(header_list);
+        }
+
+        // create ip_port from ip and port in desired format.
+        // for example "127.0.0.1:8118"
+        if let Some(ip) = get("ip") {
+            options.all_proxy = Some(match get("port") {
+                Some(port) => format!("{ip}:{port}"),
+                None => ip,
+            });
+        }
+
+        let mut extra_options = vec![
+            ("all-proxy-user", get("proxy_user")),
+            ("all-proxy-passwd", get("proxy_passwd")),
+            ("http-user", get("download_user")),
+            ("http-passwd", get("download_passwd")),
+            ("user-agent", get("user_agent")),
+            ("referer", get("referer")),
+            ("retry-wait", setting("retry-wait").cloned()),
+            ("min-split-size", Some("1M".to_string())),
+        ];
+        if let Some(CookieOption::File(path)) = cookies {
+            extra_options.push(("load-cookies", Some(path)));
+        }
+        if setting("dont-check-certificate").is_some_and(|x| x == "yes") {
+            extra_options.push(("check-certificate", Some("false".to_string())));
+        }
+        for (key, value) in extra_options {
+            if let Some(value) = value {
+                options
+                    .extra_options
+                    .insert(key.to_string(), Value::String(value));
+            }
+        }
+
+        Self(options)
+    }
+}
+
+// convert Mega to Kilo, RPC does not Support floating point numbers.
+// for example "1.5M" is converted to "1536K"
+pub fn convertLimit(limit: &str) -> String {
+    if limit == "0" || limit.is_empty() {
+        return limit.to_string();
+    }
+    // limits without K or M unit are sent as they are
+    let (number, multiplier) = if let Some(number) = limit.strip_suffix('K') {
+        (number, 1.0)
+    } else if let Some(number) = limit.strip_suffix('M') {
+        (number, 1024.0)
+    } else {
+        return limit.to_string();
+    };
+    let Ok(limit_number) = number.parse::<f32>() else {
+        return limit.to_string();
+    };
+    format!("{}K", round(multiplier * limit_number, 0))
+}
+
+// header is saved in data base as a JSON array of name and value pairs:
+// [["name1", "value1"], ["name2", "value2"]]
+// old rows and header line edits of python side use "name1=value1; name2=value2".
+pub fn headerPairs(header: &str) -> Vec<(String, String)> {
+    if let Ok(pairs) = serde_json::from_str::<Vec<(String, String)>>(header) {
+        return pairs;
+    }
+    header
+        .split("; ")
+        .filter_map(|item| item.split_once('='))
+        .filter(|(name, _)| !name.trim().is_empty())
+        .map(|(name, value)| (name.trim().to_string(), value.to_string()))
+        .collect()
+}
+
+// convert header to the format that is saved in data base
+pub(crate) fn headerJson(header: &str) -> String {
+    serde_json::to_string(&headerPairs(header)).unwrap()
+}
+
+// aria2 wants a list like this: ["name1:value1", "name2:value2"]
+pub fn headerOption(pairs: &[(String, String)]) -> Vec<String> {
+    pairs
+        .iter()
+        .map(|(name, value)| format!("{name}:{value}"))
+        .collect()
+}
+
+// headerText returns header of data base in "name1=value1; name2=value2" format
+// for header line edits. JSON is returned if a name or value contains "=" or ";",
+// so nothing is lost when text is saved again.
+#[pyfunction]
+pub fn headerText(header: &str) -> String {
+    let pairs = headerPairs(header);
+    if pairs
+        .iter()
+        .any(|(name, value)| name.contains(['=', ';']) || value.contains(';'))
+    {
+        return serde_json::to_string(&pairs).unwrap();
+    }
+    pairs
+        .iter()
+        .map(|(name, value)| format!("{name}={value}"))
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn extract_download_dict() {
+        pyo3::prepare_freethreaded_python();
+        Python::with_gil(|py| {
+            let dict = PyDict::new(py);
+            dict.set_item("gid", "a1b2c3d4e5f60718").unwrap();
+            dict.set_item("max-tries", "5").unwrap();
+            dict.set_item("retry-wait", 0).unwrap();
+            dict.set_item("timeout", 60).unwrap();
+            dict.set_item("header", vec!["Cookie: a=b", "X-Test: 1"])
+                .unwrap();
+            dict.set_item("out", py.None()).unwrap();
+            dict.set_item("referer", "").unwrap();
+            dict.set_item("all-proxy", "127.0.0.1:8118").unwrap();
+            dict.set_item("http-user", "None").unwrap();
+            dict.set_item("split", "16").unwrap();
+            dict.set_item("continue", "true").unwrap();
+            dict.set_item("check-certificate", false).unwrap();
+            dict.set_item("dir", "/tmp").unwrap();
+
+            let DownloadOptions(options) = dict.extract().unwrap();
+            assert_eq!(options.gid.as_deref(), Some("a1b2c3d4e5f60718"));
+            assert_eq!(options.max_tries, Some(5));
+            assert_eq!(options.timeout, Some(60));
+            assert_eq!(options.split, Some(16));
+            assert_eq!(options.r#continue, Some(true));
+            assert_eq!(options.all_proxy.as_deref(), Some("127.0.0.1:8118"));
+            assert_eq!(options.dir.as_deref(), Some("/tmp"));
+            assert_eq!(options.out, None);
+            assert_eq!(
+                options.header,
+                Some(vec!["Cookie: a=b".to_string(), "X-Test: 1".to_string()])
+            );
+            assert_eq!(options.extra_options["retry-wait"], "0");
+            assert_eq!(options.extra_options["check-certificate"], "false");
+            assert!(!options.extra_options.contains_key("referer"));
+            assert!(!options.extra_options.contains_key("http-user"));
+        });
+    }
+
+    #[test]
+    fn reject_unknown_and_invalid_options() {
+        pyo3::prepare_freethreaded_python();
+        Python::with_gil(|py| {
+            let dict = PyDict::new(py);
+            dict.set_item("max-trys", "5").unwrap();
+            assert!(dict.extract::<DownloadOptions>().is_err());
+
+            let dict = PyDict::new(py);
+            dict.set_item("split", "many").unwrap();
+            assert!(dict.extract::<DownloadOptions>().is_err());
+        });
+    }
+
+    fn addlink_row() -> HashMap<String, String> {
+        [
+            ("gid", "a1b2c3d4e5f60718"),
+            ("out", "NULL"),
+            ("start_time", "NULL"),
+            ("end_time", "NULL"),
+            ("link", "http://example.com/file.zip"),
+            ("ip", "127.0.0.1"),
+            ("port", "8118"),
+            ("proxy_user", "None"),
+            ("proxy_passwd", "None"),
+            ("download_user", "user"),
+            ("download_passwd", "pass"),
+            ("connections", "8"),
+            ("limit_value", "1.5M"),
+            ("download_path", "/home/user/Downloads"),
+            ("referer", "http://example.com/"),
+            ("load_cookies", "NULL"),
+            ("user_agent", "NULL"),
+            ("header", "X-Token=a=b; Accept=*/*"),
+            ("after_download", "NULL"),
+        ]
+        .into_iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect()
+    }
+
+    #[test]
+    fn options_from_addlink_row() {
+        let settings: HashMap<String, String> = [
+            ("max-tries", "5"),
+            ("retry-wait", "0"),
+            ("timeout", "60"),
+            ("dont-check-certificate", "yes"),
+            ("download_path_temp", "/home/user/.ghermez"),
+        ]
+        .into_iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect();
+
+        let DownloadOptions(options) = DownloadOptions::fromAddLink(&addlink_row(), &settings);
+        assert_eq!(options.gid.as_deref(), Some("a1b2c3d4e5f60718"));
+        assert_eq!(options.out, None);
+        assert_eq!(options.dir.as_deref(), Some("/home/user/.ghermez"));
+        assert_eq!(options.all_proxy.as_deref(), Some("127.0.0.1:8118"));
+        assert_eq!(options.max_download_limit.as_deref(), Some("1536K"));
+        assert_eq!(options.max_connection_per_server, Some(8));
+        assert_eq!(options.max_tries, Some(5));
+        assert_eq!(options.timeout, Some(60));
+        assert_eq!(
+            options.header,
+            Some(vec!["X-Token:a=b".to_string(), "Accept:*/*".to_string()])
+        );
+        assert_eq!(options.extra_options["http-user"], "user");
+        assert_eq!(options.extra_options["referer"], "http://example.com/");
+        assert_eq!(options.extra_options["check-certificate"], "false");
+        assert!(!options.extra_options.contains_key("all-proxy-user"));
+        assert!(!options.extra_options.contains_key("user-agent"));
+        assert!(!options.extra_options.contains_key("load-cookies"));
+    }
+
+    #[test]
+    fn default_settings_are_used() {
+        let settings = HashMap::from([("timeout".to_string(), "None".to_string())]);
+        let DownloadOptions(options) = DownloadOptions::fromAddLink(&addlink_row(), &settings);
+        assert_eq!(options.max_tries, Some(5));
+        assert_eq!(options.timeout, Some(60));
+        assert!(options.dir.is_some_and(|dir| dir.ends_with("ghermez")));
+        assert_eq!(options.extra_options["retry-wait"], "0");
+        assert!(!options.extra_options.contains_key("check-certificate"));
+    }
+
+    #[test]
+    fn cookies_from_addlink_row() {
+        let settings = HashMap::new();
+        let mut add_link_dict = addlink_row();
+        add_link_dict.remove("header");
+
+        // python side stores 'None' when there is no cookie
+        add_link_dict.insert("load_cookies".to_string(), "None".to_string());
+        let DownloadOptions(options) = DownloadOptions::fromAddLink(&add_link_dict, &settings);
+        assert_eq!(options.header, None);
+
+        add_link_dict.insert("load_cookies".to_string(), "a=b; c=d".to_string());
+        let DownloadOptions(options) = DownloadOptions::fromAddLink(&add_link_dict, &settings);
+        assert_eq!(options.header, Some(vec!["Cookie: a=b; c=d".to_string()]));
+
+        // cookies of other domains are not sent
+        let cookies_txt = "other.com\tFALSE\t/\tFALSE\t0\ta\tb\n";
+        add_link_dict.insert("load_cookies".to_string(), cookies_txt.to_string());
+        let DownloadOptions(options) = DownloadOptions::fromAddLink(&add_link_dict, &settings);
+        assert_eq!(options.header, None);
+        assert!(!options.extra_options.contains_key("load-cookies"));
+    }
+
+    #[test]
+    fn header_pairs_and_text() {
+        let pairs = vec![
+            ("X-Token".to_string(), "a=b".to_string()),
+            ("Accept".to_string(), "*/*".to_string()),
+        ];
+        assert_eq!(headerPairs("X-Token=a=b; Accept=*/*"), pairs);
+        assert_eq!(headerPairs(&headerJson("X-Token=a=b; Accept=*/*")), pairs);
+        assert_eq!(
+            headerJson("X-Token=a=b; Accept=*/*"),
+            r#"[["X-Token","a=b"],["Accept","*/*"]]"#
+        );
+        assert!(headerPairs("None").is_empty());
+        assert_eq!(headerOption(&pairs), vec!["X-Token:a=b", "Accept:*/*"]);
+
+        // values with "; " are kept in JSON
+        let json = r#"[["Cookie","a=1; b=2"]]"#;
+        assert_eq!(headerPairs(json)[0].1, "a=1; b=2");
+        assert_eq!(headerText(json), json);
+        assert_eq!(headerText(&headerJson("A=1; B=x=y")), "A=1; B=x=y");
+    }
+
+    #[test]
+    fn convert_speed_limit() {
+        assert_eq!(convertLimit("0"), "0");
+        assert_eq!(convertLimit("100K"), "100K");
+        assert_eq!(convertLimit("2M"), "2048K");
+        assert_eq!(convertLimit("0.5M"), "512K");
+        // user input that is not a limit is not changed
+        assert_eq!(convertLimit("10€"), "10€");
+        assert_eq!(convertLimit("€"), "€");
+        assert_eq!(convertLimit("xK"), "xK");
+    }
+}
@@ -1,6 +1,7 @@
 #![allow(non_snake_case)]
 
 use std::fs::{self, OpenOptions};
+use std::io;
 use std::path::Path;
 use std::process::{Command, Stdio};
 
@@ -9,30 +10,32 @@ use std::os::windows::process::CommandExt;
 
 use pyo3::prelude::*;
 
+use crate::error::GhermezError;
+
 #[cfg(target_os = "linux")]
-pub fn findFileManager() -> String {
+pub fn findFileManager() -> io::Result<String> {
     let output = Command::new("xdg-mime")
         .args(["query", "default", "inode/directory"])
-        .output()
-        .unwrap();
-    String::from_utf8_lossy(&output.stdout)
+        .output()?;
+    Ok(String::from_utf8_lossy(&output.stdout)
         .trim()
-        .to_lowercase()
+        .to_lowercase())
 }
 
 #[pyfunction]
-pub fn touch(file_path: &str) {
+pub fn touch(file_path: &str) -> Result<(), GhermezError> {
     OpenOptions::new()
         .create(true)
+        .truncate(false)
         .write(true)
-        .open(file_path)
-        .unwrap();
+        .open(file_path)?;
+    Ok(())
 }
 
 // xdgOpen opens files or folders
 #[pyfunction]
 #[pyo3(signature = (file_path, f_type="file", path="file"))]
-pub fn xdgOpen(file_path: &str, f_type: &str, path: &str) {
+pub fn xdgOpen(file_path: &str, f_type: &str, path: &str) -> Result<(), GhermezError> {
     // we have a file path and we want to open it's directory.
     // highlit(select) file in file manager after opening.
     // it's help to find file easier :)
@@ -41,7 +44,7 @@ pub fn xdgOpen(file_path: &str, f_type: &str, path: &str) {
     // for linux and bsd
     #[cfg(target_os = "linux")]
     {
-        let file_manager = findFileManager();
+        let file_manager = findFileManager()?;
         // check default file manager.
         // some file managers wouldn't support highlighting.
         if highlight {
@@ -52,8 +55,7 @@ pub fn xdgOpen(file_path: &str, f_type: &str, path: &str) {
                     .stderr(Stdio::piped())
                     .stdout(Stdio::piped())
                     .stdin(Stdio::piped())
-                    .status()
-                    .unwrap();
+                    .status()?;
             }
             // dde-file-manager is deepin's file manager
             else if file_manager.contains("dde-file-manager") {
@@ -62,8 +64,7 @@ pub fn xdgOpen(file_path: &str, f_type: &str, path: &str) {
                     .stderr(Stdio::piped())
                     .stdout(Stdio::piped())
                     .stdin(Stdio::piped())
-                    .status()
-                    .unwrap();
+                    .status()?;
             }
             // if file manager is nautilus or nemo or pantheon-file-manager
             else if [
@@ -93,19 +94,19 @@ pub fn xdgOpen(file_path: &str, f_type: &str, path: &str) {
                     .stderr(Stdio::piped())
                     .stdout(Stdio::piped())
                     .stdin(Stdio::piped())
-                    .status()
-                    .unwrap();
+                    .status()?;
             } else {
                 // find folder path
-                let folder_path = Path::new(file_path).parent().unwrap();
+                let folder_path = Path::new(file_path)
+                    .parent()
+                    .unwrap_or(Path::new(file_path));
 
                 Command::new("xdg-open")
                     .arg(folder_path)
                     .stderr(Stdio::piped())
                     .stdout(Stdio::piped())
                     .stdin(Stdio::piped())
-                    .status()
-                    .unwrap();
+                    .status()?;
             }
         } else {
             Command::new("xdg-open")
@@ -113,8 +114,7 @@ pub fn xdgOpen(file_path: &str, f_type: &str, path: &str) {
                 .stderr(Stdio::piped())
                 .stdout(Stdio::piped())
                 .stdin(Stdio::piped())
-                .status()
-                .unwrap();
+                .status()?;
         }
     }
 
@@ -127,16 +127,14 @@ pub fn xdgOpen(file_path: &str, f_type: &str, path: &str) {
                 .stderr(Stdio::piped())
                 .stdout(Stdio::piped())
                 .stdin(Stdio::piped())
-                .status()
-                .unwrap();
+                .status()?;
         } else {
             Command::new("open")
                 .arg(file_path)
                 .stderr(Stdio::piped())
                 .stdout(Stdio::piped())
                 .stdin(Stdio::piped())
-                .status()
-                .unwrap();
+                .status()?;
         }
     }
 
@@ -152,8 +150,7 @@ pub fn xdgOpen(file_path: &str, f_type: &str, path: &str) {
                 .stdout(Stdio::piped())
                 .stdin(Stdio::piped())
                 .creation_flags(NO_WINDOW)
-                .status()
-                .unwrap();
+                .status()?;
         } else {
             Command::new("cmd")
                 .args(["/C", "start", file_path, file_path])
@@ -161,57 +158,49 @@ pub fn xdgOpen(file_path: &str, f_type: &str, path: &str) {
                 .stdout(Stdio::piped())
                 .stdin(Stdio::piped())
                 .creation_flags(NO_WINDOW)
-                .status()
-                .unwrap();
+                .status()?;
         }
     }
+
+    Ok(())
 }
 
 // remove file with path of file_path
+// it returns false if file doesn't exist.
 #[pyfunction]
-pub fn remove(file_path: &str) -> String {
+pub fn remove(file_path: &str) -> Result<bool, GhermezError> {
     if Path::new(file_path).is_file() {
-        let result = fs::remove_file(file_path);
-        match result {
-            // function returns  ok, if operation was successful
-            Ok(_) => "ok".to_string(),
-            // function returns this, if operation was not successful
-            Err(_) => "cant".to_string(),
-        }
+        fs::remove_file(file_path)?;
+        Ok(true)
     } else {
-        // function returns this , if file is not existed
-        "no".to_string()
+        Ok(false)
     }
 }
 
 // removeDir removes folder : folder_path
+// it returns false if folder doesn't exist.
 #[pyfunction]
-pub fn removeDir(folder_path: &str) -> String {
+pub fn removeDir(folder_path: &str) -> Result<bool, GhermezError> {
     // check folder_path existence
     if Path::new(folder_path).is_dir() {
         // remove folder
-        let result = fs::remove_dir_all(folder_path);
-        match result {
-            Ok(_) => "ok".to_string(),
-            // return 'cant' if removing was not successful
-            Err(_) => "cant".to_string(),
-        }
+        fs::remove_dir_all(folder_path)?;
+        Ok(true)
     } else {
-        // return 'no' if file didn't existed
-        "no".to_string()
+        Ok(false)
     }
 }
 
 // make directory
 #[pyfunction]
 #[pyo3(signature = (folder_path, hidden=false))]
-pub fn makeDirs(folder_path: &str, hidden: bool) -> String {
+pub fn makeDirs(folder_path: &str, hidden: bool) -> Result<String, GhermezError> {
     if hidden {
         #[cfg(target_os = "windows")]
         {
             // create hidden attribute directory.
 
-            fs::create_dir_all(folder_path).unwrap();
+            fs::create_dir_all(folder_path)?;
 
             const NO_WINDOW: u32 = 0x08000000;
             Command::new("attrib")
@@ -220,31 +209,42 @@ pub fn makeDirs(folder_path: &str, hidden: bool) -> String {
                 .stdout(Stdio::piped())
                 .stdin(Stdio::piped())
                 .creation_flags(NO_WINDOW)
-                .status()
-                .unwrap();
+                .status()?;
         }
 
         #[cfg(not(target_os = "windows"))]
         {
             // In linux and bsd a dot character must be added in the start of the directory's name
-            let dir_name = Path::new(folder_path).file_name().unwrap();
-            let parent_path = Path::new(folder_path).parent().unwrap();
-            let folder_path =
-                Path::new(parent_path).join(".".to_owned() + dir_name.to_str().unwrap());
+            let folder_path = Path::new(folder_path);
+            let (Some(dir_name), Some(parent_path)) =
+                (folder_path.file_name(), folder_path.parent())
+            else {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("{} is not a folder path", folder_path.display()),
+                )
+                .into());
+            };
+            let folder_path = parent_path.join(format!(".{}", dir_name.to_string_lossy()));
 
-            fs::create_dir_all(folder_path).unwrap();
+            fs::create_dir_all(folder_path)?;
         }
     } else {
-        fs::create_dir_all(folder_path).unwrap();
+        fs::create_dir_all(folder_path)?;
     }
 
-    folder_path.to_string()
+    Ok(folder_path.to_string())
 }
 
 // move downloaded file to another destination.
+// it returns false if old_file_path or destination folder doesn't exist.
 #[pyfunction]
 #[pyo3(signature = (old_file_path, new_path, new_path_type="folder"))]
-pub fn moveFile(old_file_path: &str, new_path: &str, new_path_type: &str) -> bool {
+pub fn moveFile(
+    old_file_path: &str,
+    new_path: &str,
+    new_path_type: &str,
+) -> Result<bool, GhermezError> {
     // new_path_type can be file or folder
     // if it's folder so we have folder path
     // else we have new file path that includes file name
@@ -257,12 +257,12 @@ pub fn moveFile(old_file_path: &str, new_path: &str, new_path_type: &str) -> boo
         };
         if check_path {
             // move file to new_path
-            let result = fs::rename(old_file_path, new_path);
-            result.is_ok()
+            fs::rename(old_file_path, new_path)?;
+            Ok(true)
         } else {
-            false
+            Ok(false)
         }
     } else {
-        false
+        Ok(false)
     }
 }
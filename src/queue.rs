@@ -0,0 +1,430 @@
+#![allow(non_snake_case)]
+// pyo3 0.19 macros trigger this lint on newer compilers
+#![allow(non_local_definitions)]
+
+use std::{
+    collections::{HashMap, HashSet},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time,
+};
+
+use log::{error, info};
+use pyo3::prelude::*;
+
+use aria2_ws::TaskOptions;
+
+use crate::{
+    aria2c::{asyncRpc, downloadTableStatus, stopDownload, RUNTIME},
+    database::DataBase,
+    error::GhermezError,
+    events::{pushEvent, DownloadEvent},
+    options::convertLimit,
+    pool::{inPool, isPending, submit},
+};
+
+// queue checks its downloads every TICK.
+const TICK: time::Duration = time::Duration::from_secs(1);
+
+// statuses that mean aria2 is finished with download
+const FINISHED: [&str; 3] = ["complete", "error", "stopped"];
+
+// find next downloads of queue.
+// gid_list is in the order of category_item. queue starts from the end of
+// gid_list (top of download table), unless reverse is true.
+// completed downloads and downloads that are tried in this run are skipped.
+fn nextGids(
+    gid_list: &[String],
+    reverse: bool,
+    statuses: &HashMap<String, String>,
+    active: &[String],
+    done: &HashSet<String>,
+    free: usize,
+) -> Vec<String> {
+    let ordered: Box<dyn Iterator<Item = &String>> = if reverse {
+        Box::new(gid_list.iter())
+    } else {
+        Box::new(gid_list.iter().rev())
+    };
+    ordered
+        .filter(|gid| !active.contains(gid) && !done.contains(*gid))
+        .filter(|gid| {
+            statuses
+                .get(*gid)
+                .is_some_and(|status| status != "complete")
+        })
+        .take(free)
+        .cloned()
+        .collect()
+}
+
+// speed limit of category for aria2, or None if it's not limited.
+fn categoryLimit(category_dict: &HashMap<&str, String>) -> Option<String> {
+    if category_dict.get("limit_enable").map(String::as_str) != Some("yes") {
+        return None;
+    }
+    category_dict
+        .get("limit_value")
+        .map(|limit| convertLimit(limit))
+        .filter(|limit| limit != "0" && limit != "0K")
+}
+
+fn queueEvent(category: &str, event: &'static str) {
+    pushEvent(DownloadEvent {
+        gid: None,
+        category: Some(category.to_string()),
+        event,
+        synthetic: false,
+    });
+}
+
+// state of a running queue
+struct Walker {
+    database: DataBase,
+    category: String,
+    parallel: usize,
+    settings: HashMap<String, String>,
+    // pause between a finished download and the next one
+    wait: time::Duration,
+    stop: Arc<AtomicBool>,
+    // gids that are sent to download pool and not finished yet
+    active: Vec<String>,
+    // gids that are finished in this run. they are not started again.
+    done: HashSet<String>,
+    // speed limit of category
+    limit: Option<String>,
+    // active gids that speed limit of category is applied to them
+    limited: HashSet<String>,
+    // next download is not started before this time
+    resume_at: Option<time::Instant>,
+}
+
+impl Walker {
+    // put gid in download pool. pool sends it to aria2 when there is a free slot.
+    fn startGid(&self, gid: &str) -> Result<(), GhermezError> {
+        submit(&self.database, gid.to_string(), self.settings.clone(), 0)?;
+        info!("{gid} is sent to download pool");
+        Ok(())
+    }
+
+    // write download information of active gids in data base
+    // and find out which of them are finished.
+    async fn updateActive(&mut self) -> Result<(), GhermezError> {
+        for gid in self.active.clone() {
+            // aria2 doesn't know downloads that are waiting in pool yet
+            if isPending(&gid) {
+                continue;
+            }
+            let finished = match downloadTableStatus(&gid).await {
+                Ok(download_dict) => {
+                    let download_dict: HashMap<&str, &str> = download_dict
+                        .iter()
+                        .filter_map(|(key, value)| Some((key.as_str(), value.as_deref()?)))
+                        .collect();
+                    self.database
+                        .updateDownloadTable(vec![download_dict.clone()])?;
+                    download_dict
+                        .get("status")
+                        .is_some_and(|status| FINISHED.contains(status))
+                }
+                // aria2 doesn't know gid anymore, for example user removed it.
+                // pool may be sending it to aria2 right now.
+                Err(GhermezError::Aria2Rpc { .. }) => !inPool(&gid),
+                Err(e) => return Err(e),
+            };
+            if finished {
+                self.active.retain(|active_gid| *active_gid != gid);
+                self.limited.remove(&gid);
+                self.done.insert(gid);
+                if !self.wait.is_zero() {
+                    self.resume_at = Some(time::Instant::now() + self.wait);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // apply speed limit of category to active downloads that aria2 knows.
+    // downloads that are waiting in pool are limited after they are sent to aria2.
+    async fn applyLimit(&mut self, limit: Option<String>) {
+        if limit != self.limit {
+            // limit is canceled, so remove it from downloads of queue
+            let unlimited = if limit.is_none() {
+                self.limited.drain().collect()
+            } else {
+                vec![]
+            };
+            for gid in unlimited {
+                self.changeLimit(&gid, "0").await;
+            }
+            self.limited.clear();
+            self.limit = limit;
+        }
+        let Some(limit) = self.limit.clone() else {
+            return;
+        };
+        for gid in self.active.clone() {
+            if self.limited.contains(&gid) || isPending(&gid) {
+                continue;
+            }
+            if self.changeLimit(&gid, &limit).await {
+                self.limited.insert(gid);
+            }
+        }
+    }
+
+    // change max-download-limit of gid. it returns true if it's successful.
+    async fn changeLimit(&self, gid: &str, limit: &str) -> bool {
+        let options = TaskOptions {
+            max_download_limit: Some(limit.to_string()),
+            ..Default::default()
+        };
+        match asyncRpc(|client| async move { client.change_option(gid, options).await }).await {
+            Ok(()) => true,
+            Err(e) => {
+                error!("Speed limitation was unsuccessful: {e}");
+                false
+            }
+        }
+    }
+
+    // one step of queue. it returns true when queue is finished.
+    async fn step(&mut self) -> Result<bool, GhermezError> {
+        let Some(category_dict) = self
+            .database
+            .searchCategoryInCategoryTable(&self.category)?
+        else {
+            // category is deleted
+            return Ok(true);
+        };
+        let limit = categoryLimit(&category_dict);
+        let reverse = category_dict.get("reverse").map(String::as_str) == Some("yes");
+
+        self.updateActive().await?;
+        self.applyLimit(limit).await;
+
+        // wait between downloads
+        if self
+            .resume_at
+            .is_some_and(|resume_at| time::Instant::now() < resume_at)
+        {
+            return Ok(false);
+        }
+
+        let gid_list = self.database.gidListOfCategory(&self.category)?;
+        let statuses = self.database.statusesOfCategory(&self.category)?;
+        let free = self.parallel.saturating_sub(self.active.len());
+        for gid in nextGids(
+            &gid_list,
+            reverse,
+            &statuses,
+            &self.active,
+            &self.done,
+            free,
+        ) {
+            match self.startGid(&gid) {
+                Ok(()) => self.active.push(gid),
+                Err(e) => {
+                    error!("Queue {} couldn't start {gid}: {e}", self.category);
+                    self.done.insert(gid);
+                }
+            }
+        }
+
+        Ok(self.active.is_empty())
+    }
+
+    async fn walk(mut self, finished: Arc<AtomicBool>) {
+        info!("Queue {} is started", self.category);
+        let mut interval = tokio::time::interval(TICK);
+        loop {
+            interval.tick().await;
+            if self.stop.load(Ordering::SeqCst) {
+                for gid in &self.active {
+                    if let Err(e) = stopDownload(&self.database, gid).await {
+                        error!("Couldn't stop {gid}: {e}");
+                    }
+                }
+                info!("Queue {} is stopped", self.category);
+                queueEvent(&self.category, "queue_stopped");
+                break;
+            }
+            match self.step().await {
+                Ok(true) => {
+                    info!("Queue {} is finished", self.category);
+                    queueEvent(&self.category, "queue_complete");
+                    break;
+                }
+                Ok(false) => {}
+                Err(e) => error!("Queue {}: {e}", self.category),
+            }
+        }
+        finished.store(true, Ordering::SeqCst);
+    }
+}
+
+// QueueRunner sends downloads of one category to download pool.
+// it walks gid_list of category and keeps `parallel` downloads active until all of
+// them are finished. speed limit of category is applied to its downloads.
+// next download is started `wait` seconds after a download is finished.
+// queue_complete event is sent when queue is finished, so after download action can be done.
+// settings are same as startDownload settings.
+#[pyclass]
+pub struct QueueRunner {
+    database: DataBase,
+    category: String,
+    parallel: usize,
+    settings: HashMap<String, String>,
+    wait: time::Duration,
+    stop: Arc<AtomicBool>,
+    finished: Arc<AtomicBool>,
+    started: bool,
+}
+
+#[pymethods]
+impl QueueRunner {
+    #[new]
+    #[pyo3(signature = (database, category, settings, parallel=1, wait=0))]
+    fn new(
+        database: PyRef<DataBase>,
+        category: String,
+        settings: HashMap<String, String>,
+        parallel: usize,
+        wait: u64,
+    ) -> Self {
+        Self {
+            database: DataBase::clone(&database),
+            category,
+            parallel: parallel.max(1),
+            settings,
+            wait: time::Duration::from_secs(wait),
+            stop: Arc::new(AtomicBool::new(false)),
+            finished: Arc::new(AtomicBool::new(false)),
+            started: false,
+        }
+    }
+
+    // start queue on the shared runtime. it does nothing if queue is running.
+    fn start(&mut self) {
+        if self.isRunning() {
+            return;
+        }
+        self.stop = Arc::new(AtomicBool::new(false));
+        self.finished = Arc::new(AtomicBool::new(false));
+        self.started = true;
+
+        let walker = Walker {
+            database: self.database.clone(),
+            category: self.category.clone(),
+            parallel: self.parallel,
+            settings: self.settings.clone(),
+            wait: self.wait,
+            stop: self.stop.clone(),
+            active: vec![],
+            done: HashSet::new(),
+            limit: None,
+            limited: HashSet::new(),
+            resume_at: None,
+        };
+        RUNTIME.spawn(walker.walk(self.finished.clone()));
+    }
+
+    // stop queue and its active downloads.
+    fn stop(&self) {
+        self.stop.store(true, Ordering::SeqCst);
+    }
+
+    fn isRunning(&self) -> bool {
+        self.started && !self.finished.load(Ordering::SeqCst)
+    }
+
+    #[getter]
+    fn category(&self) -> &str {
+        &self.category
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strings(list: &[&str]) -> Vec<String> {
+        list.iter().map(|x| x.to_string()).collect()
+    }
+
+    #[test]
+    fn next_gids_follow_queue_order() {
+        let gid_list = strings(&["a", "b", "c", "d"]);
+        let statuses = HashMap::from([
+            ("a".to_string(), "stopped".to_string()),
+            ("b".to_string(), "complete".to_string()),
+            ("c".to_string(), "error".to_string()),
+            ("d".to_string(), "stopped".to_string()),
+        ]);
+        let none = HashSet::new();
+
+        // top of download table is the end of gid_list
+        assert_eq!(
+            nextGids(&gid_list, false, &statuses, &[], &none, 2),
+            strings(&["d", "c"])
+        );
+        assert_eq!(
+            nextGids(&gid_list, true, &statuses, &[], &none, 2),
+            strings(&["a", "c"])
+        );
+
+        // active and tried downloads are not started again
+        let done = HashSet::from(["c".to_string()]);
+        assert_eq!(
+            nextGids(&gid_list, false, &statuses, &strings(&["d"]), &done, 1),
+            strings(&["a"])
+        );
+        assert!(nextGids(&gid_list, false, &statuses, &[], &none, 0).is_empty());
+    }
+
+    #[test]
+    fn category_limit() {
+        let mut category_dict = HashMap::from([
+            ("limit_enable", "yes".to_string()),
+            ("limit_value", "1.5M".to_string()),
+        ]);
+        assert_eq!(categoryLimit(&category_dict), Some("1536K".to_string()));
+
+        category_dict.insert("limit_value", "0K".to_string());
+        assert_eq!(categoryLimit(&category_dict), None);
+
+        category_dict.insert("limit_enable", "no".to_string());
+        category_dict.insert("limit_value", "100K".to_string());
+        assert_eq!(categoryLimit(&category_dict), None);
+    }
+
+    #[test]
+    fn queue_reads_category_from_data_base() {
+        let database = DataBase::open(":memory:").unwrap();
+        database.createTables().unwrap();
+        database
+            .insertInDownloadTable(vec![
+                HashMap::from([
+                    ("gid", "a"),
+                    ("status", "stopped"),
+                    ("category", "Scheduled Downloads"),
+                ]),
+                HashMap::from([
+                    ("gid", "b"),
+                    ("status", "complete"),
+                    ("category", "Scheduled Downloads"),
+                ]),
+            ])
+            .unwrap();
+
+        let gid_list = database.gidListOfCategory("Scheduled Downloads").unwrap();
+        assert_eq!(gid_list, strings(&["a", "b"]));
+        let statuses = database.statusesOfCategory("Scheduled Downloads").unwrap();
+        assert_eq!(
+            nextGids(&gid_list, false, &statuses, &[], &HashSet::new(), 1),
+            strings(&["a"])
+        );
+    }
+}
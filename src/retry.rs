@@ -0,0 +1,277 @@
+#![allow(non_snake_case)]
+
+use std::{collections::HashMap, sync::Mutex, time};
+
+use log::{error, info};
+use once_cell::sync::Lazy;
+use pyo3::prelude::*;
+use serde_json::Value;
+
+use crate::{
+    aria2c::{asyncRpc, RUNTIME},
+    database::DataBase,
+    error::GhermezError,
+    events::{pushEvent, DownloadEvent},
+    pool::submit,
+};
+
+// delay between retries doesn't become longer than this.
+const MAX_BACKOFF: time::Duration = time::Duration::from_secs(30 * 60);
+
+// kind of a download error, found from errorCode and errorMessage of aria2.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ErrorKind {
+    // timeout, slow speed, network problem or name resolution failure
+    Network,
+    // HTTP 5xx, server may answer later
+    ServerError,
+    // HTTP 403 or 410, link is expired and user must refresh it
+    Expired,
+    // not enough disk space
+    DiskFull,
+    Other,
+}
+
+impl ErrorKind {
+    // only temporary errors are retried
+    fn retryable(self) -> bool {
+        matches!(self, Self::Network | Self::ServerError)
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::Network => "network error",
+            Self::ServerError => "server error",
+            Self::Expired => "link expired",
+            Self::DiskFull => "disk full",
+            Self::Other => "error",
+        }
+    }
+}
+
+// aria2 reports unexpected HTTP statuses like
+// "The response status is not successful. status=503"
+fn httpStatus(message: &str) -> Option<u16> {
+    let (_, rest) = message.split_once("status=")?;
+    let digits: String = rest.chars().take_while(char::is_ascii_digit).collect();
+    digits.parse().ok()
+}
+
+// see "EXIT STATUS" in aria2 documentation for error codes.
+fn classify(code: Option<&str>, message: Option<&str>) -> ErrorKind {
+    let status = message.and_then(httpStatus);
+    match code.and_then(|code| code.parse::<i32>().ok()) {
+        Some(9) => ErrorKind::DiskFull,
+        Some(2 | 5 | 6 | 19) => ErrorKind::Network,
+        Some(29) => ErrorKind::ServerError,
+        _ => match status {
+            Some(403 | 410) => ErrorKind::Expired,
+            Some(500..=599) => ErrorKind::ServerError,
+            _ => ErrorKind::Other,
+        },
+    }
+}
+
+// delay before retry number attempt. it's doubled after every attempt.
+fn backoff(attempt: u32, base: time::Duration) -> time::Duration {
+    let factor = 2u32.saturating_pow(attempt.saturating_sub(1));
+    base.saturating_mul(factor).min(MAX_BACKOFF)
+}
+
+#[derive(Clone)]
+struct RetryPolicy {
+    database: DataBase,
+    settings: HashMap<String, String>,
+    max_attempts: i64,
+    base_delay: time::Duration,
+}
+
+// None means automatic retry is disabled
+static POLICY: Lazy<Mutex<Option<RetryPolicy>>> = Lazy::new(|| Mutex::new(None));
+
+// put failed gid in download pool again, if user didn't resume, stop or delete it
+// in the meantime. it returns false if gid is not retried.
+// dir is the folder that aria2 downloaded the partial file into.
+pub(crate) fn requeue(
+    database: &DataBase,
+    gid: &str,
+    settings: &HashMap<String, String>,
+    dir: Option<&str>,
+) -> Result<bool, GhermezError> {
+    let status = database
+        .searchGidInDownloadTable(gid)?
+        .and_then(|download_dict| download_dict.get("status").cloned());
+    if status.as_deref() != Some("error") || database.searchGidInAddLinkTable(gid)?.is_none() {
+        return Ok(false);
+    }
+
+    // retried download continues in the same folder
+    let mut settings = settings.clone();
+    if let Some(dir) = dir {
+        settings.insert("download_path_temp".to_string(), dir.to_string());
+    }
+    // pool writes status of download and sends it to aria2 when there is a free slot.
+    submit(database, gid.to_string(), settings, 0)?;
+    Ok(true)
+}
+
+// send download request of failed gid to download pool again after backoff.
+// gid and dir are not changed, so aria2 continues the partial file.
+async fn retryDownload(policy: RetryPolicy, gid: &str) -> Result<(), GhermezError> {
+    let keys = ["errorCode", "errorMessage", "dir"]
+        .into_iter()
+        .map(String::from)
+        .collect();
+    let status =
+        asyncRpc(|client| async move { client.custom_tell_status(gid, Some(keys)).await }).await?;
+    let field = |key: &str| status.get(key).and_then(Value::as_str);
+
+    let kind = classify(field("errorCode"), field("errorMessage"));
+    let last_error = format!(
+        "{}: {}",
+        kind.name(),
+        field("errorMessage").unwrap_or("unknown error")
+    );
+    let database = &policy.database;
+    database.updateDownloadTable(vec![HashMap::from([("gid", gid), ("status", "error")])])?;
+    let attempts = database.recordRetry(gid, &last_error)?;
+
+    if !kind.retryable() || attempts > policy.max_attempts {
+        info!("{gid} is not retried: {last_error}");
+        return Ok(());
+    }
+
+    let delay = backoff(attempts as u32, policy.base_delay);
+    info!("{gid} failed ({last_error}), retry {attempts} in {delay:?}");
+    tokio::time::sleep(delay).await;
+
+    if !requeue(database, gid, &policy.settings, field("dir"))? {
+        return Ok(());
+    }
+
+    pushEvent(DownloadEvent {
+        gid: Some(gid.to_string()),
+        category: None,
+        event: "retry",
+        synthetic: false,
+    });
+    Ok(())
+}
+
+// retry manager looks at download events.
+// failed downloads are retried and retry attempts of completed downloads are forgotten.
+pub(crate) fn watchEvent(event: &DownloadEvent) {
+    let Some(gid) = event.gid.clone() else {
+        return;
+    };
+    let Some(policy) = POLICY.lock().unwrap().clone() else {
+        return;
+    };
+    match event.event {
+        "error" => {
+            RUNTIME.spawn(async move {
+                if let Err(e) = retryDownload(policy, &gid).await {
+                    error!("Couldn't retry {gid}: {e}");
+                }
+            });
+        }
+        "complete" | "bt_complete" => {
+            if let Err(e) = policy.database.resetRetry(&gid) {
+                error!("Couldn't reset retry attempts of {gid}: {e}");
+            }
+        }
+        _ => {}
+    }
+}
+
+// configureRetry enables automatic retry of failed downloads.
+// network errors and HTTP 5xx errors are retried max_attempts times. delay before first
+// retry is base_delay seconds and it's doubled after every attempt.
+// expired links (HTTP 403) and full disk are not retried.
+// retried downloads wait in download pool like other downloads, so many failures
+// at the same time don't exceed limits of pool.
+// attempts and last error are saved in retry_db_table, see DataBase.searchGidInRetryTable.
+// max_attempts = 0 disables automatic retry.
+// settings are same as startDownload settings.
+#[pyfunction]
+#[pyo3(signature = (database, settings, max_attempts=5, base_delay=10))]
+pub fn configureRetry(
+    database: PyRef<DataBase>,
+    settings: HashMap<String, String>,
+    max_attempts: i64,
+    base_delay: u64,
+) {
+    let mut policy = POLICY.lock().unwrap();
+    if max_attempts <= 0 {
+        *policy = None;
+        info!("Automatic retry is disabled");
+        return;
+    }
+    *policy = Some(RetryPolicy {
+        database: DataBase::clone(&database),
+        settings,
+        max_attempts,
+        base_delay: time::Duration::from_secs(base_delay),
+    });
+    info!("Automatic retry: {max_attempts} attempts");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn classify_aria2_errors() {
+        assert_eq!(classify(Some("6"), None), ErrorKind::Network);
+        assert_eq!(classify(Some("19"), None), ErrorKind::Network);
+        assert_eq!(classify(Some("9"), None), ErrorKind::DiskFull);
+        assert_eq!(classify(Some("29"), None), ErrorKind::ServerError);
+        assert_eq!(
+            classify(
+                Some("22"),
+                Some("The response status is not successful. status=502")
+            ),
+            ErrorKind::ServerError
+        );
+        assert_eq!(
+            classify(
+                Some("22"),
+                Some("The response status is not successful. status=403")
+            ),
+            ErrorKind::Expired
+        );
+        assert_eq!(classify(Some("3"), None), ErrorKind::Other);
+        assert_eq!(classify(None, None), ErrorKind::Other);
+        assert!(!ErrorKind::Expired.retryable());
+    }
+
+    #[test]
+    fn backoff_is_doubled() {
+        let base = time::Duration::from_secs(10);
+        assert_eq!(backoff(1, base), time::Duration::from_secs(10));
+        assert_eq!(backoff(3, base), time::Duration::from_secs(40));
+        assert_eq!(backoff(100, base), MAX_BACKOFF);
+    }
+
+    #[test]
+    fn retry_attempts_are_saved() {
+        let database = DataBase::open(":memory:").unwrap();
+        database.createTables().unwrap();
+        database
+            .insertInDownloadTable(vec![HashMap::from([
+                ("gid", "a"),
+                ("status", "error"),
+                ("category", "Single Downloads"),
+            ])])
+            .unwrap();
+
+        assert_eq!(database.recordRetry("a", "network error: x").unwrap(), 1);
+        assert_eq!(database.recordRetry("a", "server error: y").unwrap(), 2);
+        let retry_dict = database.searchGidInRetryTable("a").unwrap().unwrap();
+        assert_eq!(retry_dict["attempts"], "2");
+        assert_eq!(retry_dict["last_error"], "server error: y");
+
+        database.resetRetry("a").unwrap();
+        assert!(database.searchGidInRetryTable("a").unwrap().is_none());
+    }
+}
@@ -0,0 +1,525 @@
+#![allow(non_snake_case)]
+
+use std::{collections::HashMap, sync::Once, time};
+
+use chrono::{Local, TimeZone, Timelike};
+use log::{error, info};
+use pyo3::prelude::*;
+
+use crate::{
+    aria2c::{asyncRpc, stopDownload, RUNTIME},
+    database::{CategoryWindow, DataBase, ScheduledDownload},
+    error::GhermezError,
+    events::{pushEvent, DownloadEvent},
+    pool::submit,
+};
+
+// scheduler looks at the clock every TICK.
+const TICK: time::Duration = time::Duration::from_secs(1);
+
+// source of current time for scheduler.
+// tests use a fake clock instead of local time.
+pub trait Clock: Send + 'static {
+    // minutes since midnight
+    fn now(&self) -> u32;
+    // unix time
+    fn timestamp(&self) -> i64;
+}
+
+pub struct LocalClock;
+
+impl Clock for LocalClock {
+    fn now(&self) -> u32 {
+        let now = Local::now();
+        now.hour() * 60 + now.minute()
+    }
+
+    fn timestamp(&self) -> i64 {
+        Local::now().timestamp()
+    }
+}
+
+// sigmaTime gets time in HH:MM format and returns minutes since midnight.
+// it returns None if time is not a valid HH:MM.
+fn sigmaTime(time: &str) -> Option<u32> {
+    let (hour, minute) = time.trim().split_once(':')?;
+    let (hour, minute): (u32, u32) = (hour.parse().ok()?, minute.parse().ok()?);
+    (hour < 24 && minute < 60).then_some(hour * 60 + minute)
+}
+
+// check that point is arrived after previous tick, until now.
+// previous is bigger than now if midnight is passed between them.
+fn passed(point: u32, previous: u32, now: u32) -> bool {
+    if previous <= now {
+        previous < point && point <= now
+    } else {
+        previous < point || point <= now
+    }
+}
+
+// check that now is in the window from start to end.
+// window crosses midnight if end is before start, for example 23:00 to 1:00.
+fn inWindow(start: u32, end: u32, now: u32) -> bool {
+    if start <= end {
+        start <= now && now < end
+    } else {
+        start <= now || now < end
+    }
+}
+
+// check that window is opened since previous tick.
+// on first tick a start time without end time waits until it arrives,
+// but if now is in the window, then window is open.
+fn opens(start: Option<u32>, end: Option<u32>, previous: Option<u32>, now: u32) -> bool {
+    let Some(start) = start else {
+        return false;
+    };
+    match previous {
+        Some(previous) => passed(start, previous, now),
+        None => end.is_some_and(|end| inWindow(start, end, now)),
+    }
+}
+
+// unix time of first start time after added.
+fn firstStart(start: u32, added: i64) -> Option<i64> {
+    let added = Local.timestamp_opt(added, 0).single()?;
+    let minute = added.hour() * 60 + added.minute();
+    let wait = (start + 24 * 60 - minute) % (24 * 60);
+    Some(added.timestamp() - added.second() as i64 + wait as i64 * 60)
+}
+
+// check that scheduled download must be started since previous tick.
+// on first tick, a start time without end time that is passed while persepolis
+// was closed starts download too. download with end time starts if now is in its window.
+fn startsNow(
+    start: Option<u32>,
+    end: Option<u32>,
+    added: Option<i64>,
+    previous: Option<u32>,
+    now: u32,
+    timestamp: i64,
+) -> bool {
+    match (start, end, previous) {
+        (Some(start), None, None) => added
+            .and_then(|added| firstStart(start, added))
+            .is_some_and(|first_start| first_start <= timestamp),
+        _ => opens(start, end, previous, now),
+    }
+}
+
+// check that window is closed since previous tick.
+fn closes(end: Option<u32>, previous: Option<u32>, now: u32) -> bool {
+    matches!((end, previous), (Some(end), Some(previous)) if passed(end, previous, now))
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Action {
+    // start_time of a scheduled download is arrived
+    Start(String),
+    // end_time of a download is arrived
+    Stop(String),
+    // time window of a category is opened
+    Open(String),
+    // time window of a category is closed
+    Close(String),
+}
+
+// find out what must be done between previous tick and now.
+fn plan(
+    previous: Option<u32>,
+    now: u32,
+    timestamp: i64,
+    downloads: &[ScheduledDownload],
+    windows: &[CategoryWindow],
+) -> Vec<Action> {
+    let mut actions = vec![];
+
+    for download in downloads {
+        let start = download.start_time.as_deref().and_then(sigmaTime);
+        let end = download.end_time.as_deref().and_then(sigmaTime);
+        match download.status.as_str() {
+            "scheduled" if startsNow(start, end, download.added, previous, now, timestamp) => {
+                actions.push(Action::Start(download.gid.clone()));
+            }
+            "downloading" | "waiting" | "paused" if closes(end, previous, now) => {
+                actions.push(Action::Stop(download.gid.clone()));
+            }
+            _ => {}
+        }
+    }
+
+    for window in windows {
+        let start = window.start_time.as_deref().and_then(sigmaTime);
+        let end = window.end_time.as_deref().and_then(sigmaTime);
+        if opens(start, end, previous, now) {
+            actions.push(Action::Open(window.category.clone()));
+        }
+        if closes(end, previous, now) {
+            actions.push(Action::Close(window.category.clone()));
+        }
+    }
+
+    actions
+}
+
+pub struct Scheduler<C: Clock> {
+    clock: C,
+    // time of previous tick. it's None before first tick.
+    previous: Option<u32>,
+}
+
+impl<C: Clock> Scheduler<C> {
+    pub fn new(clock: C) -> Self {
+        Self {
+            clock,
+            previous: None,
+        }
+    }
+
+    // read start and end times from data base and return what must be done now.
+    pub fn tick(&mut self, database: &DataBase) -> Result<Vec<Action>, GhermezError> {
+        let downloads = database.scheduledDownloads()?;
+        let windows = database.categoryWindows()?;
+        let now = self.clock.now();
+        let previous = self.previous.replace(now);
+        Ok(plan(
+            previous,
+            now,
+            self.clock.timestamp(),
+            &downloads,
+            &windows,
+        ))
+    }
+}
+
+// gids that are paused when window of their category was closed.
+// they are resumed when window is opened again.
+type PausedByWindow = HashMap<String, Vec<String>>;
+
+fn schedulerEvent(gid: Option<String>, category: Option<String>, event: &'static str) {
+    pushEvent(DownloadEvent {
+        gid,
+        category,
+        event,
+        synthetic: false,
+    });
+}
+
+// put a scheduled gid in download pool.
+fn startScheduled(database: &DataBase, gid: &str) -> Result<(), GhermezError> {
+    if database.searchGidInAddLinkTable(gid)?.is_none() {
+        return Err(GhermezError::Database(format!(
+            "{gid} is not in addlink_db_table"
+        )));
+    }
+    // download can't be started without its folder and aria2 settings
+    let Some(settings) = database.takeScheduleSettings(gid)? else {
+        database.updateDownloadTable(vec![HashMap::from([("gid", gid), ("status", "error")])])?;
+        return Err(GhermezError::Database(format!(
+            "settings of {gid} are not saved"
+        )));
+    };
+    // start_time is done, so download is not scheduled anymore.
+    database.setDefaultGidInAddlinkTable(gid, true, false, false)?;
+
+    // pool writes status of download and sends it to aria2 when there is a free slot.
+    submit(database, gid.to_string(), settings, 0)?;
+    info!("{gid} is sent to download pool");
+    Ok(())
+}
+
+async fn run(
+    database: &DataBase,
+    paused: &mut PausedByWindow,
+    action: Action,
+) -> Result<(), GhermezError> {
+    match action {
+        Action::Start(gid) => {
+            startScheduled(database, &gid)?;
+            schedulerEvent(Some(gid), None, "scheduled_start");
+        }
+        Action::Stop(gid) => {
+            info!("Time is up! {gid}");
+            stopDownload(database, &gid).await?;
+            schedulerEvent(Some(gid), None, "scheduled_end");
+        }
+        Action::Open(category) => {
+            info!("Time window of {category} is opened");
+            for gid in paused.remove(&category).unwrap_or_default() {
+                let gid = gid.as_str();
+                if let Err(e) = asyncRpc(|client| async move { client.unpause(gid).await }).await {
+                    error!("Couldn't unpause {gid}: {e}");
+                }
+            }
+            schedulerEvent(None, Some(category), "window_open");
+        }
+        Action::Close(category) => {
+            info!("Time window of {category} is closed");
+            let mut paused_gids = vec![];
+            for gid in database.downloadingItemsOfCategory(&category)? {
+                let gid_ref = gid.as_str();
+                match asyncRpc(|client| async move { client.pause(gid_ref).await }).await {
+                    Ok(()) => paused_gids.push(gid),
+                    Err(e) => error!("Couldn't pause {gid}: {e}"),
+                }
+            }
+            paused.insert(category.clone(), paused_gids);
+            schedulerEvent(None, Some(category), "window_close");
+        }
+    }
+    Ok(())
+}
+
+async fn schedule<C: Clock>(database: DataBase, mut scheduler: Scheduler<C>) {
+    let mut paused = PausedByWindow::new();
+    let mut interval = tokio::time::interval(TICK);
+    loop {
+        interval.tick().await;
+        let actions = match scheduler.tick(&database) {
+            Ok(actions) => actions,
+            Err(e) => {
+                error!("Scheduler couldn't read data base: {e}");
+                continue;
+            }
+        };
+        for action in actions {
+            let description = format!("{action:?}");
+            if let Err(e) = run(&database, &mut paused, action).await {
+                error!("Scheduler couldn't do {description}: {e}");
+            }
+        }
+    }
+}
+
+// startScheduler starts the scheduler on the shared runtime.
+// scheduler starts and stops downloads at start_time and end_time of addlink_db_table,
+// and pauses and resumes downloads of a category at start time and end time of its window.
+// it's started once in a session and next calls do nothing.
+#[pyfunction]
+pub fn startScheduler(database: PyRef<DataBase>) {
+    static STARTED: Once = Once::new();
+    STARTED.call_once(|| {
+        RUNTIME.spawn(schedule(
+            DataBase::clone(&database),
+            Scheduler::new(LocalClock),
+        ));
+        info!("Scheduler is started");
+    });
+}
+
+// scheduleDownload saves settings of gid in data base until its start_time arrives.
+// then scheduler sends download request to aria2 with these settings,
+// even if persepolis is restarted meanwhile. settings are same as startDownload settings.
+#[pyfunction]
+pub fn scheduleDownload(
+    database: PyRef<DataBase>,
+    gid: &str,
+    settings: HashMap<String, String>,
+) -> Result<(), GhermezError> {
+    database.saveScheduleSettings(gid, &settings)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{
+        atomic::{AtomicU32, Ordering},
+        Arc,
+    };
+
+    use super::*;
+
+    struct FakeClock(Arc<AtomicU32>);
+
+    // fake clock is in this day
+    fn midnight() -> i64 {
+        Local
+            .with_ymd_and_hms(2024, 1, 10, 0, 0, 0)
+            .unwrap()
+            .timestamp()
+    }
+
+    impl Clock for FakeClock {
+        fn now(&self) -> u32 {
+            self.0.load(Ordering::SeqCst)
+        }
+
+        fn timestamp(&self) -> i64 {
+            midnight() + self.now() as i64 * 60
+        }
+    }
+
+    #[test]
+    fn sigma_time() {
+        assert_eq!(sigmaTime("9:05"), Some(545));
+        assert_eq!(sigmaTime("0:0"), Some(0));
+        assert_eq!(sigmaTime("24:00"), None);
+        assert_eq!(sigmaTime("None"), None);
+    }
+
+    #[test]
+    fn windows_cross_midnight() {
+        let (start, end) = (23 * 60, 60);
+        assert!(inWindow(start, end, 23 * 60 + 30));
+        assert!(inWindow(start, end, 30));
+        assert!(!inWindow(start, end, 120));
+        assert!(!inWindow(60, 120, 30));
+
+        assert!(passed(0, 23 * 60 + 59, 0));
+        assert!(passed(30, 23 * 60 + 50, 40));
+        assert!(!passed(23 * 60, 23 * 60 + 50, 40));
+        assert!(!passed(30, 30, 30));
+    }
+
+    #[test]
+    fn scheduler_starts_and_stops_downloads() {
+        let database = DataBase::open(":memory:").unwrap();
+        database.createTables().unwrap();
+        database
+            .insertInCategoryTable(HashMap::from([
+                ("category", "Night"),
+                ("start_time_enable", "yes"),
+                ("start_time", "23:00"),
+                ("end_time_enable", "yes"),
+                ("end_time", "1:00"),
+                ("reverse", "no"),
+                ("limit_enable", "no"),
+                ("limit_value", "OK"),
+                ("after_download", "no"),
+            ]))
+            .unwrap();
+        database
+            .insertInDownloadTable(vec![
+                HashMap::from([("gid", "a"), ("status", "scheduled"), ("category", "Night")]),
+                HashMap::from([
+                    ("gid", "b"),
+                    ("status", "downloading"),
+                    ("category", "Night"),
+                ]),
+            ])
+            .unwrap();
+        database
+            .insertInAddLinkTable(vec![
+                HashMap::from([("gid", "a"), ("start_time", "23:00")]),
+                HashMap::from([("gid", "b"), ("end_time", "0:30")]),
+            ])
+            .unwrap();
+
+        let now = Arc::new(AtomicU32::new(22 * 60 + 59));
+        let mut scheduler = Scheduler::new(FakeClock(now.clone()));
+        let mut tickAt = |time: &str| {
+            now.store(sigmaTime(time).unwrap(), Ordering::SeqCst);
+            scheduler.tick(&database).unwrap()
+        };
+
+        assert_eq!(tickAt("22:59"), vec![]);
+        assert_eq!(
+            tickAt("23:00"),
+            vec![
+                Action::Start("a".to_string()),
+                Action::Open("Night".to_string())
+            ]
+        );
+        assert_eq!(tickAt("23:59"), vec![]);
+        assert_eq!(tickAt("0:30"), vec![Action::Stop("b".to_string())]);
+        assert_eq!(tickAt("1:00"), vec![Action::Close("Night".to_string())]);
+    }
+
+    #[test]
+    fn settings_of_scheduled_downloads_are_saved() {
+        let database = DataBase::open(":memory:").unwrap();
+        database.createTables().unwrap();
+        database
+            .insertInDownloadTable(vec![
+                HashMap::from([
+                    ("gid", "a"),
+                    ("status", "scheduled"),
+                    ("category", "Scheduled Downloads"),
+                ]),
+                HashMap::from([
+                    ("gid", "b"),
+                    ("status", "scheduled"),
+                    ("category", "Scheduled Downloads"),
+                ]),
+            ])
+            .unwrap();
+        database
+            .insertInAddLinkTable(vec![
+                HashMap::from([("gid", "a"), ("start_time", "23:00")]),
+                HashMap::from([("gid", "b"), ("start_time", "23:00")]),
+            ])
+            .unwrap();
+
+        let settings = HashMap::from([("download_path_temp".to_string(), "/tmp".to_string())]);
+        database.saveScheduleSettings("a", &settings).unwrap();
+        // settings are taken once, when download is started
+        assert_eq!(database.takeScheduleSettings("a").unwrap(), Some(settings));
+        assert_eq!(database.takeScheduleSettings("a").unwrap(), None);
+
+        // download isn't started with default settings
+        assert!(startScheduled(&database, "b").is_err());
+        assert_eq!(
+            database.statusesOfCategory("Scheduled Downloads").unwrap()["b"],
+            "error"
+        );
+    }
+
+    #[test]
+    fn first_tick_opens_current_window() {
+        let windows = [CategoryWindow {
+            category: "Night".to_string(),
+            start_time: Some("23:00".to_string()),
+            end_time: Some("1:00".to_string()),
+        }];
+        assert_eq!(
+            plan(None, 30, 0, &[], &windows),
+            vec![Action::Open("Night".to_string())]
+        );
+        assert_eq!(plan(None, 120, 0, &[], &windows), vec![]);
+    }
+
+    #[test]
+    fn start_time_passed_while_closed() {
+        let database = DataBase::open(":memory:").unwrap();
+        database.createTables().unwrap();
+        let yesterday = (midnight() - 15 * 3600).to_string();
+        let today = (midnight() + 10 * 3600 + 20 * 60).to_string();
+        let download = |gid, added| {
+            HashMap::from([
+                ("gid", gid),
+                ("status", "scheduled"),
+                ("category", "Scheduled Downloads"),
+                ("first_try_date", added),
+            ])
+        };
+        database
+            .insertInDownloadTable(vec![
+                download("a", &yesterday),
+                download("b", &yesterday),
+                download("c", &today),
+                download("d", &yesterday),
+            ])
+            .unwrap();
+        database
+            .insertInAddLinkTable(vec![
+                HashMap::from([("gid", "a"), ("start_time", "10:00"), ("end_time", "12:00")]),
+                HashMap::from([("gid", "b"), ("start_time", "10:00")]),
+                // added after 10:00 today, so it starts at 10:00 tomorrow
+                HashMap::from([("gid", "c"), ("start_time", "10:00")]),
+                // its window is closed already
+                HashMap::from([("gid", "d"), ("start_time", "8:00"), ("end_time", "9:00")]),
+            ])
+            .unwrap();
+
+        // persepolis is started at 10:30
+        let now = Arc::new(AtomicU32::new(sigmaTime("10:30").unwrap()));
+        let mut scheduler = Scheduler::new(FakeClock(now.clone()));
+        assert_eq!(
+            scheduler.tick(&database).unwrap(),
+            vec![
+                Action::Start("a".to_string()),
+                Action::Start("b".to_string())
+            ]
+        );
+        assert_eq!(scheduler.tick(&database).unwrap(), vec![]);
+    }
+}
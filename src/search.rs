@@ -0,0 +1,594 @@
+#![allow(non_snake_case)]
+
+use pyo3::{
+    exceptions::PyValueError,
+    prelude::*,
+    types::{IntoPyDict, PyDict},
+};
+use rusqlite::{params_from_iter, types::Value, Connection};
+
+use crate::{
+    error::GhermezError,
+    useful_tools::{dateToTimestamp, sizeInBytes},
+};
+
+// columns of download_db_table that downloads can be sorted by
+const SORT_COLUMNS: [&str; 8] = [
+    "file_name",
+    "status",
+    "size",
+    "downloaded_size",
+    "rate",
+    "first_try_date",
+    "last_try_date",
+    "category",
+];
+
+// filters, sort order and page of searchDownloads. python side sends a dictionary
+// with the same keys, every key is optional and filters that are set must all match.
+// dates are unix time or "2017/09/09 , 13:12:26", sizes are byte or like "1.5 GiB".
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DownloadQuery {
+    // part of file name, case insensitive for ASCII letters
+    pub name: Option<String>,
+    // FTS5 query on file name and link, for example "ubuntu AND iso" or "linu*"
+    pub text: Option<String>,
+    // download has one of these statuses, empty list is every status
+    pub statuses: Vec<String>,
+    pub category: Option<String>,
+    // host of link, subdomains of host match too
+    pub host: Option<String>,
+    // date ranges, ends are included
+    pub first_try_from: Option<i64>,
+    pub first_try_to: Option<i64>,
+    pub last_try_from: Option<i64>,
+    pub last_try_to: Option<i64>,
+    // size range in byte, ends are included
+    pub min_size: Option<i64>,
+    pub max_size: Option<i64>,
+    // one of SORT_COLUMNS. downloads that have no value for it are the last ones.
+    pub sort: &'static str,
+    pub descending: bool,
+    pub offset: i64,
+    // None returns all downloads after offset
+    pub limit: Option<i64>,
+}
+
+// newest downloads are the first ones by default
+impl Default for DownloadQuery {
+    fn default() -> Self {
+        Self {
+            name: None,
+            text: None,
+            statuses: vec![],
+            category: None,
+            host: None,
+            first_try_from: None,
+            first_try_to: None,
+            last_try_from: None,
+            last_try_to: None,
+            min_size: None,
+            max_size: None,
+            sort: "first_try_date",
+            descending: true,
+            offset: 0,
+            limit: Some(100),
+        }
+    }
+}
+
+impl<'source> FromPyObject<'source> for DownloadQuery {
+    fn extract(ob: &'source PyAny) -> PyResult<Self> {
+        let dict: &PyDict = ob.downcast()?;
+        let mut query = Self::default();
+
+        for (key, value) in dict.iter() {
+            let key: &str = key.extract()?;
+
+            // limit is the only key that None means something for
+            if key == "limit" {
+                query.limit = value.extract()?;
+                continue;
+            }
+            if value.is_none() {
+                continue;
+            }
+
+            match key {
+                "name" => query.name = Some(value.extract()?),
+                "text" => query.text = Some(value.extract()?),
+                "statuses" => query.statuses = value.extract()?,
+                "category" => query.category = Some(value.extract()?),
+                "host" => query.host = Some(value.extract::<String>()?.to_lowercase()),
+                "first_try_from" => query.first_try_from = Some(extractDate(key, value)?),
+                "first_try_to" => query.first_try_to = Some(extractDate(key, value)?),
+                "last_try_from" => query.last_try_from = Some(extractDate(key, value)?),
+                "last_try_to" => query.last_try_to = Some(extractDate(key, value)?),
+                "min_size" => query.min_size = Some(extractSize(key, value)?),
+                "max_size" => query.max_size = Some(extractSize(key, value)?),
+                "sort" => {
+                    let sort: &str = value.extract()?;
+                    query.sort = SORT_COLUMNS
+                        .into_iter()
+                        .find(|column| *column == sort)
+                        .ok_or_else(|| {
+                            PyValueError::new_err(format!("downloads can't be sorted by {sort:?}"))
+                        })?;
+                }
+                "descending" => query.descending = value.extract()?,
+                "offset" => query.offset = value.extract()?,
+                _ => {
+                    return Err(PyValueError::new_err(format!(
+                        "unknown search filter: {key}"
+                    )))
+                }
+            }
+        }
+
+        Ok(query)
+    }
+}
+
+// unix time or date like "2017/09/09 , 13:12:26" in local time
+fn extractDate(key: &str, value: &PyAny) -> PyResult<i64> {
+    if let Ok(timestamp) = value.extract() {
+        return Ok(timestamp);
+    }
+    let date: &str = value.extract()?;
+    dateToTimestamp(date)
+        .ok_or_else(|| PyValueError::new_err(format!("{key} must be a date, not {date:?}")))
+}
+
+// byte or human readable size like "1.5 GiB"
+fn extractSize(key: &str, value: &PyAny) -> PyResult<i64> {
+    if let Ok(size) = value.extract() {
+        return Ok(size);
+    }
+    let size: &str = value.extract()?;
+    sizeInBytes(size)
+        .ok_or_else(|| PyValueError::new_err(format!("{key} must be a size, not {size:?}")))
+}
+
+// a download that is found by searchDownloads.
+// sizes are in byte, rate in byte per second and dates in unix time.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DownloadRow {
+    pub gid: String,
+    pub file_name: Option<String>,
+    pub status: Option<String>,
+    pub size: Option<i64>,
+    pub downloaded_size: Option<i64>,
+    // from 0 to 100
+    pub percent: Option<i64>,
+    pub rate: Option<i64>,
+    pub link: Option<String>,
+    pub first_try_date: Option<i64>,
+    pub last_try_date: Option<i64>,
+    pub category: Option<String>,
+}
+
+impl IntoPy<PyObject> for DownloadRow {
+    fn into_py(self, py: Python<'_>) -> PyObject {
+        [
+            ("gid", self.gid.into_py(py)),
+            ("file_name", self.file_name.into_py(py)),
+            ("status", self.status.into_py(py)),
+            ("size", self.size.into_py(py)),
+            ("downloaded_size", self.downloaded_size.into_py(py)),
+            ("percent", self.percent.into_py(py)),
+            ("rate", self.rate.into_py(py)),
+            ("link", self.link.into_py(py)),
+            ("first_try_date", self.first_try_date.into_py(py)),
+            ("last_try_date", self.last_try_date.into_py(py)),
+            ("category", self.category.into_py(py)),
+        ]
+        .into_py_dict(py)
+        .into()
+    }
+}
+
+// WHERE clause of query and values of its parameters
+fn conditions(query: &DownloadQuery) -> (String, Vec<Value>) {
+    let mut conditions = vec![];
+    let mut params = vec![];
+    let text = |x: &str| Value::Text(x.to_string());
+
+    if let Some(name) = &query.name {
+        conditions.push("instr(lower(file_name), lower(?)) > 0".to_string());
+        params.push(text(name));
+    }
+    if let Some(fts_query) = &query.text {
+        conditions.push(
+            "rowid IN (SELECT rowid FROM download_fts WHERE download_fts MATCH ?)".to_string(),
+        );
+        params.push(text(fts_query));
+    }
+    if !query.statuses.is_empty() {
+        let marks = vec!["?"; query.statuses.len()].join(", ");
+        conditions.push(format!("status IN ({marks})"));
+        params.extend(query.statuses.iter().map(|status| text(status)));
+    }
+    if let Some(category) = &query.category {
+        conditions.push("category = ?".to_string());
+        params.push(text(category));
+    }
+    if let Some(host) = &query.host {
+        conditions.push("(host_of(link) = ? OR substr(host_of(link), -length(?)) = ?)".to_string());
+        let subdomain = format!(".{host}");
+        params.extend([text(host), text(&subdomain), text(&subdomain)]);
+    }
+    let ranges = [
+        ("first_try_date >= ?", query.first_try_from),
+        ("first_try_date <= ?", query.first_try_to),
+        ("last_try_date >= ?", query.last_try_from),
+        ("last_try_date <= ?", query.last_try_to),
+        ("size >= ?", query.min_size),
+        ("size <= ?", query.max_size),
+    ];
+    for (condition, value) in ranges {
+        if let Some(value) = value {
+            conditions.push(condition.to_string());
+            params.push(Value::Integer(value));
+        }
+    }
+
+    if conditions.is_empty() {
+        conditions.push("1".to_string());
+    }
+    (conditions.join(" AND "), params)
+}
+
+// find downloads of query in download_db_table.
+// an invalid FTS5 query in text returns an error.
+pub(crate) fn queryDownloads(
+    connection: &Connection,
+    query: &DownloadQuery,
+) -> Result<Vec<DownloadRow>, GhermezError> {
+    let (conditions, mut params) = conditions(query);
+
+    // sort column is one of SORT_COLUMNS, so it can be a part of SQL
+    let order = if query.descending { "DESC" } else { "ASC" };
+    let collate = if query.sort == "file_name" {
+        " COLLATE NOCASE"
+    } else {
+        ""
+    };
+    let sort = query.sort;
+    let sql = format!(
+        "
+        SELECT gid, file_name, status, size, downloaded_size, percent, rate,
+        link, first_try_date, last_try_date, category
+        FROM download_db_table WHERE {conditions}
+        ORDER BY {sort} IS NULL, {sort}{collate} {order}, rowid {order}
+        LIMIT ? OFFSET ?
+        "
+    );
+    // negative limit is no limit in sqlite
+    params.push(Value::Integer(query.limit.unwrap_or(-1)));
+    params.push(Value::Integer(query.offset.max(0)));
+
+    let mut stmt = connection.prepare_cached(&sql)?;
+    let rows = stmt
+        .query_map(params_from_iter(params), |row| {
+            Ok(DownloadRow {
+                gid: row.get(0)?,
+                file_name: row.get(1)?,
+                status: row.get(2)?,
+                size: row.get(3)?,
+                downloaded_size: row.get(4)?,
+                percent: row.get(5)?,
+                rate: row.get(6)?,
+                link: row.get(7)?,
+                first_try_date: row.get(8)?,
+                last_try_date: row.get(9)?,
+                category: row.get(10)?,
+            })
+        })?
+        .collect::<Result<_, _>>()?;
+    Ok(rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::database::DataBase;
+
+    // file name, status, size, link, first try date and category of test downloads
+    const DOWNLOADS: [(&str, &str, &str, &str, &str, &str); 5] = [
+        (
+            "ubuntu-22.04.iso",
+            "complete",
+            "3.5 GiB",
+            "https://releases.ubuntu.com/ubuntu-22.04.iso",
+            "2023/01/10 , 10:00:00",
+            "Single Downloads",
+        ),
+        (
+            "debian.iso",
+            "error",
+            "600 MiB",
+            "http://dl.example.com/debian.iso",
+            "2023/02/10 , 10:00:00",
+            "Single Downloads",
+        ),
+        (
+            "Music's best.mp3",
+            "stopped",
+            "8 MiB",
+            "http://example.com:8080/music.mp3",
+            "2023/03/10 , 10:00:00",
+            "Scheduled Downloads",
+        ),
+        (
+            "linux-6.1.tar.xz",
+            "complete",
+            "***",
+            "http://notexample.com/linux-6.1.tar.xz",
+            "2023/04/10 , 10:00:00",
+            "Single Downloads",
+        ),
+        (
+            "فیلم.mkv",
+            "downloading",
+            "1.2 GiB",
+            "magnet:?xt=urn:btih:abc",
+            "2023/05/10 , 10:00:00",
+            "Scheduled Downloads",
+        ),
+    ];
+
+    fn testDataBase() -> DataBase {
+        let database = DataBase::open(":memory:").unwrap();
+        database.createTables().unwrap();
+        // data base saves numbers, like aria2 answers them
+        let numbers: Vec<_> = DOWNLOADS
+            .iter()
+            .map(|(_, _, size, _, date, _)| {
+                (
+                    sizeInBytes(size).map_or("***".to_string(), |size| size.to_string()),
+                    dateToTimestamp(date).unwrap().to_string(),
+                )
+            })
+            .collect();
+        let list = DOWNLOADS
+            .iter()
+            .zip(&numbers)
+            .enumerate()
+            .map(
+                |(i, ((file_name, status, _, link, _, category), (size, date)))| {
+                    HashMap::from([
+                        ("file_name", *file_name),
+                        ("status", *status),
+                        ("size", size.as_str()),
+                        ("gid", ["g0", "g1", "g2", "g3", "g4"][i]),
+                        ("link", *link),
+                        ("first_try_date", date.as_str()),
+                        ("last_try_date", date.as_str()),
+                        ("category", *category),
+                    ])
+                },
+            )
+            .collect();
+        database.insertInDownloadTable(list).unwrap();
+        database
+    }
+
+    fn gids(database: &DataBase, query: DownloadQuery) -> Vec<String> {
+        database
+            .searchDownloads(query)
+            .unwrap()
+            .into_iter()
+            .map(|row| row.gid)
+            .collect()
+    }
+
+    #[test]
+    fn downloads_are_filtered() {
+        let database = testDataBase();
+        let search = |query| gids(&database, query);
+
+        // newest downloads are the first ones
+        assert_eq!(
+            search(DownloadQuery::default()),
+            ["g4", "g3", "g2", "g1", "g0"]
+        );
+        assert_eq!(
+            search(DownloadQuery {
+                name: Some("UBUNTU".to_string()),
+                ..Default::default()
+            }),
+            ["g0"]
+        );
+        assert_eq!(
+            search(DownloadQuery {
+                name: Some("'s".to_string()),
+                ..Default::default()
+            }),
+            ["g2"]
+        );
+        assert_eq!(
+            search(DownloadQuery {
+                text: Some("debian OR linu*".to_string()),
+                ..Default::default()
+            }),
+            ["g3", "g1"]
+        );
+        assert_eq!(
+            search(DownloadQuery {
+                statuses: vec!["complete".to_string(), "error".to_string()],
+                category: Some("Single Downloads".to_string()),
+                ..Default::default()
+            }),
+            ["g3", "g1", "g0"]
+        );
+        assert_eq!(
+            search(DownloadQuery {
+                host: Some("example.com".to_string()),
+                ..Default::default()
+            }),
+            ["g2", "g1"]
+        );
+        assert_eq!(
+            search(DownloadQuery {
+                first_try_from: dateToTimestamp("2023/02/10 , 10:00:00"),
+                last_try_to: dateToTimestamp("2023/04/01 , 00:00:00"),
+                ..Default::default()
+            }),
+            ["g2", "g1"]
+        );
+        assert_eq!(
+            search(DownloadQuery {
+                min_size: sizeInBytes("100 MiB"),
+                max_size: sizeInBytes("2 GiB"),
+                ..Default::default()
+            }),
+            ["g4", "g1"]
+        );
+
+        let rows = database
+            .searchDownloads(DownloadQuery {
+                category: Some("Scheduled Downloads".to_string()),
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(rows[1].file_name.as_deref(), Some("Music's best.mp3"));
+        assert_eq!(rows[1].size, Some(8 * 1024 * 1024));
+        assert_eq!(
+            rows[1].first_try_date,
+            dateToTimestamp("2023/03/10 , 10:00:00")
+        );
+    }
+
+    #[test]
+    fn downloads_are_sorted_and_paged() {
+        let database = testDataBase();
+        let search = |query| gids(&database, query);
+
+        // downloads without size are the last ones
+        let by_size = DownloadQuery {
+            sort: "size",
+            descending: false,
+            ..Default::default()
+        };
+        assert_eq!(search(by_size.clone()), ["g2", "g1", "g4", "g0", "g3"]);
+        assert_eq!(
+            search(DownloadQuery {
+                offset: 1,
+                limit: Some(2),
+                ..by_size.clone()
+            }),
+            ["g1", "g4"]
+        );
+        assert!(search(DownloadQuery {
+            offset: 5,
+            ..by_size
+        })
+        .is_empty());
+        assert_eq!(
+            search(DownloadQuery {
+                sort: "file_name",
+                descending: false,
+                limit: None,
+                ..Default::default()
+            }),
+            ["g1", "g3", "g2", "g0", "g4"]
+        );
+    }
+
+    #[test]
+    fn full_text_index_follows_downloads() {
+        let database = testDataBase();
+        let text = |fts_query: &str| {
+            gids(
+                &database,
+                DownloadQuery {
+                    text: Some(fts_query.to_string()),
+                    ..Default::default()
+                },
+            )
+        };
+        database
+            .updateDownloadTable(vec![HashMap::from([
+                ("gid", "g0"),
+                ("file_name", "kubuntu.iso"),
+            ])])
+            .unwrap();
+        assert_eq!(text("kubuntu"), ["g0"]);
+        // words of link are indexed too
+        assert_eq!(text("releases"), ["g0"]);
+
+        database.deleteItemInDownloadTable("g0", "").unwrap();
+        assert!(text("kubuntu").is_empty());
+        database.resetDataBase().unwrap();
+        assert!(text("debian").is_empty());
+
+        // invalid FTS5 query
+        assert!(database
+            .searchDownloads(DownloadQuery {
+                text: Some("AND (".to_string()),
+                ..Default::default()
+            })
+            .is_err());
+    }
+
+    #[test]
+    fn extract_query_dict() {
+        pyo3::prepare_freethreaded_python();
+        Python::with_gil(|py| {
+            let dict = PyDict::new(py);
+            dict.set_item("name", "iso").unwrap();
+            dict.set_item("statuses", vec!["complete"]).unwrap();
+            dict.set_item("host", "Example.COM").unwrap();
+            dict.set_item("first_try_from", "2023/01/01 , 00:00:00")
+                .unwrap();
+            dict.set_item("first_try_to", 1700000000).unwrap();
+            dict.set_item("min_size", "1 KiB").unwrap();
+            dict.set_item("max_size", 4096).unwrap();
+            dict.set_item("category", py.None()).unwrap();
+            dict.set_item("sort", "size").unwrap();
+            dict.set_item("descending", false).unwrap();
+            dict.set_item("offset", 10).unwrap();
+            dict.set_item("limit", py.None()).unwrap();
+
+            let query: DownloadQuery = dict.extract().unwrap();
+            assert_eq!(
+                query,
+                DownloadQuery {
+                    name: Some("iso".to_string()),
+                    statuses: vec!["complete".to_string()],
+                    host: Some("example.com".to_string()),
+                    first_try_from: dateToTimestamp("2023/01/01 , 00:00:00"),
+                    first_try_to: Some(1700000000),
+                    min_size: Some(1024),
+                    max_size: Some(4096),
+                    sort: "size",
+                    descending: false,
+                    offset: 10,
+                    limit: None,
+                    ..Default::default()
+                }
+            );
+
+            for (key, value) in [("sort", "link"), ("min_size", "big"), ("color", "red")] {
+                let dict = PyDict::new(py);
+                dict.set_item(key, value).unwrap();
+                assert!(dict.extract::<DownloadQuery>().is_err());
+            }
+
+            let row = DownloadRow {
+                gid: "g0".to_string(),
+                size: Some(1024),
+                ..Default::default()
+            }
+            .into_py(py);
+            let row: &PyDict = row.downcast(py).unwrap();
+            assert_eq!(
+                row.get_item("size").unwrap().extract::<i64>().unwrap(),
+                1024
+            );
+            assert!(row.get_item("link").unwrap().is_none());
+        });
+    }
+}
@@ -0,0 +1,649 @@
+#![allow(non_snake_case)]
+// pyo3 0.19 macros trigger this lint on newer compilers
+#![allow(non_local_definitions)]
+
+use std::{
+    collections::{HashMap, HashSet},
+    sync::{mpsc, Arc},
+    time,
+};
+
+use log::{error, info};
+use percent_encoding::percent_decode_str;
+use pyo3::{prelude::*, types::IntoPyDict};
+use reqwest::{
+    header::{self, HeaderMap},
+    Client, Proxy, RequestBuilder, Response, StatusCode,
+};
+
+use tokio::{sync::Semaphore, task::JoinSet};
+
+use crate::{
+    aria2c::RUNTIME,
+    cookies::{cookieHeader, cookiesFor, loadCookies, unixTime},
+    database::DataBase,
+    error::GhermezError,
+    options::headerPairs,
+    pool::hostOf,
+    useful_tools::humanReadableSize,
+};
+
+// spider gives up if server doesn't answer in this time.
+const SPIDER_TIMEOUT: time::Duration = time::Duration::from_secs(15);
+
+// what spider found out about a link
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SpiderResult {
+    // link after following redirects
+    pub link: String,
+    pub status: u16,
+    // "out" of add_link_dict, file name from Content-Disposition or last part of link
+    pub file_name: Option<String>,
+    // file name from Content-Disposition
+    pub server_file_name: Option<String>,
+    pub size: Option<u64>,
+    // server accepts range requests, so download can be resumed
+    pub resumable: bool,
+    pub content_type: Option<String>,
+}
+
+impl IntoPy<PyObject> for SpiderResult {
+    fn into_py(self, py: Python<'_>) -> PyObject {
+        let file_size = self
+            .size
+            .map(|size| humanReadableSize(size as f32, "file_size"));
+        [
+            ("link", self.link.into_py(py)),
+            ("status", self.status.into_py(py)),
+            ("file_name", self.file_name.into_py(py)),
+            ("server_file_name", self.server_file_name.into_py(py)),
+            ("file_size", file_size.into_py(py)),
+            ("size", self.size.into_py(py)),
+            ("resumable", self.resumable.into_py(py)),
+            ("content_type", self.content_type.into_py(py)),
+        ]
+        .into_py_dict(py)
+        .into()
+    }
+}
+
+// split parameters of Content-Disposition like `attachment; filename="a;b.txt"`.
+// names are lowercase and quoted values are unquoted.
+fn dispositionParams(value: &str) -> Vec<(String, String)> {
+    let mut params = vec![];
+    let mut chars = value.chars().peekable();
+
+    // skip disposition type
+    for c in chars.by_ref() {
+        if c == ';' {
+            break;
+        }
+    }
+
+    loop {
+        let mut name = String::new();
+        for c in chars.by_ref() {
+            if c == '=' || c == ';' {
+                break;
+            }
+            name.push(c);
+        }
+        let name = name.trim().to_lowercase();
+
+        while chars.next_if(|c| c.is_whitespace()).is_some() {}
+        let mut param = String::new();
+        if chars.next_if_eq(&'"').is_some() {
+            while let Some(c) = chars.next() {
+                match c {
+                    '\\' => param.extend(chars.next()),
+                    '"' => break,
+                    c => param.push(c),
+                }
+            }
+            // skip anything after closing quote
+            for c in chars.by_ref() {
+                if c == ';' {
+                    break;
+                }
+            }
+        } else {
+            for c in chars.by_ref() {
+                if c == ';' {
+                    break;
+                }
+                param.push(c);
+            }
+        }
+
+        if !name.is_empty() {
+            params.push((name, param.trim().to_string()));
+        }
+        if chars.peek().is_none() {
+            return params;
+        }
+    }
+}
+
+// decode RFC 5987 value like `UTF-8''na%C3%AFve.txt`
+fn decodeExtValue(value: &str) -> Option<String> {
+    let mut parts = value.splitn(3, '\'');
+    let charset = parts.next()?;
+    let _language = parts.next()?;
+    let encoded = parts.next()?;
+
+    let bytes: Vec<u8> = percent_decode_str(encoded).collect();
+    if charset.eq_ignore_ascii_case("utf-8") {
+        String::from_utf8(bytes).ok()
+    } else if charset.eq_ignore_ascii_case("iso-8859-1") {
+        Some(bytes.into_iter().map(char::from).collect())
+    } else {
+        None
+    }
+}
+
+// file name must not contain a path
+fn cleanFileName(file_name: &str) -> Option<String> {
+    let file_name = file_name.rsplit(['/', '\\']).next()?.trim();
+    (!file_name.is_empty() && file_name != "." && file_name != "..").then(|| file_name.to_string())
+}
+
+// find file name in Content-Disposition. filename* is preferred to filename.
+fn dispositionFileName(value: &str) -> Option<String> {
+    let params = dispositionParams(value);
+    let param = |name: &str| {
+        params
+            .iter()
+            .find(|(param_name, _)| param_name == name)
+            .map(|(_, value)| value.as_str())
+    };
+    param("filename*")
+        .and_then(decodeExtValue)
+        .or_else(|| param("filename").map(String::from))
+        .and_then(|file_name| cleanFileName(&file_name))
+}
+
+// last part of link path, for example "file.zip" for "http://a.com/dl/file%2Ezip?x=1"
+fn linkFileName(link: &str) -> Option<String> {
+    let path = link.split(['?', '#']).next()?;
+    let path = path.split_once("://").map_or(path, |(_, rest)| rest);
+    let (_, path) = path.split_once('/')?;
+    let name = path.rsplit('/').next()?;
+    cleanFileName(&percent_decode_str(name).decode_utf8_lossy())
+}
+
+// total size from Content-Range like "bytes 0-0/12345"
+fn contentRangeSize(value: &str) -> Option<u64> {
+    value.rsplit_once('/')?.1.trim().parse().ok()
+}
+
+fn headerValue(headers: &HeaderMap, name: header::HeaderName) -> Option<&str> {
+    headers.get(name)?.to_str().ok()
+}
+
+// make http client with proxy of add_link_dict
+fn spiderClient(get: &impl Fn(&str) -> Option<String>) -> Result<Client, GhermezError> {
+    let mut builder = Client::builder().timeout(SPIDER_TIMEOUT);
+    if let Some(ip) = get("ip") {
+        let proxy_url = match get("port") {
+            Some(port) => format!("http://{ip}:{port}"),
+            None => format!("http://{ip}"),
+        };
+        let mut proxy = Proxy::all(proxy_url)?;
+        if let Some(proxy_user) = get("proxy_user") {
+            proxy = proxy.basic_auth(&proxy_user, &get("proxy_passwd").unwrap_or_default());
+        }
+        builder = builder.proxy(proxy);
+    }
+    Ok(builder.build()?)
+}
+
+// add authentication, cookies, referer, user agent and custom headers to request
+fn prepareRequest(
+    mut request: RequestBuilder,
+    link: &str,
+    get: &impl Fn(&str) -> Option<String>,
+) -> RequestBuilder {
+    if let Some(download_user) = get("download_user") {
+        request = request.basic_auth(download_user, get("download_passwd"));
+    }
+    // only cookies of link are sent
+    let cookies = get("load_cookies")
+        .and_then(|load_cookies| loadCookies(&load_cookies).ok())
+        .and_then(|cookies| cookieHeader(&cookiesFor(&cookies, link, unixTime())));
+    if let Some(cookies) = cookies {
+        request = request.header(header::COOKIE, cookies);
+    }
+    if let Some(referer) = get("referer") {
+        request = request.header(header::REFERER, referer);
+    }
+    if let Some(user_agent) = get("user_agent") {
+        request = request.header(header::USER_AGENT, user_agent);
+    }
+    if let Some(header) = get("header") {
+        for (name, value) in headerPairs(&header) {
+            request = request.header(name, value.trim());
+        }
+    }
+    request
+}
+
+// read download information from headers of response
+fn spiderResult(response: &Response) -> SpiderResult {
+    let headers = response.headers();
+    let content_range = headerValue(headers, header::CONTENT_RANGE);
+
+    // answer of range request has total size in Content-Range
+    let size = match content_range {
+        Some(content_range) if response.status() == StatusCode::PARTIAL_CONTENT => {
+            contentRangeSize(content_range)
+        }
+        _ => headerValue(headers, header::CONTENT_LENGTH).and_then(|x| x.parse().ok()),
+    };
+    let resumable = match headerValue(headers, header::ACCEPT_RANGES) {
+        Some(accept_ranges) => accept_ranges.trim().eq_ignore_ascii_case("bytes"),
+        None => response.status() == StatusCode::PARTIAL_CONTENT,
+    };
+    let content_type = headerValue(headers, header::CONTENT_TYPE)
+        .and_then(|x| x.split(';').next())
+        .map(|x| x.trim().to_lowercase());
+    let link = response.url().to_string();
+    let server_file_name =
+        headerValue(headers, header::CONTENT_DISPOSITION).and_then(dispositionFileName);
+
+    SpiderResult {
+        file_name: server_file_name.clone().or_else(|| linkFileName(&link)),
+        server_file_name,
+        link,
+        status: response.status().as_u16(),
+        size,
+        resumable,
+        content_type,
+    }
+}
+
+// send HEAD request and follow redirects. some servers refuse HEAD,
+// then a GET request for the first byte is sent and its body is not read.
+pub(crate) async fn asyncSpider(
+    add_link_dict: &HashMap<String, String>,
+) -> Result<SpiderResult, GhermezError> {
+    // python side stores 'None' for empty values
+    let get = |key: &str| {
+        add_link_dict
+            .get(key)
+            .filter(|value| !["NULL", "None", ""].contains(&value.as_str()))
+            .cloned()
+    };
+    let link = get("link").ok_or_else(|| GhermezError::Http("link is empty".to_string()))?;
+    let client = spiderClient(&get)?;
+
+    let head = prepareRequest(client.head(&link), &link, &get).send().await;
+    let response = match head {
+        Ok(response) if response.status().is_success() => response,
+        _ => {
+            prepareRequest(client.get(&link), &link, &get)
+                .header(header::RANGE, "bytes=0-0")
+                .send()
+                .await?
+        }
+    };
+
+    let mut result = spiderResult(&response);
+    if !response.status().is_success() {
+        result.size = None;
+        result.resumable = false;
+    }
+
+    // user chose file name in add link window
+    if let Some(out) = get("out") {
+        result.file_name = Some(out);
+    }
+    Ok(result)
+}
+
+// spider finds file name, size and type of a download like spider of wget.
+// add_link_dict is a row of addlink_db_table. proxy, authentication, cookies,
+// referer, user agent and header of it are used.
+// it returns a dictionary with link (after redirects), status, file_name,
+// server_file_name, file_size (human readable), size (bytes), resumable and content_type.
+// it is exported as ghermez.spider, rust name differs from name of its module.
+#[pyfunction]
+#[pyo3(name = "spider")]
+pub fn spiderLink(
+    py: Python<'_>,
+    add_link_dict: HashMap<String, String>,
+) -> Result<SpiderResult, GhermezError> {
+    py.allow_threads(|| RUNTIME.block_on(asyncSpider(&add_link_dict)))
+        .inspect(|result| info!("Spider: {} {:?}", result.link, result.file_name))
+        .inspect_err(|e| error!("Spider couldn't find download information: {e}"))
+}
+
+// one link of spiderMany
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SpiderItem {
+    // position of link in the list that is given to spiderMany
+    pub index: usize,
+    pub link: String,
+    // link is in addlink_db_table or it's repeated in the list, so it's not checked
+    pub duplicate: bool,
+    pub result: Option<SpiderResult>,
+    pub error: Option<String>,
+}
+
+impl IntoPy<PyObject> for SpiderItem {
+    fn into_py(self, py: Python<'_>) -> PyObject {
+        [
+            ("index", self.index.into_py(py)),
+            ("link", self.link.into_py(py)),
+            ("duplicate", self.duplicate.into_py(py)),
+            ("result", self.result.into_py(py)),
+            ("error", self.error.into_py(py)),
+        ]
+        .into_py_dict(py)
+        .into()
+    }
+}
+
+// find duplicate links. a link is duplicate if it's seen before in links
+// or exists returns true for it.
+fn duplicateLinks<E>(
+    links: &[String],
+    mut exists: impl FnMut(&str) -> Result<bool, E>,
+) -> Result<Vec<bool>, E> {
+    let mut seen = HashSet::new();
+    links
+        .iter()
+        .map(|link| Ok(!seen.insert(link.as_str()) || exists(link)?))
+        .collect()
+}
+
+// check links concurrently and send results to sender as soon as they are ready.
+// at most parallel links are checked at the same time and at most per_host of them
+// are on the same host. per_host = 0 means no limit for hosts.
+async fn spiderBatch(
+    add_link_dicts: Vec<HashMap<String, String>>,
+    duplicates: Vec<bool>,
+    parallel: usize,
+    per_host: usize,
+    sender: mpsc::Sender<SpiderItem>,
+) {
+    let slots = Arc::new(Semaphore::new(parallel.max(1)));
+    let mut hosts: HashMap<String, Arc<Semaphore>> = HashMap::new();
+    let mut tasks = JoinSet::new();
+    let mut duplicate_number = 0;
+
+    for (index, (add_link_dict, duplicate)) in
+        add_link_dicts.into_iter().zip(duplicates).enumerate()
+    {
+        let link = add_link_dict.get("link").cloned().unwrap_or_default();
+        if duplicate {
+            duplicate_number += 1;
+            let _ = sender.send(SpiderItem {
+                index,
+                link,
+                duplicate: true,
+                result: None,
+                error: None,
+            });
+            continue;
+        }
+
+        let host_slots = hostOf(&link).filter(|_| per_host != 0).map(|host| {
+            hosts
+                .entry(host)
+                .or_insert_with(|| Arc::new(Semaphore::new(per_host)))
+                .clone()
+        });
+        let slots = slots.clone();
+        let sender = sender.clone();
+        tasks.spawn(async move {
+            // wait for host first, so links of a busy host don't take all slots
+            let _host_permit = match host_slots {
+                Some(host_slots) => host_slots.acquire_owned().await.ok(),
+                None => None,
+            };
+            let _permit = slots.acquire_owned().await;
+
+            let (result, error) = match asyncSpider(&add_link_dict).await {
+                Ok(result) => (Some(result), None),
+                Err(e) => (None, Some(e.to_string())),
+            };
+            let failed = error.is_some();
+            let _ = sender.send(SpiderItem {
+                index,
+                link,
+                duplicate: false,
+                result,
+                error,
+            });
+            failed
+        });
+    }
+
+    let mut checked_number = 0;
+    let mut failed_number = 0;
+    while let Some(failed) = tasks.join_next().await {
+        checked_number += 1;
+        if failed.unwrap_or(true) {
+            failed_number += 1;
+        }
+    }
+    info!(
+        "Spider checked {checked_number} links, {failed_number} failed, {duplicate_number} duplicates"
+    );
+}
+
+// results of spiderMany. it's an iterator that returns results in the order
+// that they are ready. next blocks until next result is ready.
+#[pyclass]
+pub struct SpiderStream {
+    receiver: mpsc::Receiver<SpiderItem>,
+}
+
+#[pymethods]
+impl SpiderStream {
+    fn __iter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    fn __next__(mut slf: PyRefMut<'_, Self>, py: Python<'_>) -> Option<SpiderItem> {
+        let receiver = &mut slf.receiver;
+        py.allow_threads(move || receiver.recv().ok())
+    }
+}
+
+// spiderMany checks a list of add_link_dicts like spider, but concurrently.
+// links that are in addlink_db_table or repeated in the list are not checked.
+// it returns an iterator of dictionaries with index (position in add_link_dicts),
+// link, duplicate, result (same as spider) and error.
+#[pyfunction]
+#[pyo3(signature = (database, add_link_dicts, parallel=8, per_host=2))]
+pub fn spiderMany(
+    database: PyRef<DataBase>,
+    add_link_dicts: Vec<HashMap<String, String>>,
+    parallel: usize,
+    per_host: usize,
+) -> Result<SpiderStream, GhermezError> {
+    let links: Vec<String> = add_link_dicts
+        .iter()
+        .map(|add_link_dict| add_link_dict.get("link").cloned().unwrap_or_default())
+        .collect();
+    let duplicates = duplicateLinks(&links, |link| database.searchLinkInAddLinkTable(link))?;
+
+    let (sender, receiver) = mpsc::channel();
+    RUNTIME.spawn(spiderBatch(
+        add_link_dicts,
+        duplicates,
+        parallel,
+        per_host,
+        sender,
+    ));
+    Ok(SpiderStream { receiver })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{convert::Infallible, net::SocketAddr};
+
+    use hyper::{
+        service::{make_service_fn, service_fn},
+        Body, Method, Request, Response as HyperResponse, Server,
+    };
+
+    use super::*;
+
+    #[test]
+    fn content_disposition_file_name() {
+        assert_eq!(
+            dispositionFileName("attachment; filename=\"a; b.txt\""),
+            Some("a; b.txt".to_string())
+        );
+        assert_eq!(
+            dispositionFileName("attachment; filename=plain.zip"),
+            Some("plain.zip".to_string())
+        );
+        assert_eq!(
+            dispositionFileName(
+                "attachment; filename=\"fallback.txt\"; filename*=UTF-8''na%C3%AFve%20file.txt"
+            ),
+            Some("naïve file.txt".to_string())
+        );
+        assert_eq!(
+            dispositionFileName("attachment; filename*=iso-8859-1'en'%E9t%E9.txt"),
+            Some("été.txt".to_string())
+        );
+        assert_eq!(
+            dispositionFileName("attachment; filename=\"..\\\\..\\\\evil.exe\""),
+            Some("evil.exe".to_string())
+        );
+        assert_eq!(dispositionFileName("inline"), None);
+    }
+
+    #[test]
+    fn file_name_from_link() {
+        assert_eq!(
+            linkFileName("http://a.com/dl/my%20file.zip?x=1#y"),
+            Some("my file.zip".to_string())
+        );
+        assert_eq!(linkFileName("http://a.com/"), None);
+        assert_eq!(linkFileName("http://a.com"), None);
+        assert_eq!(contentRangeSize("bytes 0-0/5000"), Some(5000));
+    }
+
+    async fn handle(request: Request<Body>) -> Result<HyperResponse<Body>, Infallible> {
+        let response = HyperResponse::builder();
+        let response = match (request.method(), request.uri().path()) {
+            (_, "/redirect") => response
+                .status(302)
+                .header("Location", "/file")
+                .body(Body::empty()),
+            (&Method::HEAD, "/file") => response
+                .header("Content-Length", "1000")
+                .header("Accept-Ranges", "bytes")
+                .header("Content-Type", "application/zip; charset=binary")
+                .header(
+                    "Content-Disposition",
+                    "attachment; filename*=UTF-8''na%C3%AFve.zip",
+                )
+                .body(Body::empty()),
+            // this server refuses HEAD
+            (&Method::HEAD, "/dl/no-head") => response.status(405).body(Body::empty()),
+            (&Method::GET, "/dl/no-head") => {
+                assert_eq!(request.headers()["range"], "bytes=0-0");
+                assert_eq!(request.headers()["referer"], "http://referer.com");
+                response
+                    .status(206)
+                    .header("Content-Range", "bytes 0-0/5000")
+                    .body(Body::from("x"))
+            }
+            _ => response.status(404).body(Body::empty()),
+        };
+        Ok(response.unwrap())
+    }
+
+    async fn testServer() -> SocketAddr {
+        let make_service = make_service_fn(|_| async { Ok::<_, Infallible>(service_fn(handle)) });
+        let server = Server::bind(&SocketAddr::from(([127, 0, 0, 1], 0))).serve(make_service);
+        let address = server.local_addr();
+        tokio::spawn(server);
+        address
+    }
+
+    fn addLinkDict(link: String) -> HashMap<String, String> {
+        HashMap::from([
+            ("link".to_string(), link),
+            ("referer".to_string(), "http://referer.com".to_string()),
+            ("ip".to_string(), "None".to_string()),
+            ("out".to_string(), "None".to_string()),
+        ])
+    }
+
+    #[test]
+    fn duplicate_links() {
+        let links: Vec<String> = ["a", "b", "a", "c"].iter().map(|x| x.to_string()).collect();
+        let duplicates = duplicateLinks(&links, |link| Ok::<_, ()>(link == "c")).unwrap();
+        assert_eq!(duplicates, vec![false, false, true, true]);
+    }
+
+    #[tokio::test]
+    async fn spider_many_streams_results() {
+        let address = testServer().await;
+        let add_link_dicts = vec![
+            addLinkDict(format!("http://{address}/file")),
+            addLinkDict(format!("http://{address}/dl/no-head")),
+            addLinkDict(format!("http://{address}/file")),
+        ];
+        let (sender, receiver) = mpsc::channel();
+        spiderBatch(add_link_dicts, vec![false, false, true], 2, 1, sender).await;
+
+        let mut items: Vec<SpiderItem> = receiver.iter().collect();
+        items.sort_by_key(|item| item.index);
+        assert_eq!(items.len(), 3);
+        assert_eq!(
+            items[0].result.as_ref().unwrap().file_name,
+            Some("naïve.zip".to_string())
+        );
+        assert_eq!(items[1].result.as_ref().unwrap().size, Some(5000));
+        assert!(items[2].duplicate);
+        assert!(items[2].result.is_none());
+    }
+
+    #[tokio::test]
+    async fn spider_follows_redirects() {
+        let address = testServer().await;
+        let result = asyncSpider(&addLinkDict(format!("http://{address}/redirect")))
+            .await
+            .unwrap();
+        assert_eq!(
+            result,
+            SpiderResult {
+                link: format!("http://{address}/file"),
+                status: 200,
+                file_name: Some("naïve.zip".to_string()),
+                server_file_name: Some("naïve.zip".to_string()),
+                size: Some(1000),
+                resumable: true,
+                content_type: Some("application/zip".to_string()),
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn spider_falls_back_to_range_request() {
+        let address = testServer().await;
+        let result = asyncSpider(&addLinkDict(format!("http://{address}/dl/no-head")))
+            .await
+            .unwrap();
+        assert_eq!(result.status, 206);
+        assert_eq!(result.size, Some(5000));
+        assert!(result.resumable);
+        assert_eq!(result.file_name, Some("no-head".to_string()));
+        assert_eq!(result.server_file_name, None);
+
+        let mut add_link_dict = addLinkDict(format!("http://{address}/missing"));
+        add_link_dict.insert("out".to_string(), "chosen.bin".to_string());
+        let result = asyncSpider(&add_link_dict).await.unwrap();
+        assert_eq!(result.status, 404);
+        assert_eq!(result.size, None);
+        assert_eq!(result.file_name, Some("chosen.bin".to_string()));
+    }
+}
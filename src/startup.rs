@@ -17,6 +17,8 @@ use std::process::Command;
 
 use pyo3::prelude::*;
 
+use crate::error::GhermezError;
+
 #[cfg(target_os = "windows")]
 use winreg::enums::*;
 #[cfg(target_os = "windows")]
@@ -31,12 +33,12 @@ pub fn checkstartup() -> bool {
     // check if the startup exists
     #[cfg(any(target_os = "linux", target_os = "freebsd", target_os = "openbsd"))]
     return HOME_ADDRESS
-        .join("/.config/autostart/ghermez.desktop")
+        .join(".config/autostart/ghermez.desktop")
         .is_file();
 
     #[cfg(target_os = "macos")]
     return HOME_ADDRESS
-        .join("/Library/LaunchAgents/com.ghermez.plist")
+        .join("Library/LaunchAgents/com.ghermez.plist")
         .is_file();
 
     #[cfg(target_os = "windows")]
@@ -53,7 +55,7 @@ pub fn checkstartup() -> bool {
 }
 
 #[pyfunction]
-pub fn addstartup() {
+pub fn addstartup() -> Result<(), GhermezError> {
     #[cfg(any(target_os = "linux", target_os = "freebsd", target_os = "openbsd"))]
     {
         let entry = "[Desktop Entry]
@@ -73,7 +75,7 @@ pub fn addstartup() {
     ";
         let autostart_dir = HOME_ADDRESS.join(".config").join("autostart");
         if !autostart_dir.exists() {
-            fs::create_dir_all(&autostart_dir).unwrap();
+            fs::create_dir_all(&autostart_dir)?;
             let _ = fs::set_permissions(&autostart_dir, fs::Permissions::from_mode(0o755));
         }
         let desktop_file_path = autostart_dir.join("ghermez.desktop");
@@ -83,7 +85,7 @@ pub fn addstartup() {
 
     #[cfg(target_os = "macos")]
     {
-        let cwd = env::current_dir().unwrap();
+        let cwd = env::current_dir()?;
         let entry = format!("<?xml version=\"1.0\" encoding=\"UTF-8\"?>
     <!DOCTYPE plist PUBLIC \"-//Apple//DTD PLIST 1.0//EN\" \"http://www.apple.com/DTDs/PropertyList-1.0.dtd\">
     <plist version=\"1.0\">
@@ -100,44 +102,44 @@ pub fn addstartup() {
       <true/>
     </dict>
     </plist>\n", cwd.display());
-        let startup_file_path = HOME_ADDRESS.join("/Library/LaunchAgents/com.ghermez.plist");
-        fs::write(&startup_file_path, entry).unwrap();
+        let startup_file_path = HOME_ADDRESS.join("Library/LaunchAgents/com.ghermez.plist");
+        fs::write(&startup_file_path, entry)?;
         Command::new("launchctl")
-            .args(["load", startup_file_path.to_str().unwrap()])
-            .spawn()
-            .unwrap();
+            .arg("load")
+            .arg(&startup_file_path)
+            .spawn()?;
     }
 
     #[cfg(target_os = "windows")]
     {
-        let key = RegKey::predef(HKEY_CURRENT_USER)
-            .open_subkey_with_flags(
-                "Software\\Microsoft\\Windows\\CurrentVersion\\Run",
-                KEY_ALL_ACCESS,
-            )
-            .unwrap();
+        let key = RegKey::predef(HKEY_CURRENT_USER).open_subkey_with_flags(
+            "Software\\Microsoft\\Windows\\CurrentVersion\\Run",
+            KEY_ALL_ACCESS,
+        )?;
 
-        let cwd = env::current_dir().unwrap();
+        let cwd = env::current_dir()?;
         let ghermez_exe_tray =
             format!("\"{}\\Ghermez Download Manager.exe\" --tray", cwd.display());
 
         let _ = key.set_value("ghermez", &ghermez_exe_tray);
     }
+
+    Ok(())
 }
 
 #[pyfunction]
-pub fn removestartup() {
+pub fn removestartup() -> Result<(), GhermezError> {
     #[cfg(any(target_os = "linux", target_os = "freebsd", target_os = "openbsd"))]
-    fs::remove_file(HOME_ADDRESS.join("/.config/autostart/ghermez.desktop")).unwrap();
+    fs::remove_file(HOME_ADDRESS.join(".config/autostart/ghermez.desktop"))?;
 
     #[cfg(target_os = "macos")]
     {
         if checkstartup() {
-            let startup_file_path = HOME_ADDRESS.join("/Library/LaunchAgents/com.ghermez.plist");
+            let startup_file_path = HOME_ADDRESS.join("Library/LaunchAgents/com.ghermez.plist");
             Command::new("launchctl")
-                .args(["unload", startup_file_path.to_str().unwrap()])
-                .status()
-                .unwrap();
+                .arg("unload")
+                .arg(&startup_file_path)
+                .status()?;
             let _ = fs::remove_file(startup_file_path);
         }
     }
@@ -145,13 +147,13 @@ pub fn removestartup() {
     #[cfg(target_os = "windows")]
     {
         if checkstartup() {
-            let key = RegKey::predef(HKEY_CURRENT_USER)
-                .open_subkey_with_flags(
-                    "Software\\Microsoft\\Windows\\CurrentVersion\\Run",
-                    KEY_ALL_ACCESS,
-                )
-                .unwrap();
+            let key = RegKey::predef(HKEY_CURRENT_USER).open_subkey_with_flags(
+                "Software\\Microsoft\\Windows\\CurrentVersion\\Run",
+                KEY_ALL_ACCESS,
+            )?;
             let _ = key.delete_value("ghermez");
         }
     }
+
+    Ok(())
 }
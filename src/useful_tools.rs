@@ -6,6 +6,7 @@ use std::{collections::HashMap, path::PathBuf};
 #[cfg(target_os = "linux")]
 use std::{env, fs, path::Path};
 
+use chrono::{Local, NaiveDateTime, TimeZone};
 use home::home_dir;
 #[cfg(not(target_os = "windows"))]
 use log::error;
@@ -54,10 +55,7 @@ pub fn determineConfigFolder() -> PathBuf {
 pub fn osAndDesktopEnvironment() -> (&'static str, Option<String>) {
     #[cfg(any(target_os = "linux", target_os = "freebsd", target_os = "openbsd"))]
     {
-        let desktop_env = match env::var("XDG_CURRENT_DESKTOP") {
-            Ok(val) => Some(val),
-            Err(_) => None,
-        };
+        let desktop_env = env::var("XDG_CURRENT_DESKTOP").ok();
         (OS_TYPE, desktop_env)
     }
 
@@ -134,6 +132,53 @@ pub fn round(x: f32, decimals: u32) -> f32 {
     (x * y).round() / y
 }
 
+// this function converts human readable size like "1.5 GiB", "512 KB" or speed like
+// "100 KiB/s" to byte. plain numbers are byte too. KB, MB and GB of old versions
+// are 1024 based. None is returned for unknown sizes like "***".
+pub fn sizeInBytes(size: &str) -> Option<i64> {
+    let size = size.trim();
+    let size = size.strip_suffix("/s").unwrap_or(size).trim();
+    let number_end = size
+        .find(|x: char| !(x.is_ascii_digit() || x == '.'))
+        .unwrap_or(size.len());
+    let value: f64 = size[..number_end].parse().ok()?;
+    let power = match size[number_end..].trim().to_uppercase().as_str() {
+        "" | "B" => 0,
+        "K" | "KB" | "KIB" => 1,
+        "M" | "MB" | "MIB" => 2,
+        "G" | "GB" | "GIB" => 3,
+        "T" | "TB" | "TIB" => 4,
+        _ => return None,
+    };
+    Some((value * 1024f64.powi(power)).round() as i64)
+}
+
+// format of dates in data base of old versions and in download table,
+// for example >> 2017/09/09 , 13:12:26
+pub(crate) const DATE_FORMAT: &str = "%Y/%m/%d , %H:%M:%S";
+
+// this function converts date in DATE_FORMAT and local time to unix time.
+// plain numbers are unix time.
+pub fn dateToTimestamp(date: &str) -> Option<i64> {
+    let date = date.trim();
+    if let Ok(timestamp) = date.parse() {
+        return Some(timestamp);
+    }
+    let date = NaiveDateTime::parse_from_str(date, DATE_FORMAT).ok()?;
+    Local
+        .from_local_datetime(&date)
+        .earliest()
+        .map(|date| date.timestamp())
+}
+
+// this function converts unix time to date in DATE_FORMAT and local time
+pub fn timestampToDate(timestamp: i64) -> Option<String> {
+    Local
+        .timestamp_opt(timestamp, 0)
+        .single()
+        .map(|date| date.format(DATE_FORMAT).to_string())
+}
+
 #[cfg(not(target_os = "windows"))]
 #[pyfunction]
 pub fn freeSpace(directory: &str) -> Option<u64> {
@@ -274,6 +319,8 @@ pub fn returnDefaultSettings(_available_styles: Vec<&str>) -> HashMap<&str, Stri
         ("show-menubar", "no".to_string()),
         ("show-sidepanel", "yes".to_string()),
         ("rpc-port", "6801".to_string()),
+        // empty rpc-secret means a random secret for every session
+        ("rpc-secret", "".to_string()),
         ("notification", "Native notification".to_string()),
         ("after-dialog", "yes".to_string()),
         ("tray-icon", "yes".to_string()),
@@ -282,6 +329,9 @@ pub fn returnDefaultSettings(_available_styles: Vec<&str>) -> HashMap<&str, Stri
         ("max-tries", "5".to_string()),
         ("retry-wait", "0".to_string()),
         ("timeout", "60".to_string()),
+        ("max-active-downloads", "5".to_string()),
+        // 0 means no limit
+        ("downloads-per-host", "0".to_string()),
         ("connections", "16".to_string()),
         (
             "download_path_temp",
@@ -328,3 +378,25 @@ pub fn returnDefaultSettings(_available_styles: Vec<&str>) -> HashMap<&str, Stri
     ]);
     default_setting_dict
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sizes_and_dates_are_converted() {
+        assert_eq!(sizeInBytes("1.5 GiB"), Some(1610612736));
+        assert_eq!(sizeInBytes("512 KB"), Some(524288));
+        assert_eq!(sizeInBytes("100 KiB/s"), Some(102400));
+        assert_eq!(sizeInBytes("10M"), Some(10485760));
+        assert_eq!(sizeInBytes("700 B"), Some(700));
+        assert_eq!(sizeInBytes("4096"), Some(4096));
+        assert_eq!(sizeInBytes("***"), None);
+        assert_eq!(sizeInBytes("5 parsecs"), None);
+
+        let timestamp = dateToTimestamp("2017/09/09 , 13:12:26").unwrap();
+        assert_eq!(timestampToDate(timestamp).unwrap(), "2017/09/09 , 13:12:26");
+        assert_eq!(dateToTimestamp(&timestamp.to_string()), Some(timestamp));
+        assert_eq!(dateToTimestamp("yesterday"), None);
+    }
+}
@@ -0,0 +1,88 @@
+-- ghermez.db of persepolis 3.2 (python data_base.py), PRAGMA user_version = 0.
+-- sizes use KB/MB/GB, items of categories are in gid_list and headers are "name=value; ..." strings.
+CREATE TABLE category_db_table(
+    category TEXT PRIMARY KEY,
+    start_time_enable TEXT,
+    start_time TEXT,
+    end_time_enable TEXT,
+    end_time TEXT,
+    reverse TEXT,
+    limit_enable TEXT,
+    limit_value TEXT,
+    after_download TEXT,
+    gid_list TEXT
+);
+CREATE TABLE download_db_table(
+    file_name TEXT,
+    status TEXT,
+    size TEXT,
+    downloaded_size TEXT,
+    percent TEXT,
+    connections TEXT,
+    rate TEXT,
+    estimate_time_left TEXT,
+    gid TEXT PRIMARY KEY,
+    link TEXT,
+    first_try_date TEXT,
+    last_try_date TEXT,
+    category TEXT,
+    FOREIGN KEY(category) REFERENCES category_db_table(category)
+    ON UPDATE CASCADE
+    ON DELETE CASCADE
+);
+CREATE TABLE addlink_db_table(
+    ID INTEGER PRIMARY KEY,
+    gid TEXT,
+    out TEXT,
+    start_time TEXT,
+    end_time TEXT,
+    link TEXT,
+    ip TEXT,
+    port TEXT,
+    proxy_user TEXT,
+    proxy_passwd TEXT,
+    download_user TEXT,
+    download_passwd TEXT,
+    connections TEXT,
+    limit_value TEXT,
+    download_path TEXT,
+    referer TEXT,
+    load_cookies TEXT,
+    user_agent TEXT,
+    header TEXT,
+    after_download TEXT,
+    FOREIGN KEY(gid) REFERENCES download_db_table(gid)
+    ON UPDATE CASCADE
+    ON DELETE CASCADE
+);
+CREATE TABLE video_finder_db_table(
+    ID INTEGER PRIMARY KEY,
+    video_gid TEXT,
+    audio_gid TEXT,
+    video_completed TEXT,
+    audio_completed TEXT,
+    muxing_status TEXT,
+    checking TEXT,
+    download_path TEXT,
+    FOREIGN KEY(video_gid) REFERENCES download_db_table(gid)
+    ON DELETE CASCADE,
+    FOREIGN KEY(audio_gid) REFERENCES download_db_table(gid)
+    ON DELETE CASCADE
+);
+
+INSERT INTO category_db_table VALUES
+    ('All Downloads', 'no', '0:0', 'no', '0:0', 'no', 'no', '0K', 'no', '[''5a1b2c3d4e5f6071'', ''6b2c3d4e5f607182'']'),
+    ('Single Downloads', 'no', '0:0', 'no', '0:0', 'no', 'no', '0K', 'no', '[''5a1b2c3d4e5f6071'']'),
+    ('Scheduled Downloads', 'no', '0:0', 'no', '0:0', 'no', 'no', '0K', 'no', '[''6b2c3d4e5f607182'']');
+
+INSERT INTO download_db_table VALUES
+    ('ubuntu.iso', 'complete', '1.5 GB', '1.5 GB', '100%', '0', '0', '0', '5a1b2c3d4e5f6071',
+     'http://example.com/ubuntu.iso', '2020/01/01 , 10:00:00', '2020/01/01 , 10:30:00', 'Single Downloads'),
+    ('music.mp3', 'stopped', '8 MB', '512 KB', '6%', '0', '0', '0', '6b2c3d4e5f607182',
+     'http://example.com/music.mp3', '2020/01/02 , 10:00:00', '2020/01/02 , 10:01:00', 'Scheduled Downloads');
+
+INSERT INTO addlink_db_table VALUES
+    (1, '5a1b2c3d4e5f6071', NULL, NULL, NULL, 'http://example.com/ubuntu.iso', NULL, NULL, NULL, NULL,
+     NULL, NULL, '16', '0', '/home/user/Downloads', NULL, NULL, NULL, 'X-Token=a=b; Accept=*/*', NULL),
+    (2, '6b2c3d4e5f607182', NULL, '1:00', NULL, 'http://example.com/music.mp3', NULL, NULL, NULL, NULL,
+     NULL, NULL, '16', '0', '/home/user/Downloads', NULL, NULL, NULL, NULL, NULL);